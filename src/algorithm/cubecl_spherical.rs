@@ -24,7 +24,7 @@ fn distance_to_scalar<F: Float>(from_lat: F, from_lon: F, to_lat: F, to_lon: F)
 
     let mut δλ = (to_lon - from_lon) * PI / F::new(180.0);
     if F::abs(δλ) > PI {
-        if δλ > 0.0 {
+        if δλ > F::new(0.0) {
             δλ = δλ - TAU
         } else {
             δλ = TAU + δλ
@@ -38,8 +38,8 @@ fn distance_to_scalar<F: Float>(from_lat: F, from_lon: F, to_lat: F, to_lon: F)
 
     //distance
     let mut q = δφ / δψ;
-    if F::abs(δψ) <= F::new(10e-12) {
-        q = F::cos(φ1)
+    if F::abs(δφ) <= F::new(1e-3) {
+        q = F::cos((φ1 + φ2) / F::new(2.0))
     }
 
     let δ = F::sqrt(δφ * δφ + q*q* δλ * δλ);
@@ -47,3 +47,125 @@ fn distance_to_scalar<F: Float>(from_lat: F, from_lon: F, to_lat: F, to_lon: F)
 
     d
 }
+
+#[cube(launch_unchecked)]
+pub(crate) fn heading_to_array<F: Float>(from_lat: &Array<F>, from_lon: &Array<F>, to_lat: &Array<F>, to_lon: &Array<F>, output: &mut Array<F>) {
+    if ABSOLUTE_POS < from_lat.len() {
+        output[ABSOLUTE_POS] = heading_to_scalar::<F>(from_lat[ABSOLUTE_POS], from_lon[ABSOLUTE_POS], to_lat[ABSOLUTE_POS], to_lon[ABSOLUTE_POS]);
+    }
+}
+
+#[cube]
+fn heading_to_scalar<F: Float>(from_lat: F, from_lon: F, to_lat: F, to_lon: F) -> F {
+    let PI = F::new(3.14159265358979323846264338327950288);
+    let TAU = F::new(6.28318530717958647692528676655900577);
+    let FRAC_PI_4 = F::new(0.785398163397448309615660845819875721);
+
+    let φ1 = from_lat * PI / F::new(180.0);
+    let φ2 = to_lat * PI / F::new(180.0);
+
+    let mut δλ = (to_lon - from_lon) * PI / F::new(180.0);
+    if F::abs(δλ) > PI {
+        if δλ > F::new(0.0) {
+            δλ = δλ - TAU
+        } else {
+            δλ = TAU + δλ
+        }
+    }
+
+    let δψ = F::log(
+        (F::sin(φ2/F::new(2.0)+FRAC_PI_4) / (F::cos(φ2/F::new(2.0)+FRAC_PI_4))) /
+        (F::sin(φ1/F::new(2.0)+FRAC_PI_4) / F::cos(φ1/F::new(2.0)+FRAC_PI_4))
+    );
+
+    //heading
+    let θ = atan2_scalar::<F>(δλ, δψ);
+
+    let mut b = θ * F::new(180.0) / PI;
+    if b < F::new(0.0) {
+        b = b + F::new(360.0)
+    }
+
+    b
+}
+
+#[cube(launch_unchecked)]
+pub(crate) fn destination_array<F: Float>(from_lat: &Array<F>, from_lon: &Array<F>, heading: &Array<F>, distance: &Array<F>, to_lat: &mut Array<F>, to_lon: &mut Array<F>) {
+    if ABSOLUTE_POS < from_lat.len() {
+        destination_scalar::<F>(from_lat[ABSOLUTE_POS], from_lon[ABSOLUTE_POS], heading[ABSOLUTE_POS], distance[ABSOLUTE_POS], to_lat, to_lon, ABSOLUTE_POS);
+    }
+}
+
+#[cube]
+fn destination_scalar<F: Float>(from_lat: F, from_lon: F, heading: F, distance: F, to_lat: &mut Array<F>, to_lon: &mut Array<F>, index: UInt) {
+    let mean_earth_radius = F::new(6371008.8);
+    let PI = F::new(3.14159265358979323846264338327950288);
+    let FRAC_PI_2 = F::new(1.57079632679489661923132169163975144);
+    let FRAC_PI_4 = F::new(0.785398163397448309615660845819875721);
+
+    let φ1 = from_lat * PI / F::new(180.0);
+    let λ1 = from_lon * PI / F::new(180.0);
+    let θ = heading * PI / F::new(180.0);
+
+    let δ = distance / mean_earth_radius;
+
+    let δφ = δ * F::cos(θ);
+    let mut φ2 = φ1 + δφ;
+
+    if F::abs(φ2) > FRAC_PI_2 {
+        if φ2 > F::new(0.0) {
+            φ2 = PI - φ2
+        } else {
+            φ2 = F::new(0.0) - PI - φ2
+        }
+    }
+
+    let δψ = F::log(
+        (F::sin(φ2/F::new(2.0)+FRAC_PI_4) / (F::cos(φ2/F::new(2.0)+FRAC_PI_4))) /
+        (F::sin(φ1/F::new(2.0)+FRAC_PI_4) / F::cos(φ1/F::new(2.0)+FRAC_PI_4))
+    );
+
+    // δφ / δψ loses precision in f32 on courses close to east or west, where it tends to the cosine of the mean latitude
+    let mut q = δφ / δψ;
+    if F::abs(δφ) <= F::new(1e-3) {
+        q = F::cos((φ1 + φ2) / F::new(2.0))
+    }
+
+    let δλ = δ * F::sin(θ) / q;
+    let λ2 = λ1 + δλ;
+
+    to_lat[index] = φ2 * F::new(180.0) / PI;
+    to_lon[index] = λ2 * F::new(180.0) / PI;
+}
+
+/// cubecl has no inverse trigonometry, atan is approximated by a minimax polynomial on [0, 1]
+#[cube]
+fn atan2_scalar<F: Float>(y: F, x: F) -> F {
+    let PI = F::new(3.14159265358979323846264338327950288);
+    let FRAC_PI_2 = F::new(1.57079632679489661923132169163975144);
+
+    let ax = F::abs(x);
+    let ay = F::abs(y);
+
+    let mut a = F::new(0.0);
+    if ax > ay {
+        a = ay / ax;
+    } else if ay > F::new(0.0) {
+        a = ax / ay;
+    }
+
+    let s = a * a;
+    let mut r = a * (F::new(0.99997726) + s * (F::new(-0.33262347) + s * (F::new(0.19354346) + s * (F::new(-0.11643287) + s * (F::new(0.05265332) + s * F::new(-0.01172120))))));
+
+    if ay > ax {
+        r = FRAC_PI_2 - r;
+    }
+    if x < F::new(0.0) {
+        r = PI - r;
+    }
+    if y < F::new(0.0) {
+        r = F::new(0.0) - r;
+    }
+
+    r
+}
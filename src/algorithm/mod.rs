@@ -1,7 +1,12 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
 use crate::position::Coords;
 use crate::utils::{Distance, DistanceUnit};
 
 pub(crate) mod spherical;
+pub(crate) mod great_circle;
 pub(crate) mod cubecl_spherical;
 
 const MEAN_EARTH_RADIUS: Distance = Distance {
@@ -19,6 +24,55 @@ pub(crate) trait Algorithm {
     fn destination(&self, from : &Coords, heading: f64, distance: &Distance) -> Coords;
 
     fn intersection(&self, line: (&Coords, &Coords), from: &Coords, heading: f64) -> Option<Coords>;
+
+    fn cross_track_distance(&self, path: (&Coords, &Coords), point: &Coords) -> Distance;
+
+    fn along_track_distance(&self, path: (&Coords, &Coords), point: &Coords) -> Distance;
+}
+
+/// Brings a longitude, in radians, into [-π, π).
+pub(crate) fn wrap_lon(λ: f64) -> f64 {
+    (λ + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Angular cross-track and along-track distances (radians) of `point` relative to the great circle `path`.
+/// Cross-track is negative on the left of the path, along-track is negative behind its origin.
+pub(crate) fn track_angles(path: (&Coords, &Coords), point: &Coords) -> (f64, f64) {
+    let (φ1, λ1) = (path.0.lat.to_radians(), path.0.lon.to_radians());
+    let (φ2, λ2) = (path.1.lat.to_radians(), path.1.lon.to_radians());
+    let (φ3, λ3) = (point.lat.to_radians(), point.lon.to_radians());
+
+    // angular distance path origin - point
+    let (δφ, δλ) = (φ3 - φ1, λ3 - λ1);
+    let a = (δφ/2.0).sin() * (δφ/2.0).sin() + φ1.cos() * φ3.cos() * (δλ/2.0).sin() * (δλ/2.0).sin();
+    let δ13 = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    // initial bearings from path origin
+    let θ13 = ((λ3 - λ1).sin() * φ3.cos()).atan2(φ1.cos() * φ3.sin() - φ1.sin() * φ3.cos() * (λ3 - λ1).cos());
+    let θ12 = ((λ2 - λ1).sin() * φ2.cos()).atan2(φ1.cos() * φ2.sin() - φ1.sin() * φ2.cos() * (λ2 - λ1).cos());
+
+    let δxt = (δ13.sin() * (θ13 - θ12).sin()).max(-1.0).min(1.0).asin();
+    let δat = (δ13.cos() / δxt.cos()).max(-1.0).min(1.0).acos();
+
+    (δxt, δat * (θ12 - θ13).cos().signum())
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Tsify)]
+#[serde(rename_all = "snake_case")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum AlgorithmKind {
+    #[default]
+    Spherical,
+    GreatCircle,
+}
+
+impl AlgorithmKind {
+    pub(crate) fn algorithm(&self) -> Box<dyn Algorithm> {
+        match self {
+            AlgorithmKind::Spherical => Box::new(spherical::Spherical{}),
+            AlgorithmKind::GreatCircle => Box::new(great_circle::GreatCircle{}),
+        }
+    }
 }
 
 trait Utils {
@@ -1,6 +1,7 @@
 use std::f64::consts;
 use std::f64::consts::PI;
-use crate::algorithm::{Algorithm, Distance, MEAN_EARTH_RADIUS, Utils};
+use crate::algorithm::great_circle::GreatCircle;
+use crate::algorithm::{track_angles, wrap_lon, Algorithm, Distance, MEAN_EARTH_RADIUS, Utils};
 use crate::position::Coords;
 
 pub(crate) struct Spherical {
@@ -130,15 +131,17 @@ impl Algorithm for Spherical {
         // see www.edwilliams.org/avform.htm#Intersection
 
         let p1 = line.0;
-        let brng1 = self.heading_to(line.0, line.1);
+        // initial bearing of the great circle through the line, the rhumb one leading elsewhere off the equator
+        let brng1 = GreatCircle {}.heading_to(line.0, line.1);
 
         let (φ1, λ1) = (p1.lat.to_radians(), p1.lon.to_radians());
-        let (φ2, λ2) = (p2.lat.to_radians(), p2.lon.to_radians());
+        // unwrapped so that p1-p2 never spans more than half the globe, across the antimeridian
+        let (φ2, λ2) = (p2.lat.to_radians(), λ1 + wrap_lon(p2.lon.to_radians() - λ1));
         let (θ13, θ23) = (brng1.to_radians(), brng2.to_radians());
         let (δφ, δλ) = (φ2 - φ1, λ2 - λ1);
 
         // angular distance p1-p2
-        let δ12 = 2.0 * (((δφ /2.0).sin() * (δφ /2.0).sin() + φ1.cos() * φ2.cos()).sqrt() * (δλ /2.0).sin() * (δλ /2.0).sin()).asin();
+        let δ12 = 2.0 * ((δφ /2.0).sin() * (δφ /2.0).sin() + φ1.cos() * φ2.cos() * (δλ /2.0).sin() * (δλ /2.0).sin()).sqrt().asin();
         if δ12.abs() < f64::EPSILON {
             return Some(p1.clone()); // coincident points
         }
@@ -168,11 +171,40 @@ impl Algorithm for Spherical {
         let φ3 = (φ1.sin()*δ13.cos() + φ1.cos()*δ13.sin()*θ13.cos()).max(-1.0).min(1.0).asin();
 
         let δλ13 = (θ13.sin()*δ13.sin()*φ1.cos()).atan2(δ13.cos() - φ1.sin()*φ3.sin());
-        let λ3 = λ1 + δλ13;
+        let λ3 = wrap_lon(λ1 + δλ13);
 
         Some(Coords {
             lat: φ3.to_degrees(),
             lon: λ3.to_degrees()
         })
     }
-}
\ No newline at end of file
+
+    fn cross_track_distance(&self, path: (&Coords, &Coords), point: &Coords) -> Distance {
+        let (δxt, _) = track_angles(path, point);
+
+        MEAN_EARTH_RADIUS * δxt
+    }
+
+    fn along_track_distance(&self, path: (&Coords, &Coords), point: &Coords) -> Distance {
+        let (_, δat) = track_angles(path, point);
+
+        MEAN_EARTH_RADIUS * δat
+    }
+}
+
+impl Spherical {
+    /// `distance_to` of each pair, computed in parallel with the `rayon` feature.
+    pub(crate) fn distance_to_many(&self, pairs: &[(Coords, Coords)]) -> Vec<Distance> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            pairs.par_iter().map(|(from, to)| self.distance_to(from, to)).collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            pairs.iter().map(|(from, to)| self.distance_to(from, to)).collect()
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Entry points for the benchmark-style tests of `tests/`, which only see the public api.
+
+use std::sync::Arc;
+
+use crate::position::{BoatSettings, BoatStatus, Coords, Penalties};
+use crate::router::echeneis::{Alternative, NavDuration, Position};
+use crate::utils::{Distance, Speed};
+use crate::wind::Wind;
+
+/// Builds `count` alternatives of a single position each, returning their merge to be run apart.
+///
+/// The merge gives the farthest distance from the start kept, in nm.
+pub fn merging_alternatives(count: usize) -> impl FnOnce() -> f64 {
+    let status = BoatStatus {
+        aground: false,
+        boat_speed: Speed::from_kts(0.0),
+        wind: Wind { direction: 0.0, speed: Default::default(), gust: None },
+        foil: 0,
+        boost: 0,
+        best_ratio: 0.0,
+        ratio: 0,
+        vmgs: None,
+        penalties: Penalties::default(),
+        stamina: 100.0,
+    };
+
+    let src = Position {
+        az: 0,
+        point: Coords { lat: 46.0, lon: -5.0 },
+        from_dist: Distance::zero(),
+        dist_to: Distance::zero(),
+        duration: NavDuration::zero(),
+        distance: Distance::zero(),
+        reached: None,
+        settings: BoatSettings::default(),
+        status: Arc::new(status),
+        previous: None,
+        is_in_ice_limits: false,
+        remaining_penalties: Penalties::default(),
+        remaining_stamina: 100.0,
+    };
+
+    let alternatives = (0..count).map(|i| {
+        let mut alternative = Alternative::empty();
+        alternative.merge_fast(Position {
+            from_dist: Distance::from_nm(i as f64 % 50.0),
+            reached: Some("buoy".to_string()),
+            previous: Some(Arc::new(src.clone())),
+            ..src.clone()
+        });
+        alternative
+    }).collect::<Vec<_>>();
+
+    move || {
+        let mut merged = Alternative::empty();
+        for alternative in alternatives {
+            merged.merge_all(alternative);
+        }
+
+        // through the kept positions, `best` cloning the one it returns
+        (0..8).filter_map(|sail| merged.get(sail)).map(|pos| pos.from_dist.nm()).fold(0.0, f64::max)
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::position::Coords;
+use crate::utils::{Distance, Speed};
+
+/// Surface currents : the direction the water flows to, in degrees, and its speed.
+pub(crate) trait CurrentProvider {
+    fn current(&self, m: &DateTime<Utc>, point: &Coords) -> (f64, Speed);
+}
+
+pub(crate) struct NoCurrent;
+
+impl CurrentProvider for NoCurrent {
+    fn current(&self, _m: &DateTime<Utc>, _point: &Coords) -> (f64, Speed) {
+        (0.0, Speed::from_kts(0.0))
+    }
+}
+
+/// Same current everywhere, at any time.
+pub(crate) struct ConstantCurrent {
+    pub(crate) direction: f64,
+    pub(crate) speed: Speed,
+}
+
+impl CurrentProvider for ConstantCurrent {
+    fn current(&self, _m: &DateTime<Utc>, _point: &Coords) -> (f64, Speed) {
+        (self.direction, self.speed.clone())
+    }
+}
+
+/// Currents of a provider at a given instant.
+pub(crate) struct InstantCurrent {
+    provider: Arc<dyn CurrentProvider + Send + Sync>,
+    m: DateTime<Utc>,
+}
+
+impl InstantCurrent {
+    pub(crate) fn new(provider: Arc<dyn CurrentProvider + Send + Sync>, m: DateTime<Utc>) -> Self {
+        Self { provider, m }
+    }
+
+    pub(crate) fn interpolate(&self, point: &Coords) -> (f64, Speed) {
+        self.provider.current(&self.m, point)
+    }
+}
+
+/// Vector sum of the boat's course through water and the current drift, as a (heading, distance) over ground.
+pub(crate) fn over_ground(heading: f64, distance: &Distance, current_direction: f64, drift: &Distance) -> (f64, Distance) {
+    let (heading, current_direction) = (heading.to_radians(), current_direction.to_radians());
+
+    let east = distance.m() * heading.sin() + drift.m() * current_direction.sin();
+    let north = distance.m() * heading.cos() + drift.m() * current_direction.cos();
+
+    (east.atan2(north).to_degrees().rem_euclid(360.0), Distance::from_m(east.hypot(north)))
+}
@@ -0,0 +1,67 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+/// Errors with a stable `code`, raised through `anyhow` and recovered with
+/// `downcast_ref` at the bindings boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhtError {
+    ProviderNotFound(String),
+    ProviderNotReady,
+    OutOfCoverage(String),
+    PolarNotFound(String),
+    RaceNotFound(String),
+    NavigationFailed(String),
+    Cancelled,
+}
+
+impl PhtError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PhtError::ProviderNotFound(_) => "provider_not_found",
+            PhtError::ProviderNotReady => "provider_not_ready",
+            PhtError::OutOfCoverage(_) => "out_of_coverage",
+            PhtError::PolarNotFound(_) => "polar_not_found",
+            PhtError::RaceNotFound(_) => "race_not_found",
+            PhtError::NavigationFailed(_) => "navigation_failed",
+            PhtError::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl Display for PhtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhtError::ProviderNotFound(name) => write!(f, "Provider {name} not found"),
+            PhtError::ProviderNotReady => write!(f, "Provider not ready : no forecast loaded"),
+            PhtError::OutOfCoverage(detail) => write!(f, "Out of coverage : {detail}"),
+            PhtError::PolarNotFound(name) => write!(f, "Polar {name} not found"),
+            PhtError::RaceNotFound(name) => write!(f, "Race {name} not found"),
+            PhtError::NavigationFailed(cause) => write!(f, "Navigation failed : {cause}"),
+            PhtError::Cancelled => write!(f, "Routing cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for PhtError {}
+
+/// Error as seen from JS : `{ code, message }`.
+#[derive(Debug, Serialize)]
+pub struct ErrorPayload {
+    pub code: String,
+    pub message: String,
+}
+
+impl From<&anyhow::Error> for ErrorPayload {
+    fn from(e: &anyhow::Error) -> Self {
+        let code = match e.downcast_ref::<PhtError>() {
+            Some(e) => e.code(),
+            None => "internal",
+        };
+
+        ErrorPayload {
+            code: code.to_string(),
+            message: e.to_string(),
+        }
+    }
+}
@@ -1,3 +1,12 @@
+use std::collections::HashMap;
+#[cfg(not(feature = "wasm"))]
+use std::path::PathBuf;
+
 pub(crate) enum ProviderConfig {
   Vr,
+  /// Same tiles as `Vr`, from files given at runtime instead of the embedded `land` folder.
+  VrFiles(HashMap<String, Vec<u8>>),
+  /// Same tiles as `Vr`, from a copy of the `land` folder.
+  #[cfg(not(feature = "wasm"))]
+  VrDir(PathBuf),
 }
@@ -4,7 +4,7 @@ use std::{collections::HashMap, f64::consts::PI, sync::{Arc, RwLock}};
 use config::ProviderConfig;
 use log::{debug, error, info};
 
-use crate::{position::Coords, utils};
+use crate::{error::PhtError, position::Coords, utils};
 
 pub(crate) mod config;
 pub(crate) mod vr;
@@ -23,25 +23,30 @@ impl Providers {
     pub(crate) async fn init_provider(&self, config: &ProviderConfig) -> Result<()> {
         info!("Init provider");
 
-        match config {
-            ProviderConfig::Vr => {
-                let providers = self.providers.clone();
-
-                match vr::VrLandProvider::new() {
-                    Ok(vr) => {
-                        let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<Box<dyn LandsProvider + Sync + Send>>>> = providers.write().unwrap();
-                        providers.insert("vr".into(), Arc::new(vr));
-                    },
-                    Err(e) => {
-                        error!("Failed starting vr land provider : {}", e);
-                    }
-                }
+        let vr = match config {
+            ProviderConfig::Vr => vr::VrLandProvider::new(),
+            ProviderConfig::VrFiles(files) => vr::VrLandProvider::from_files(files),
+            #[cfg(not(feature = "wasm"))]
+            ProviderConfig::VrDir(dir) => vr::VrLandProvider::from_dir(dir),
+        };
+
+        match vr {
+            Ok(vr) => {
+                let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<Box<dyn LandsProvider + Sync + Send>>>> = self.providers.write().unwrap();
+                providers.insert("vr".into(), Arc::new(vr));
+            },
+            Err(e) => {
+                error!("Failed starting vr land provider : {}", e);
             }
         }
 
         Ok(())
     }
 
+    pub(crate) fn get(&self, provider: &str) -> Option<Arc<Box<dyn LandsProvider + Sync + Send>>> {
+        self.providers.read().unwrap().get(provider).cloned()
+    }
+
     pub(crate) fn draw(&self, provider: String, x: i64, y: i64, z: u32, width: usize, height: usize, f: Box<dyn FnOnce(&Vec<u8>) -> Result<()> + 'static>) -> Result<()> {
         debug!("Draw land {provider} ({x},{y},{z}) ({width},{height})");
 
@@ -54,7 +59,7 @@ impl Providers {
                 provider.draw(x, y, z, width, height, f)
             },
             None => {
-                bail!("Provider not found")
+                bail!(PhtError::ProviderNotFound(provider))
             },
         }
     }
@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+#[cfg(not(feature = "wasm"))]
+use std::path::Path;
+
 use anyhow::{bail, Result};
 use cfg_if::cfg_if;
+#[cfg(not(feature = "land"))]
+use log::warn;
 use rust_embed::Embed;
 use crate::land::LandsProvider;
 
@@ -107,47 +113,100 @@ impl VrLandProvider {
 
     pub(crate) fn new() -> Result<Box<dyn LandsProvider + Send + Sync>> {
 
+        cfg_if! {
+            if #[cfg(feature = "land")] {
+                let index = match Carto::get("index") {
+                    Some(index) => index.data,
+                    None => {
+                        bail!("Tiles index not found");
+                    }
+                };
+
+                let provider = Self::load(&index, |file_name| Carto::get(&format!("carto/{file_name}")).map(|file| file.data.into_owned()))?;
+            } else {
+                warn!("Built without the land feature : no land is detected, see VrLandProvider::from_files");
+
+                const SEA: Tile = Tile::Sea;
+                const SEA_ARRAY: [Tile;360] = [SEA;360];
+
+                let provider = Self {
+                    tiles: Box::new([SEA_ARRAY;180]),
+                };
+            }
+        }
+
+        Ok(Box::new(provider))
+    }
+
+    /// Provider over the `index` and `carto/*.deg` files of the `land` folder, loaded at runtime.
+    pub(crate) fn from_files(files: &HashMap<String, Vec<u8>>) -> Result<Box<dyn LandsProvider + Send + Sync>> {
+        let index = match files.get("index") {
+            Some(index) => index,
+            None => {
+                bail!("Tiles index not found");
+            }
+        };
+
+        Self::from_reader(index, |file_name| files.get(&format!("carto/{file_name}")).cloned())
+    }
+
+    /// Provider over an `index` and the mixed tiles `tiles` returns by file name, such as `1_-5_47.deg`.
+    ///
+    /// Only the tiles the index marks as mixed are asked for.
+    pub(crate) fn from_reader(index: &[u8], tiles: impl Fn(&str) -> Option<Vec<u8>>) -> Result<Box<dyn LandsProvider + Send + Sync>> {
+        Ok(Box::new(Self::load(index, tiles)?))
+    }
+
+    /// Provider over a copy of the `land` folder.
+    #[cfg(not(feature = "wasm"))]
+    pub(crate) fn from_dir(dir: &Path) -> Result<Box<dyn LandsProvider + Send + Sync>> {
+        let index = std::fs::read(dir.join("index"))?;
+
+        Self::from_reader(&index, |file_name| std::fs::read(dir.join("carto").join(file_name)).ok())
+    }
+
+    fn load(index: &[u8], tiles: impl Fn(&str) -> Option<Vec<u8>>) -> Result<Self> {
+
         const LAND: Tile = Tile::Sea;
         const LAND_ARRAY: [Tile;360] = [LAND;360];
 
         let mut tiles_array: Box<[[Tile;360];180]> = Box::new([LAND_ARRAY;180]);
 
-        #[cfg(feature = "land")]
-        {
-            let index = match Carto::get("index") {
-                Some(index) => index.data,
-                None => {
-                    bail!("Tiles index not found");
-                }
-            };
+        if index.len() < 180 * 360 / 4 {
+            bail!("Tiles index too short : {} bytes", index.len());
+        }
 
-            for d_lat in 0..180 {
-                let latitude = Self::LAT_0 + d_lat as i32;
+        for d_lat in 0..180 {
+            let latitude = Self::LAT_0 + d_lat as i32;
 
-                for d_lon in 0..360 {
-                    let longitude = Self::LON_0 + d_lon as i32;
+            for d_lon in 0..360 {
+                let longitude = Self::LON_0 + d_lon as i32;
 
-                    let file_name = format!("1_{}_{}.deg", longitude, latitude);
+                let file_name = format!("1_{}_{}.deg", longitude, latitude);
 
-                    let p = d_lat * Self::LON_N as usize + d_lon;
+                let p = d_lat * Self::LON_N as usize + d_lon;
 
-                    let tile = match (index[p/4] >> (6 - 2*(p%4))) & 3 {
-                        0 => Tile::Sea,
-                        1 => Tile::load(&format!("carto/{file_name}"))?,
-                        2 => Tile::Land,
-                        _ => {
-                            bail!("bad value");
+                let tile = match (index[p/4] >> (6 - 2*(p%4))) & 3 {
+                    0 => Tile::Sea,
+                    1 => match tiles(&file_name) {
+                        Some(buf) => Tile::Mixed(buf),
+                        None => {
+                            bail!("Tile {} not found", file_name);
                         }
-                    };
+                    },
+                    2 => Tile::Land,
+                    _ => {
+                        bail!("bad value");
+                    }
+                };
 
-                    tiles_array[d_lat][d_lon] = tile;
-                }
+                tiles_array[d_lat][d_lon] = tile;
             }
         }
 
-        Ok(Box::new(Self {
+        Ok(Self {
             tiles: tiles_array,
-        }))
+        })
     }
 }
 
@@ -158,18 +217,3 @@ enum Tile {
     Land,
     Mixed(Vec<u8>),
 }
-
-impl Tile {
-    #[cfg(feature = "land")]
-    fn load(file_name: &str) -> Result<Tile> {
-
-        let buf = match Carto::get(file_name) {
-            Some(buf) => buf.data.as_ref().to_vec(),
-            None => {
-                bail!("Tile {} not found", file_name);
-            }
-        };
-
-        Ok(Tile::Mixed(buf))
-    }
-}
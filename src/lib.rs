@@ -1,6 +1,8 @@
 // #![feature(btree_extract_if)]
 
 pub(crate) mod algorithm;
+pub(crate) mod current;
+pub mod error;
 pub(crate) mod land;
 pub mod phtheirichthys;
 pub(crate) mod polar;
@@ -9,9 +11,14 @@ pub(crate) mod race;
 mod router;
 mod utils;
 pub mod wind;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 #[cfg(feature = "wasm")]
 pub mod wasm_binding;
 
+pub use router::{Isochrone, IsochronePoint, IsochroneSection, RouteResult};
+
 #[cfg(test)]
 mod tests;
 
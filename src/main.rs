@@ -10,6 +10,8 @@ use crate::wind::Wind;
 
 
 pub(crate) mod algorithm;
+pub(crate) mod current;
+pub mod error;
 pub(crate) mod land;
 pub mod phtheirichthys;
 pub(crate) mod polar;
@@ -21,8 +23,7 @@ pub mod wind;
 mod tests;
 
 #[cfg(feature = "tokio")]
-#[tokio::main]
-async fn main() {
+fn main() {
 
     std::env::var("RUST_LOG").map_err(|_| {
         std::env::set_var("RUST_LOG", "error,phtheirichthys=debug");
@@ -33,12 +34,16 @@ async fn main() {
 
     let phtheirichthys = Phtheirichthys::new();
 
-    phtheirichthys.add_wind_provider().await;
-    phtheirichthys.add_land_provider().await;
+    // kept alive while routing, the forecasts being refreshed on it
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    runtime.block_on(async {
+        phtheirichthys.add_wind_provider().await;
+        phtheirichthys.add_land_provider().await;
 
-    while phtheirichthys.get_wind_provider_status("vr".to_string()).is_err() {
-        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    }
+        while phtheirichthys.get_wind_provider_status("vr".to_string()).is_err() {
+            tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        }
+    });
 
     let status = phtheirichthys.get_wind_provider_status("vr".to_string()).unwrap();
 
@@ -53,7 +58,7 @@ async fn main() {
 
     let polar = serde_json::from_str(polar).expect("polar ok");
 
-    phtheirichthys.add_polar("19".to_string(), polar);
+    phtheirichthys.add_polar("19".to_string(), polar).expect("polar ok");
 
     let race = r#"
     {
@@ -80,6 +85,7 @@ async fn main() {
         hull: false,
         winch: false,
         stamina: true,
+        manual_sails: false,
     };
 
     let request = RouteRequest {
@@ -89,7 +95,7 @@ async fn main() {
         status: BoatStatus {
             aground: false,
             boat_speed: Speed::from_kts(0f64),
-            wind: Wind { direction: 0.0, speed: Default::default() },
+            wind: Wind { direction: 0.0, speed: Default::default(), gust: None },
             foil: 0,
             boost: 0,
             best_ratio: 0.0,
@@ -109,7 +115,7 @@ async fn main() {
         ],
     };
 
-    match phtheirichthys.navigate("vr".to_string(), "19".to_string(), race, boat_options, request).await {
+    match phtheirichthys.navigate_blocking("vr".to_string(), "19".to_string(), race, boat_options, request, None, None) {
         Ok(_) => info!("Ok"),
         Err(err) => error!("Navigate error : {}", err)
     }
@@ -1,21 +1,27 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use anyhow::{bail, Result};
 
 use chrono::{DateTime, Duration, Utc};
 use cubecl::prelude::*;
 // use gloo::timers::callback::Timeout;
-use log::error;
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
 use tsify_next::Tsify;
 use wasm_bindgen::prelude::*;
 
 use crate::{algorithm, land, wind};
+use crate::current::{ConstantCurrent, CurrentProvider, NoCurrent};
+use crate::land::LandsProvider;
 use crate::land::vr::VrLandProvider;
+use crate::error::PhtError;
 use crate::race::{Race, Races, RacesSpec};
-use crate::router::echeneis::EcheneisConfig;
-use crate::router::{RouteResult, Router};
-use crate::{polar::{Polar, Polars, PolarsSpec}, position::{Heading, Penalties, Coords}, router::{echeneis::{Echeneis, NavDuration, Position}, RouteRequest}, utils::Distance, wind::{providers::config::ProviderConfig, ProviderStatus, Wind}};
-use crate::algorithm::Algorithm;
+use crate::router::echeneis::{EcheneisConfig, IsochroneListener};
+use crate::router::{CancellationToken, LiveBest, RouteResult, Router, Telemetry};
+use crate::{polar::{Polar, Polars, PolarsSpec}, position::{BoatSettings, BoatStatus, Heading, Coords, Penalties}, router::{echeneis::{Echeneis, NavDuration, Position}, RouteRequest}, utils::{Distance, Speed}, wind::{providers::config::ProviderConfig, ProviderStatus, Wind}};
+use crate::algorithm::{Algorithm, AlgorithmKind};
+use crate::algorithm::great_circle::GreatCircle;
+use crate::algorithm::spherical::Spherical;
 use crate::polar::PolarCache;
 
 pub struct Phtheirichthys {
@@ -23,6 +29,7 @@ pub struct Phtheirichthys {
     land_providers: land::Providers,
     polars: Polars,
     races: Races,
+    currents: RwLock<Arc<dyn CurrentProvider + Send + Sync>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
@@ -31,6 +38,73 @@ pub struct SnakeResult {
     positions: Vec<(i64, Coords)>
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct JumpResult {
+    pub(crate) destination: Coords,
+    #[tsify(type = "number")]
+    pub(crate) boat_speed: Speed,
+    pub(crate) foil: u8,
+    pub(crate) penalties: Penalties,
+    pub(crate) stamina: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PolarSpeed {
+    #[tsify(type = "number")]
+    pub(crate) speed: Speed,
+    pub(crate) foil: u8,
+    pub(crate) best: f64,
+}
+
+/// Speeds of the sails at one twa of a polar.
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SpeedGridRow {
+    pub(crate) twa: f64,
+    /// `(sail index, speed in knots)` for every sail.
+    pub(crate) speeds: Vec<(usize, f64)>,
+}
+
+/// Where `test_webgpu` ran.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum ComputeBackend {
+    Gpu,
+    Cpu,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BestHeading {
+    pub(crate) heading: f64,
+    #[tsify(type = "number")]
+    pub(crate) vmg: Speed,
+}
+
+/// Rhumb line and great circle from one point to another, distances in nm.
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct NavSolution {
+    #[tsify(type = "number")]
+    pub(crate) rhumb_distance: Distance,
+    pub(crate) rhumb_bearing: f64,
+    #[tsify(type = "number")]
+    pub(crate) gc_distance: Distance,
+    pub(crate) gc_initial_bearing: f64,
+    pub(crate) gc_final_bearing: f64,
+}
+
+/// Wind felt aboard, `angle` from the bow as a twa.
+#[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ApparentWind {
+    pub(crate) angle: f64,
+    #[tsify(type = "number")]
+    pub(crate) speed: Speed,
+}
+
 impl Phtheirichthys {
 
     pub fn new() -> Self {
@@ -39,6 +113,7 @@ impl Phtheirichthys {
             land_providers: land::Providers::new(),
             polars: <Polars as PolarsSpec>::new(),
             races: <Races as RacesSpec>::new(),
+            currents: RwLock::new(Arc::new(NoCurrent)),
         }
     }
 
@@ -50,6 +125,17 @@ impl Phtheirichthys {
         }
     }
 
+    /// Registers an already built wind provider under `name`.
+    #[cfg(test)]
+    pub(crate) fn insert_wind_provider(&self, name: String, provider: Arc<dyn wind::Provider + Sync + Send>) {
+        self.wind_providers.insert(name, provider);
+    }
+
+    /// Sets the current flowing to `direction` everywhere during the next navigations, none with a zero `speed`.
+    pub fn set_current(&self, direction: f64, speed: Speed) {
+        *self.currents.write().unwrap() = Arc::new(ConstantCurrent { direction, speed });
+    }
+
     pub fn get_wind_provider_status(&self, provider: String) -> anyhow::Result<ProviderStatus> {
         self.wind_providers.get_status(provider)
     }
@@ -66,6 +152,23 @@ impl Phtheirichthys {
         }
     }
 
+    /// Same as `add_land_provider`, with the tiles of the `land` folder given at runtime.
+    pub async fn add_land_provider_from_files(&self, files: HashMap<String, Vec<u8>>) {
+        match self.land_providers.init_provider(&land::config::ProviderConfig::VrFiles(files)).await {
+            Ok(()) => {},
+            Err(e) => error!("Failed adding provider : {}", e)
+        }
+    }
+
+    /// Same as `add_land_provider`, reading the tiles from a copy of the `land` folder.
+    #[cfg(not(feature = "wasm"))]
+    pub async fn add_land_provider_from_dir(&self, dir: std::path::PathBuf) {
+        match self.land_providers.init_provider(&land::config::ProviderConfig::VrDir(dir)).await {
+            Ok(()) => {},
+            Err(e) => error!("Failed adding provider : {}", e)
+        }
+    }
+
     pub(crate) fn draw_land(&self, provider: String, x: i64, y: i64, z: u32, width: usize, height: usize, f: Box<dyn FnOnce(&Vec<u8>) -> Result<()> + 'static>) -> Result<()> {
         self.land_providers.draw(provider, x, y, z, width, height, f)
     }
@@ -74,10 +177,41 @@ impl Phtheirichthys {
         self.wind_providers.draw(provider, m, x, y, z, width, height, f)
     }
 
-    pub fn add_polar(&self, name: String, polar: Polar) {
+    pub fn add_polar(&self, name: String, polar: Polar) -> Result<()> {
+        polar.validate()?;
+
         let mut polars = self.polars.write().unwrap();
 
         polars.insert(name, Arc::new(polar));
+
+        Ok(())
+    }
+
+    /// Registers as `name` a polar going at `speed` whatever the wind, see `Polar::motor`.
+    pub fn add_motor_polar(&self, name: String, speed: Speed) -> Result<()> {
+        self.add_polar(name, Polar::motor(speed))
+    }
+
+    pub fn list_polars(&self) -> Vec<String> {
+        self.polars.list()
+    }
+
+    pub fn remove_polar(&self, name: String) -> Result<()> {
+        self.polars.remove(&name)
+    }
+
+    /// `(id, name)` of the sails of polar `name`.
+    pub fn polar_sails(&self, name: String) -> Result<Vec<(usize, String)>> {
+        Ok(self.polars.get(&name)?.sails())
+    }
+
+    pub(crate) fn route_telemetry(&self, route: &RouteResult, polar: String) -> Result<Vec<Telemetry>> {
+        Ok(route.telemetry(&self.polars.get(&polar)?))
+    }
+
+    /// Distance left to sail in nm at each waypoint of `route`, through the buoys of `race` not passed yet.
+    pub(crate) fn route_remaining_distances(&self, route: &RouteResult, race: &Race) -> Vec<f64> {
+        route.remaining_distances(race).iter().map(|distance| distance.nm()).collect()
     }
 
     pub(crate) fn list_races(&self) -> Vec<Race> {
@@ -92,7 +226,29 @@ impl Phtheirichthys {
         self.races.set(name, race)
     }
 
+    pub(crate) fn remove_race(&self, name: String) -> Result<()> {
+        self.races.remove(&name)
+    }
+
+    pub(crate) fn clear_races(&self) {
+        self.races.clear()
+    }
+
+    pub(crate) fn set_race_from_vr_json(&self, name: String, bytes: &[u8]) -> Result<()> {
+        self.races.set(name, Race::from_vr_json(bytes)?);
+
+        Ok(())
+    }
+
+    pub(crate) fn set_race_from_gpx(&self, name: String, bytes: &[u8]) -> Result<()> {
+        self.races.set(name, Race::from_gpx(bytes)?);
+
+        Ok(())
+    }
+
     pub(crate) fn eval_snake(&self, route_request: RouteRequest, params: SnakeParams, heading: Heading) -> Result<SnakeResult> {
+        route_request.validate()?;
+
         let wind_provider = self.wind_providers.get(params.wind_provider)?;
         let start = Arc::new(route_request.from.clone());
         let mut polar = PolarCache::new(self.polars.get(&params.polar)?);
@@ -112,10 +268,10 @@ impl Phtheirichthys {
             distance: Distance::zero(),
             reached: None,
             settings: route_request.boat_settings,
-            status: route_request.status.clone(),
+            status: Arc::new(route_request.status.clone()),
             previous: None,
             is_in_ice_limits: false,
-            remaining_penalties: Penalties::new(),
+            remaining_penalties: route_request.status.penalties.clone(),
             remaining_stamina: route_request.status.stamina,
         };
         let mut result = vec![(0, src.point.clone())];
@@ -127,12 +283,13 @@ impl Phtheirichthys {
             let jump = Echeneis::<_>::jump2(
                 &std::sync::Arc::new(crate::algorithm::spherical::Spherical{}),
                 None,
+                None,
                 &mut polar,
                 &boat_options.clone(),
                 &start,
                 &Arc::new(src),
                 &None,
-                &t, Duration::hours(1), &wind, 1.0, true
+                &t, Duration::hours(1), &wind, None, 1.0, true
             );
 
             src = jump.iter().map(|(_, pos)| pos).max_by_key(|pos| &pos.distance).unwrap().to_owned();
@@ -148,6 +305,140 @@ impl Phtheirichthys {
         Ok(SnakeResult { positions: result })
     }
 
+    /// Speed of `polar` at a raw `twa` and `tws`, to plot its curves.
+    pub(crate) fn polar_speed_at(&self, polar: String, twa: f64, tws: Speed, sail: Option<usize>, boat_options: BoatOptions, ice: bool) -> Result<PolarSpeed> {
+        let result = self.polars.get(&polar)?.speed_at(twa, tws, sail, &boat_options, ice);
+
+        Ok(PolarSpeed {
+            speed: result.speed,
+            foil: result.foil,
+            best: result.best,
+        })
+    }
+
+    pub(crate) fn polar_speed_grid(&self, polar: String, tws: Speed, boat_options: BoatOptions, ice: bool) -> Result<Vec<SpeedGridRow>> {
+        Ok(self.polars.get(&polar)?.speed_grid(tws, &boat_options, ice).into_iter()
+            .map(|(twa, speeds)| SpeedGridRow {
+                twa,
+                speeds: speeds.into_iter().map(|(sail, speed)| (sail, speed.kts())).collect(),
+            })
+            .collect())
+    }
+
+    /// Heading of `polar` making the best way toward `bearing_to_dest` in `wind`.
+    pub(crate) fn best_heading_to(&self, polar: String, wind: Wind, bearing_to_dest: f64, sail: Option<usize>, boat_options: BoatOptions, ice: bool) -> Result<BestHeading> {
+        let (heading, vmg) = self.polars.get(&polar)?.best_heading_to(&wind, bearing_to_dest, sail, &boat_options, ice);
+
+        Ok(BestHeading {
+            heading: heading.heading(wind.direction),
+            vmg,
+        })
+    }
+
+    /// Wind felt aboard a boat sailing `boat_heading` at `boat_speed` in the true `wind`.
+    pub(crate) fn apparent_wind(&self, wind: Wind, boat_heading: f64, boat_speed: Speed) -> ApparentWind {
+        let (angle, speed) = wind.apparent(boat_heading, boat_speed);
+
+        ApparentWind { angle, speed }
+    }
+
+    /// Rhumb line and great circle distances and bearings from `from` to `to`.
+    pub(crate) fn navigation_solution(&self, from: Coords, to: Coords) -> NavSolution {
+        let (rhumb_distance, rhumb_bearing) = Spherical {}.distance_and_heading_to(&from, &to);
+        let (gc_distance, gc_initial_bearing) = GreatCircle {}.distance_and_heading_to(&from, &to);
+
+        NavSolution {
+            rhumb_distance,
+            rhumb_bearing,
+            gc_distance,
+            gc_initial_bearing,
+            gc_final_bearing: from.great_circle_final_bearing_to(&to),
+        }
+    }
+
+    /// Time to sail straight from `from` to `to`, starting with `settings` in `wind`.
+    pub(crate) fn eta_to(&self, polar: String, boat_options: BoatOptions, from: Coords, settings: BoatSettings, wind: Wind, to: Coords) -> Result<Duration> {
+        let polar = self.polars.get(&polar)?;
+
+        let (distance, heading) = Spherical {}.distance_and_heading_to(&from, &to);
+        let heading = Heading::HEADING(heading);
+
+        let polar_result = polar.get_boat_speed(&heading, &wind, Some(&settings.sail), &settings.sail, false);
+        if polar_result.speed.kts() <= 0.0 {
+            bail!("No boat speed for {:?}", heading);
+        }
+
+        let penalties = polar.add_penalties(&Arc::new(boat_options), Penalties::default(), 100.0,
+                                            settings.heading.twa(wind.direction), heading.twa(wind.direction),
+                                            &settings.sail, &polar_result.sail,
+                                            &wind.speed
+        );
+
+        let (duration, _, _, _) = Polar::duration(polar_result.speed, distance, penalties);
+
+        Ok(duration)
+    }
+
+    /// Single jump of `duration_secs` from `from`, keeping `settings` in a uniform `wind`.
+    pub(crate) fn eval_jump(&self, polar: String, boat_options: BoatOptions, from: Coords, settings: BoatSettings, wind: Wind, duration_secs: i64) -> Result<JumpResult> {
+        if duration_secs <= 0 {
+            bail!("Invalid jump duration {duration_secs}s : should be positive");
+        }
+
+        let mut polar = PolarCache::new(self.polars.get(&polar)?);
+        let heading = settings.heading.clone();
+
+        let src = Position {
+            az: 0,
+            point: from.clone(),
+            from_dist: Distance::zero(),
+            dist_to: Distance::zero(),
+            duration: NavDuration::zero(),
+            distance: Distance::zero(),
+            reached: None,
+            settings,
+            status: Arc::new(BoatStatus {
+                aground: false,
+                boat_speed: Speed::from_kts(0.0),
+                wind: wind.clone(),
+                foil: 0,
+                boost: 0,
+                best_ratio: 0.0,
+                ratio: 0,
+                vmgs: None,
+                penalties: Penalties::default(),
+                stamina: 100.0,
+            }),
+            previous: None,
+            is_in_ice_limits: false,
+            remaining_penalties: Penalties::default(),
+            remaining_stamina: 100.0,
+        };
+
+        let jump = Echeneis::<_>::jump2(
+            &Arc::new(Spherical {}),
+            None,
+            None,
+            &mut polar,
+            &Arc::new(boat_options),
+            &Arc::new(from),
+            &Arc::new(src),
+            &None,
+            &heading, Duration::seconds(duration_secs), &wind, None, 1.0, true
+        );
+
+        match jump.into_iter().map(|(_, pos)| pos).max_by(|a, b| a.distance.cmp(&b.distance)) {
+            Some(pos) => Ok(JumpResult {
+                destination: pos.point,
+                boat_speed: pos.status.boat_speed.clone(),
+                foil: pos.status.foil,
+                penalties: pos.status.penalties.clone(),
+                stamina: pos.remaining_stamina,
+            }),
+            None => bail!("No boat speed for {:?}", heading),
+        }
+    }
+
     fn launch<R: Runtime>(device: &R::Device) {
 
         let start = Utc::now();
@@ -200,36 +491,110 @@ impl Phtheirichthys {
         // Should be [-0.1587,  0.0000,  0.8413,  5.0000]
         println!("Executed gelu with runtime {:?} in {:?}ns => {output:?}", R::name(), (Utc::now() - start).num_nanoseconds());
 
+        Self::launch_cpu(from_lat, from_lon, to_lat, to_lon);
+    }
+
+    fn launch_cpu(from_lat: &[f32], from_lon: &[f32], to_lat: &[f32], to_lon: &[f32]) {
         let start = Utc::now();
-        let algo = algorithm::spherical::Spherical {};
-        for i in 0..vec.capacity() {
-            algo.distance_to(&Coords {lat: from_lat[i] as f64, lon: from_lon[i] as f64 }, &Coords {lat: to_lat[i] as f64, lon: to_lon[i] as f64 });
-        }
+        let pairs = (0..from_lat.len())
+            .map(|i| (Coords {lat: from_lat[i] as f64, lon: from_lon[i] as f64 }, Coords {lat: to_lat[i] as f64, lon: to_lon[i] as f64 }))
+            .collect::<Vec<_>>();
+        algorithm::spherical::Spherical {}.distance_to_many(&pairs);
 
         println!("Executed loop in {:?}ns", (Utc::now() - start).num_nanoseconds());
     }
 
-    pub fn test_webgpu(&self) -> Result<()> {
-        Self::launch::<cubecl::wgpu::WgpuRuntime>(&Default::default());
+    /// Runs `gpu`, or the cpu loop if it panics, as cubecl does when no gpu can be initialized.
+    ///
+    /// Only a native safety net : built with `panic = "abort"`, as wasm32 is by default, the panic can't be caught,
+    /// hence the adapter probe in `test_webgpu`.
+    pub(crate) fn with_cpu_fallback(gpu: impl FnOnce() + std::panic::UnwindSafe) -> ComputeBackend {
+        match std::panic::catch_unwind(gpu) {
+            Ok(()) => ComputeBackend::Gpu,
+            Err(_) => Self::fallback_to_cpu(),
+        }
+    }
 
-        Ok(())
+    fn fallback_to_cpu() -> ComputeBackend {
+        error!("No gpu available, falling back to cpu");
+
+        let values = || (0..1024).map(|_| rand::random::<f32>()).collect::<Vec<_>>();
+        Self::launch_cpu(&values(), &values(), &values(), &values());
+
+        ComputeBackend::Cpu
+    }
+
+    /// Whether wgpu finds an adapter to launch the kernel on, cubecl panicking when there is none.
+    async fn gpu_available() -> bool {
+        wgpu::Instance::default().request_adapter(&wgpu::RequestAdapterOptions::default()).await.is_some()
+    }
+
+    pub async fn test_webgpu(&self) -> Result<ComputeBackend> {
+        if !Self::gpu_available().await {
+            return Ok(Self::fallback_to_cpu());
+        }
+
+        Ok(Self::with_cpu_fallback(|| Self::launch::<cubecl::wgpu::WgpuRuntime>(&Default::default())))
     }
     
-    pub async fn navigate(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest) -> Result<RouteResult> {
-        let wind_provider = self.wind_providers.get(wind_provider)?;
-        let polar = self.polars.get(&polar_id)?;
-        let lands_provider = Arc::new(VrLandProvider::new()?);
-        let algorithm = std::sync::Arc::new(crate::algorithm::spherical::Spherical{});
+    pub async fn navigate(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>, cancellation: Option<CancellationToken>) -> Result<RouteResult> {
+        self.navigate_with(wind_provider, polar_id, race, boat_options, request, max_duration_hours, algorithm, cancellation, None, None).await
+    }
+
+    /// Same as `navigate`, blocking the current thread until the routing is done.
+    #[cfg(not(feature = "wasm"))]
+    pub fn navigate_blocking(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>) -> Result<RouteResult> {
+        crate::utils::block_on(self.navigate(wind_provider, polar_id, race, boat_options, request, max_duration_hours, algorithm, None))
+    }
+
+    /// Same as `navigate`, keeping the best waypoint reached so far in `live_best` while routing.
+    pub(crate) async fn navigate_live(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>, cancellation: Option<CancellationToken>, live_best: Option<LiveBest>) -> Result<RouteResult> {
+        self.navigate_with(wind_provider, polar_id, race, boat_options, request, max_duration_hours, algorithm, cancellation, live_best, None).await
+    }
+
+    /// Same as `navigate_live`, calling `on_isochrone` with each isochrone as soon as it is computed.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) async fn navigate_streaming(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>, cancellation: Option<CancellationToken>, live_best: Option<LiveBest>, on_isochrone: IsochroneListener) -> Result<RouteResult> {
+        self.navigate_with(wind_provider, polar_id, race, boat_options, request, max_duration_hours, algorithm, cancellation, live_best, Some(on_isochrone)).await
+    }
+
+    async fn navigate_with(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>, cancellation: Option<CancellationToken>, live_best: Option<LiveBest>, on_isochrone: Option<IsochroneListener>) -> Result<RouteResult> {
+        let (wind_provider, polar, lands_provider) = self.routing_providers(wind_provider, polar_id, &request.start_time)?;
+        let lands = lands_provider.clone();
+        let currents = self.currents.read().unwrap().clone();
+        let config = EcheneisConfig { max_duration_hours: max_duration_hours.unwrap_or(EcheneisConfig::default().max_duration_hours), ..Default::default() };
 
         // let timeout = Timeout::new(0, move || {
         //     wasm_bindgen_futures::spawn_local(async move {
-                let router = Echeneis::new("".to_string(), polar, wind_provider, lands_provider, algorithm, EcheneisConfig { accuracy: 1.0, display_all_isochrones: false, timeout: 60 });
+                let result = match algorithm.unwrap_or_default() {
+                    AlgorithmKind::Spherical => {
+                        let mut router = Echeneis::new("".to_string(), polar, wind_provider, lands_provider, Arc::new(Spherical{}), config).with_currents(currents);
+                        if let Some(on_isochrone) = on_isochrone {
+                            router = router.with_isochrone_listener(on_isochrone);
+                        }
+                        if let Some(live_best) = live_best {
+                            router = router.with_live_best(live_best);
+                        }
+                        router.route(&race, boat_options, request, None, cancellation).await
+                    },
+                    AlgorithmKind::GreatCircle => {
+                        let mut router = Echeneis::new("".to_string(), polar, wind_provider, lands_provider, Arc::new(GreatCircle{}), config).with_currents(currents);
+                        if let Some(on_isochrone) = on_isochrone {
+                            router = router.with_isochrone_listener(on_isochrone);
+                        }
+                        if let Some(live_best) = live_best {
+                            router = router.with_live_best(live_best);
+                        }
+                        router.route(&race, boat_options, request, None, cancellation).await
+                    },
+                };
 
-                match router.route(&race, boat_options, request, None).await {
+                match result {
                     Ok(result) => {
-                        Ok(result)
+                        Ok(Self::check_lands(result, &**lands))
                     },
-                    Err(e) => bail!("Navigation failed : {}", e)
+                    Err(e) if e.is::<PhtError>() => Err(e),
+                    Err(e) => bail!(PhtError::NavigationFailed(e.to_string()))
                 }
         //     });
         // });
@@ -237,6 +602,59 @@ impl Phtheirichthys {
         // timeout.forget();
 
     }
+
+    /// Routes again from `new_from` at `new_time`, the buoys `previous` passed before its nearest waypoint being validated.
+    pub async fn reroute(&self, wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, previous: RouteResult, new_from: Coords, new_time: DateTime<Utc>, max_duration_hours: Option<u64>, algorithm: Option<AlgorithmKind>) -> Result<RouteResult> {
+        let (wind_provider, polar, lands_provider) = self.routing_providers(wind_provider, polar_id, &new_time)?;
+        let lands = lands_provider.clone();
+        let currents = self.currents.read().unwrap().clone();
+        let config = EcheneisConfig { max_duration_hours: max_duration_hours.unwrap_or(EcheneisConfig::default().max_duration_hours), ..Default::default() };
+
+        let result = match algorithm.unwrap_or_default() {
+            AlgorithmKind::Spherical => {
+                let router = Echeneis::new("".to_string(), polar, wind_provider, lands_provider, Arc::new(Spherical{}), config).with_currents(currents);
+                router.reroute(&race, boat_options, &previous, new_from, new_time, None).await
+            },
+            AlgorithmKind::GreatCircle => {
+                let router = Echeneis::new("".to_string(), polar, wind_provider, lands_provider, Arc::new(GreatCircle{}), config).with_currents(currents);
+                router.reroute(&race, boat_options, &previous, new_from, new_time, None).await
+            },
+        };
+
+        match result {
+            Ok(result) => Ok(Self::check_lands(result, &**lands)),
+            Err(e) if e.is::<PhtError>() => Err(e),
+            Err(e) => bail!(PhtError::NavigationFailed(e.to_string()))
+        }
+    }
+
+    /// Flags the way when the segment between two of its waypoints cuts through land.
+    pub(crate) fn check_lands(mut result: RouteResult, lands: &dyn LandsProvider) -> RouteResult {
+        result.infos.crosses_land = result.crosses_land(lands);
+        if let Some(index) = result.infos.crosses_land {
+            warn!("Route crossing land after waypoint {}", index);
+        }
+        result
+    }
+
+    /// Wind, polar and lands to route with, the forecasts having to cover `start_time`.
+    fn routing_providers(&self, wind_provider: String, polar_id: String, start_time: &DateTime<Utc>) -> Result<(Arc<dyn wind::Provider + Sync + Send>, Arc<Polar>, Arc<Box<dyn LandsProvider + Sync + Send>>)> {
+        let wind_provider = self.wind_providers.get(wind_provider)?;
+
+        match wind_provider.available_range() {
+            Some((first, last)) if first <= *start_time && *start_time <= last => {},
+            Some((first, last)) => bail!(PhtError::OutOfCoverage(format!("{} not in [{}, {}]", start_time, first, last))),
+            None => bail!(PhtError::ProviderNotReady),
+        }
+
+        let polar = self.polars.get(&polar_id)?;
+        let lands_provider = match self.land_providers.get("vr") {
+            Some(lands_provider) => lands_provider,
+            None => Arc::new(VrLandProvider::new()?),
+        };
+
+        Ok((wind_provider, polar, lands_provider))
+    }
 }
 
 #[derive(Serialize, Deserialize, Tsify)]
@@ -248,7 +666,7 @@ pub(crate) struct SnakeParams {
     boat_options: BoatOptions,
 }
 
-#[derive(Serialize, Deserialize, Tsify)]
+#[derive(Default, Serialize, Deserialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct BoatOptions {
     pub lt: bool,
@@ -258,10 +676,53 @@ pub struct BoatOptions {
     pub hull: bool,
     pub winch: bool,
     pub stamina: bool,
+    /// Sails are only changed on purpose : no speed up of the current sail when close to the best one.
+    #[serde(default)]
+    pub manual_sails: bool,
 }
 
 impl BoatOptions {
     pub fn new() -> Self {
-        Self { lt: false, gt: false, code0: false, foil: false, hull: false, winch: false, stamina: false }
+        Self::default()
+    }
+
+    pub fn with_lt(mut self, lt: bool) -> Self {
+        self.lt = lt;
+        self
+    }
+
+    pub fn with_gt(mut self, gt: bool) -> Self {
+        self.gt = gt;
+        self
+    }
+
+    pub fn with_code0(mut self, code0: bool) -> Self {
+        self.code0 = code0;
+        self
+    }
+
+    pub fn with_foil(mut self, foil: bool) -> Self {
+        self.foil = foil;
+        self
+    }
+
+    pub fn with_hull(mut self, hull: bool) -> Self {
+        self.hull = hull;
+        self
+    }
+
+    pub fn with_winch(mut self, winch: bool) -> Self {
+        self.winch = winch;
+        self
+    }
+
+    pub fn with_stamina(mut self, stamina: bool) -> Self {
+        self.stamina = stamina;
+        self
+    }
+
+    pub fn with_manual_sails(mut self, manual_sails: bool) -> Self {
+        self.manual_sails = manual_sails;
+        self
     }
 }
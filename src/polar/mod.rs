@@ -4,6 +4,7 @@ use anyhow::{bail, Result};
 use chrono::Duration;
 use serde::{Serialize, Deserialize};
 use tsify_next::Tsify;
+use crate::error::PhtError;
 use crate::phtheirichthys::BoatOptions;
 use crate::position;
 use crate::position::{Heading, Penalties, Penalty};
@@ -15,7 +16,11 @@ pub(crate) type Polars = Arc<RwLock<HashMap<String, Arc<Polar>>>>;
 pub(crate) trait PolarsSpec {
     fn new() -> Self;
 
+    fn list(&self) -> Vec<String>;
+
     fn get(&self, name: &String) -> Result<Arc<Polar>>;
+
+    fn remove(&self, name: &String) -> Result<()>;
 }
 
 impl PolarsSpec for Polars {
@@ -23,11 +28,24 @@ impl PolarsSpec for Polars {
         Arc::new(RwLock::new(HashMap::new()))
     }
 
+    fn list(&self) -> Vec<String> {
+        let polars = self.read().unwrap();
+        polars.keys().cloned().collect::<Vec<_>>()
+    }
+
     fn get(&self, name: &String) -> Result<Arc<Polar>> {
         let polars = self.read().unwrap();
         match polars.get(name) {
             Some(polar) => Ok(polar.clone()),
-            None => bail!("Polar {name} not found"),
+            None => bail!(PhtError::PolarNotFound(name.clone())),
+        }
+    }
+
+    fn remove(&self, name: &String) -> Result<()> {
+        let mut polars = self.write().unwrap();
+        match polars.remove(name) {
+            Some(_) => Ok(()),
+            None => bail!(PhtError::PolarNotFound(name.clone())),
         }
     }
 }
@@ -41,6 +59,24 @@ pub(crate) struct PolarResult {
     pub(crate) best: f64,
 }
 
+/// Sails kept by `get_boat_speeds`, from their ratio to the best speed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SailFilter {
+    All,
+    BestOnly,
+    Threshold(f64),
+}
+
+impl SailFilter {
+    pub(crate) fn keeps(&self, best: f64) -> bool {
+        match self {
+            SailFilter::All => true,
+            SailFilter::BestOnly => best >= 1.0,
+            SailFilter::Threshold(threshold) => best >= *threshold,
+        }
+    }
+}
+
 pub(crate) struct PolarCache {
     polar: Arc<Polar>,
     twa_indices: BTreeMap<i32, (usize, usize, f64)>,
@@ -59,20 +95,18 @@ impl PolarCache {
         }
     }
 
-    pub(crate) fn get_boat_speeds(&mut self, heading: &Heading, wind: &Wind, current_sail: &position::Sail, is_in_ice_limits: bool, all: bool) -> Vec<PolarResult> {
+    pub(crate) fn get_boat_speeds(&mut self, heading: &Heading, wind: &Wind, current_sail: &position::Sail, is_in_ice_limits: bool, filter: SailFilter, auto_sail_change: bool) -> Vec<PolarResult> {
 
-        let mut twa = heading.twa(wind.direction);
-        if twa < 0.0 {
-            twa = -1.0 * twa
-        }
-        if twa > 180.0 {
-            twa = 360.0 - twa
-        }
+        let twa = self.polar.polar_twa(heading.twa(wind.direction));
 
         let tws_indices = self.tws_interpolation_index(wind.speed.kts());
         let twa_indices = self.twa_interpolation_index(twa);
 
-        self.polar.get_boat_speeds(heading, wind, current_sail, is_in_ice_limits, all, tws_indices, twa_indices)
+        self.polar.get_boat_speeds(heading, wind, current_sail, is_in_ice_limits, filter, auto_sail_change, tws_indices, twa_indices)
+    }
+
+    pub(crate) fn sail_filter(&self) -> SailFilter {
+        self.polar.sail_filter()
     }
 
     pub(crate) fn add_penalties(&self, boat_options: &Arc<BoatOptions>, penalties: Penalties, stamina: f64, previous_twa: f64, new_twa: f64, previous_sail: &position::Sail, new_sail: &position::Sail, wind_speed: &Speed) -> Penalties {
@@ -87,13 +121,15 @@ impl PolarCache {
         self.polar.recovers(stamina, duration, wind_speed)
     }
 
+    /// Cached by tenths of degree, keeping the sign for asymmetric polars.
     fn twa_interpolation_index(&mut self, twa: f64) -> (usize, usize, f64) {
-        let binding = self.twa_indices.get(&(twa as i32));
+        let key = (twa * 10.0).round() as i32;
+        let binding = self.twa_indices.get(&key);
         if binding.is_some() {
             binding.unwrap().clone()
         } else {
             let twa_indices = Polar::interpolation_index(&self.polar.twa, twa);
-            self.twa_indices.insert(twa as i32, twa_indices);
+            self.twa_indices.insert(key, twa_indices);
             twa_indices.clone()
         }
     }
@@ -109,7 +145,73 @@ impl PolarCache {
 }
 
 impl Polar {
-    fn interpolation_index(values: &Vec<f64>, value: f64) -> (usize, usize, f64) {
+    pub(crate) fn sail_label(&self, index: usize) -> &str {
+        self.sail.get(index).map_or("Unknown", |sail| sail.name.as_str())
+    }
+
+    /// Checks that the speeds of every sail cover the whole, non empty, twa by tws grid, and that there is a tiredness coefficient per wind threshold.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.twa.is_empty() || self.tws.is_empty() {
+            bail!("Invalid polar {} : {} twa and {} tws, should have at least one of each", self.label, self.twa.len(), self.tws.len());
+        }
+
+        if self.sail.is_empty() {
+            bail!("Invalid polar {} : no sail", self.label);
+        }
+
+        for sail in self.sail.iter() {
+            if sail.speed.len() != self.twa.len() || sail.speed.iter().any(|speeds| speeds.len() != self.tws.len()) {
+                bail!("Invalid polar {} : speeds of {} should be {} twa by {} tws", self.label, sail.name, self.twa.len(), self.tws.len());
+            }
+        }
+
+        if let Tiredness::Coefs(coefs) = &self.stamina.tiredness {
+            if coefs.len() != self.wind_thresholds.tiredness.len() {
+                bail!("Invalid polar {} : {} tiredness coefficients for {} wind thresholds", self.label, coefs.len(), self.wind_thresholds.tiredness.len());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `(id, name)` of every sail, in polar order.
+    pub(crate) fn sails(&self) -> Vec<(usize, String)> {
+        self.sail.iter().map(|sail| (sail.id, sail.name.clone())).collect()
+    }
+
+    /// Polar of a boat under motor: `speed` at every twa and tws, a single sail and no penalty.
+    pub(crate) fn motor(speed: Speed) -> Polar {
+        let no_penalty = || PenaltyCase { std_timer_sec: 0, std_ratio: 1.0, pro_timer_sec: 0, pro_ratio: 1.0, std: None, pro: None };
+
+        Polar {
+            id: 0,
+            label: "motor".to_string(),
+            global_speed_ratio: 1.0,
+            ice_speed_ratio: 1.0,
+            auto_sail_change_tolerance: 1.0,
+            bad_sail_tolerance: 0.0,
+            max_speed: speed.kts(),
+            foil: Foil { speed_ratio: 1.0, twa_min: 0.0, twa_max: 0.0, twa_merge: 0.0, tws_min: 0.0, tws_max: 0.0, tws_merge: 0.0 },
+            hull: Hull { speed_ratio: 1.0 },
+            winch: Winch { tack: no_penalty(), gybe: no_penalty(), sail_change: no_penalty(), lws: None, hws: None },
+            tws: vec![0.0, 100.0],
+            twa: vec![0.0, 180.0],
+            sail: vec![PolarSail { id: 1, name: "Motor".to_string(), speed: vec![vec![speed.kts(); 2]; 2] }],
+            stamina: StaminaModel { tack: 0.0, gybe: 0.0, sail_change: 0.0, ..Default::default() },
+            min_boat_speed: Speed::from_kts(0.0),
+            wind_thresholds: Default::default(),
+        }
+    }
+
+    /// `(i0, i1, f)` such that `value` is at `values[i0] * f + values[i1] * (1 - f)`.
+    ///
+    /// Values outside of the grid are clamped to its first or last value, `f` being 1 on `values[i0]` above it.
+    /// A singleton grid always gives its only value, an empty one is rejected by `validate`.
+    pub(crate) fn interpolation_index(values: &Vec<f64>, value: f64) -> (usize, usize, f64) {
+        if values.len() < 2 {
+            return (0, 0, 0.0);
+        }
+
         let mut i = 0;
         while values[i] < value {
             i += 1;
@@ -128,15 +230,12 @@ impl Polar {
         (0, 0, 0.0)
     }
 
-    pub(crate) fn get_boat_speeds(&self, heading: &Heading, wind: &Wind, current_sail: &position::Sail, is_in_ice_limits: bool, all: bool, tws_indices: (usize, usize, f64), twa_indices: (usize, usize, f64)) -> Vec<PolarResult> {
+    /// Speed of each sail, the current one sped up to the best when within `auto_sail_change_tolerance` unless `auto_sail_change` is off.
+    pub(crate) fn get_boat_speeds(&self, heading: &Heading, wind: &Wind, current_sail: &position::Sail, is_in_ice_limits: bool, filter: SailFilter, auto_sail_change: bool, tws_indices: (usize, usize, f64), twa_indices: (usize, usize, f64)) -> Vec<PolarResult> {
+        self.sail_speeds(self.polar_twa(heading.twa(wind.direction)), &wind.speed, current_sail, is_in_ice_limits, filter, auto_sail_change, tws_indices, twa_indices)
+    }
 
-        let mut twa = heading.twa(wind.direction);
-        if twa < 0.0 {
-            twa = -1.0 * twa
-        }
-        if twa > 180.0 {
-            twa = 360.0 - twa
-        }
+    fn sail_speeds(&self, twa: f64, wind_speed: &Speed, current_sail: &position::Sail, is_in_ice_limits: bool, filter: SailFilter, auto_sail_change: bool, tws_indices: (usize, usize, f64), twa_indices: (usize, usize, f64)) -> Vec<PolarResult> {
 
         let mut boat_speed_max = Speed::from_kts(0.0);
 
@@ -157,9 +256,12 @@ impl Polar {
             }
             // TODO : manage hull option
             boat_speed *= self.hull.speed_ratio;
-            let foil = self.foil_amount(twa, &wind.speed);
+            let foil = self.foil_amount(twa.abs(), wind_speed);
             // TODO : manage foil option
             boat_speed *= foil;
+            if boat_speed < self.min_boat_speed {
+                boat_speed = self.min_boat_speed.clone();
+            }
 
             if boat_speed_max.kts() < boat_speed.kts() {
                 boat_speed_max = boat_speed.clone();
@@ -170,7 +272,7 @@ impl Polar {
 
         speeds.into_iter().map(|(s, boat_speed, foil)| {
 
-            if &s == current_sail {
+            if auto_sail_change && &s == current_sail {
                 let boost = boat_speed_max.kts() / boat_speed.kts();
                 if boost <= self.auto_sail_change_tolerance {
                     return PolarResult {
@@ -190,7 +292,37 @@ impl Polar {
                 boost: 0,
                 best: boat_speed.kts() / boat_speed_max.kts()
             }
-        }).filter(|res| res.best >= if all { 0.0 } else { 0.5 }).collect()
+        }).filter(|res| filter.keeps(res.best)).collect()
+    }
+
+    /// Default filter of the sails worth considering, see `bad_sail_ratio`.
+    pub(crate) fn sail_filter(&self) -> SailFilter {
+        SailFilter::Threshold(self.bad_sail_ratio())
+    }
+
+    /// Whether the speeds are given over -180..180 twa, port and starboard differing.
+    pub(crate) fn is_asymmetric(&self) -> bool {
+        self.twa.first().is_some_and(|twa| *twa < 0.0)
+    }
+
+    /// `twa` as indexed in the speeds, mirrored to 0..180 unless asymmetric.
+    fn polar_twa(&self, twa: f64) -> f64 {
+        if self.is_asymmetric() {
+            twa
+        } else {
+            twa.abs()
+        }
+    }
+
+    /// Lowest ratio to the best speed of the sails worth considering.
+    ///
+    /// `bad_sail_tolerance` is only used when in (0, 1], defaulting to 0.5.
+    fn bad_sail_ratio(&self) -> f64 {
+        if self.bad_sail_tolerance > 0.0 && self.bad_sail_tolerance <= 1.0 {
+            self.bad_sail_tolerance
+        } else {
+            0.5
+        }
     }
 
     pub(crate) fn get_boat_speed(&self, heading: &Heading, wind: &Wind, using_sail: Option<&position::Sail>, current_sail: &position::Sail, is_in_ice_limits: bool) -> PolarResult {
@@ -204,18 +336,12 @@ impl Polar {
         let mut max_boat_speed: Speed = Default::default();
         let mut best = PolarResult::default();
 
-        let mut twa = heading.twa(wind.direction);
-        if twa < 0.0 {
-            twa = -1.0 * twa
-        }
-        if twa > 180.0 {
-            twa = 360.0 - twa
-        }
+        let twa = self.polar_twa(heading.twa(wind.direction));
 
         let tws_indices = Self::interpolation_index(&self.tws, wind.speed.kts());
         let twa_indices = Self::interpolation_index(&self.twa, twa);
 
-        for polar_result in self.get_boat_speeds(heading, wind, current_sail, is_in_ice_limits, true, tws_indices, twa_indices).into_iter() {
+        for polar_result in self.get_boat_speeds(heading, wind, current_sail, is_in_ice_limits, SailFilter::All, true, tws_indices, twa_indices).into_iter() {
             if using_sail.as_ref().is_some_and(|using_sail| {
                 &&polar_result.sail != using_sail
             }) {
@@ -230,6 +356,43 @@ impl Polar {
         best
     }
 
+    /// Boat speed at `twa` in a `tws` wind, with the best sail or the `sail` index.
+    ///
+    /// Of the options, only `manual_sails` is applied yet.
+    pub(crate) fn speed_at(&self, twa: f64, tws: Speed, sail: Option<usize>, boat_options: &BoatOptions, ice: bool) -> PolarResult {
+        let twa = self.polar_twa(twa);
+        let current_sail = position::Sail::from_index(sail.unwrap_or(0));
+
+        let tws_indices = Self::interpolation_index(&self.tws, tws.kts());
+        let twa_indices = Self::interpolation_index(&self.twa, twa);
+
+        let mut max_boat_speed: Speed = Default::default();
+        let mut best = PolarResult::default();
+
+        for polar_result in self.sail_speeds(twa, &tws, &current_sail, ice, SailFilter::All, !boat_options.manual_sails, tws_indices, twa_indices).into_iter() {
+            if sail.is_some_and(|sail| polar_result.sail.index != sail) {
+                continue;
+            }
+            if polar_result.speed > max_boat_speed {
+                max_boat_speed = polar_result.speed.clone();
+                best = polar_result;
+            }
+        }
+
+        best
+    }
+
+    /// Speed of each sail at every twa of the polar in a `tws` wind, as given by `speed_at` for that sail.
+    pub(crate) fn speed_grid(&self, tws: Speed, boat_options: &BoatOptions, ice: bool) -> Vec<(f64, Vec<(usize, Speed)>)> {
+        self.twa.iter().map(|&twa| {
+            let speeds = (0..self.sail.len())
+                .map(|sail| (sail, self.speed_at(twa, tws.clone(), Some(sail), boat_options, ice).speed))
+                .collect();
+
+            (twa, speeds)
+        }).collect()
+    }
+
     fn  get_boat_speed_from_wind_index(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool, tws_indices: (usize, usize, f64), twa: f64) -> (Speed, position::Sail, f64) {
         let (twa_index_0, twa_index_1, twa_factor) = Self::interpolation_index(&self.twa, twa);
 
@@ -266,11 +429,43 @@ impl Polar {
         let foil = self.foil_amount(twa, wind_speed);
         // TODO : manage foil option
         max_boat_speed *= foil;
+        if max_boat_speed < self.min_boat_speed {
+            max_boat_speed = self.min_boat_speed.clone();
+        }
 
         (max_boat_speed, best_sail, foil)
     }
 
-    pub(crate) fn get_vmg(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool) -> Vmgs {
+    fn vmg_at(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool, tws_indices: (usize, usize, f64), twa: f64) -> Vmg {
+        let (max_boat_speed, best_sail, _) = self.get_boat_speed_from_wind_index(wind_speed, using_sail, is_in_ice_limits, tws_indices, twa);
+
+        Vmg {
+            twa,
+            sail: best_sail,
+            vmg: Speed::from_kts(max_boat_speed.kts() * (twa.to_radians().cos())),
+        }
+    }
+
+    /// Heading with the best speed projected on `bearing_to_dest`, sweeping twa by 0.1°, and that projected speed.
+    pub(crate) fn best_heading_to(&self, wind: &Wind, bearing_to_dest: f64, sail: Option<usize>, boat_options: &BoatOptions, ice: bool) -> (Heading, Speed) {
+        let mut best = (Heading::HEADING(bearing_to_dest), Speed::from_kts(f64::MIN));
+
+        for twa in (-1799..=1800).map(|twa| twa as f64 / 10.0) {
+            let heading = Heading::TWA(twa).heading(wind.direction);
+            let speed = self.speed_at(twa, wind.speed.clone(), sail, boat_options, ice).speed;
+            let vmg = Speed::from_kts(speed.kts() * (heading - bearing_to_dest).to_radians().cos());
+
+            if vmg > best.1 {
+                best = (Heading::HEADING(heading), vmg);
+            }
+        }
+
+        best
+    }
+
+    /// Best upwind and downwind vmg, sweeping twa from 0 to 180 by 0.1°.
+    #[cfg_attr(feature = "rayon", allow(dead_code))]
+    pub(crate) fn vmg_sweep(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool) -> (Vmg, Vmg) {
 
         let mut upwind_vmg = Vmg {
             twa: 0.0,
@@ -287,24 +482,62 @@ impl Polar {
         let tws_indices = Self::interpolation_index(&self.tws, wind_speed.kts());
 
         for twa in 0..1801 {
-            let twa = twa as f64 / 10.0;
-
-            let (max_boat_speed, best_sail, _) = self.get_boat_speed_from_wind_index(wind_speed, using_sail, is_in_ice_limits, tws_indices, twa);
+            let vmg = self.vmg_at(wind_speed, using_sail, is_in_ice_limits, tws_indices, twa as f64 / 10.0);
 
-            let vmg = Speed::from_kts(max_boat_speed.kts() * (twa.to_radians().cos()));
-
-            if vmg > upwind_vmg.vmg {
-                upwind_vmg.twa = twa;
-                upwind_vmg.sail = best_sail.clone();
-                upwind_vmg.vmg = vmg.clone();
+            if vmg.vmg > upwind_vmg.vmg {
+                upwind_vmg = vmg.clone();
             }
-            if vmg <= downwind_vmg.vmg.clone() {
-                downwind_vmg.twa = twa;
-                downwind_vmg.sail = best_sail;
-                downwind_vmg.vmg = vmg;
+            if vmg.vmg <= downwind_vmg.vmg {
+                downwind_vmg = vmg;
             }
         }
 
+        (upwind_vmg, downwind_vmg)
+    }
+
+    /// Same as `vmg_sweep`, twa being evaluated in parallel.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_vmg_sweep(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool) -> (Vmg, Vmg) {
+        use rayon::prelude::*;
+
+        let tws_indices = Self::interpolation_index(&self.tws, wind_speed.kts());
+        let zero = Speed::default();
+
+        // upwind : highest vmg > 0, lowest twa first. Downwind : lowest vmg <= 0, highest twa first.
+        let (upwind_vmg, downwind_vmg) = (0..1801).into_par_iter()
+            .map(|twa| {
+                let vmg = self.vmg_at(wind_speed, using_sail, is_in_ice_limits, tws_indices, twa as f64 / 10.0);
+                (Some(vmg.clone()).filter(|v| v.vmg > zero), Some(vmg).filter(|v| v.vmg <= zero))
+            })
+            .reduce(|| (None, None), |(upwind_a, downwind_a), (upwind_b, downwind_b)| (
+                Self::best_vmg(upwind_a, upwind_b, |a, b| b.vmg > a.vmg || b.vmg == a.vmg && b.twa < a.twa),
+                Self::best_vmg(downwind_a, downwind_b, |a, b| b.vmg < a.vmg || b.vmg == a.vmg && b.twa > a.twa),
+            ));
+
+        (
+            upwind_vmg.unwrap_or(Vmg { twa: 0.0, sail: position::Sail::from_index(0), vmg: Default::default() }),
+            downwind_vmg.unwrap_or(Vmg { twa: 180.0, sail: position::Sail::from_index(0), vmg: Default::default() }),
+        )
+    }
+
+    #[cfg(feature = "rayon")]
+    fn best_vmg(a: Option<Vmg>, b: Option<Vmg>, b_is_better: impl Fn(&Vmg, &Vmg) -> bool) -> Option<Vmg> {
+        match (a, b) {
+            (Some(a), Some(b)) => if b_is_better(&a, &b) { Some(b) } else { Some(a) },
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+
+    pub(crate) fn get_vmg(&self, wind_speed: &Speed, using_sail: Option<&position::Sail>, is_in_ice_limits: bool) -> Vmgs {
+
+        #[cfg(feature = "rayon")]
+        let (upwind_vmg, downwind_vmg) = self.par_vmg_sweep(wind_speed, using_sail, is_in_ice_limits);
+        #[cfg(not(feature = "rayon"))]
+        let (upwind_vmg, downwind_vmg) = self.vmg_sweep(wind_speed, using_sail, is_in_ice_limits);
+
+        let tws_indices = Self::interpolation_index(&self.tws, wind_speed.kts());
+
         // try to optim vmg
         let mut optimized_upwind_vmg = None;
         let upwind_vmg_twa = upwind_vmg.twa.clone();
@@ -389,7 +622,8 @@ impl Polar {
         let stamina_coef = match boat_options.stamina {
             false => 1.0,
             true => {
-                0.5 + (100.0 - stamina) / 100.0 * 1.5
+                let model = &self.stamina;
+                model.rested_penalty_coef + (100.0 - stamina) / 100.0 * (model.exhausted_penalty_coef - model.rested_penalty_coef)
             }
         };
 
@@ -427,24 +661,16 @@ impl Polar {
     pub(crate) fn tired(&self, stamina: f64, previous_twa: f64, new_twa: f64, previous_sail: &position::Sail, new_sail: &position::Sail, wind_speed: &Speed) -> f64 {
         let mut stamina = stamina;
 
-        let stamina_coef = if wind_speed.kts() <= 10.0 {
-            1.0 + wind_speed.kts() / 10.0 * 0.25
-        } else if wind_speed.kts() <= 20.0 {
-            1.25 + (wind_speed.kts() - 10.0) / 10.0 * 0.25
-        } else if wind_speed.kts() <= 30.0 {
-            1.5 + (wind_speed.kts() - 20.0) / 10.0 * 0.5
-        } else {
-            2.0
-        };
+        let stamina_coef = self.stamina.tiredness_coef(&self.wind_thresholds, wind_speed);
 
         if previous_twa * new_twa < 0.0 && new_twa.abs() <= 90.0 {
-            stamina = stamina - 10.0 * stamina_coef;
+            stamina = stamina - self.stamina.tack * stamina_coef;
         } else if previous_twa * new_twa < 0.0 && new_twa.abs() > 90.0 {
-            stamina = stamina - 10.0 * stamina_coef;
+            stamina = stamina - self.stamina.gybe * stamina_coef;
         }
 
         if previous_sail != new_sail {
-            stamina = stamina - 20.0 * stamina_coef;
+            stamina = stamina - self.stamina.sail_change * stamina_coef;
         }
 
         stamina = stamina.max(0.0);
@@ -455,12 +681,15 @@ impl Polar {
     pub(crate) fn recovers(&self, stamina: f64, duration: &Duration, wind_speed: &Speed) -> f64 {
         let mut stamina = stamina;
 
-        let recovery_time = if wind_speed.kts() <= 0.0 {
-            5.0
-        } else if wind_speed.kts() >= 30.0 {
-            15.0
+        let model = &self.stamina;
+        let lws = model.recovery_lws.unwrap_or(self.wind_thresholds.recovery_lws);
+        let hws = model.recovery_hws.unwrap_or(self.wind_thresholds.recovery_hws);
+        let recovery_time = if wind_speed.kts() <= lws {
+            model.lw_recovery_minutes
+        } else if wind_speed.kts() >= hws {
+            model.hw_recovery_minutes
         } else {
-            Self::interpolation(0.0, 30.0, 5.0, 15.0, wind_speed.kts())
+            Self::interpolation(lws, hws, model.lw_recovery_minutes, model.hw_recovery_minutes, wind_speed.kts())
         };
 
         let recovery = duration.num_minutes() as f64 / recovery_time;
@@ -562,6 +791,7 @@ pub(crate) struct Polar {
     pub(crate) global_speed_ratio: f64,
     pub(crate) ice_speed_ratio: f64,
     pub(crate) auto_sail_change_tolerance: f64,
+    #[serde(default)]
     pub(crate) bad_sail_tolerance: f64,
     pub(crate) max_speed: f64,
     pub(crate) foil: Foil,
@@ -570,6 +800,107 @@ pub(crate) struct Polar {
     pub(crate) tws: Vec<f64>,
     pub(crate) twa: Vec<f64>,
     pub(crate) sail: Vec<PolarSail>,
+    #[serde(default)]
+    pub(crate) stamina: StaminaModel,
+    /// Floor of the boat speed, so that routing doesn't stall in light air.
+    #[serde(default)]
+    #[tsify(type = "number")]
+    pub(crate) min_boat_speed: Speed,
+    #[serde(default)]
+    pub(crate) wind_thresholds: WindThresholds,
+}
+
+/// Wind speeds, in knots, at which the stamina computations change.
+#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct WindThresholds {
+    /// Stops, by increasing tws, of the `Tiredness::Coefs` coefficients.
+    pub(crate) tiredness: Vec<f64>,
+    /// Recovery is the fastest at `recovery_lws` and below, the slowest at `recovery_hws` and above.
+    pub(crate) recovery_lws: f64,
+    pub(crate) recovery_hws: f64,
+}
+
+impl Default for WindThresholds {
+    fn default() -> Self {
+        Self {
+            tiredness: vec![0.0, 10.0, 20.0, 30.0],
+            recovery_lws: 0.0,
+            recovery_hws: 30.0,
+        }
+    }
+}
+
+/// How maneuvers tire the crew and how fast it recovers.
+#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct StaminaModel {
+    /// Stamina points lost by a tack, a gybe or a sail change, before the wind coefficient.
+    pub(crate) tack: f64,
+    pub(crate) gybe: f64,
+    pub(crate) sail_change: f64,
+    /// Wind coefficient applied to the losses.
+    pub(crate) tiredness: Tiredness,
+    /// Minutes to recover a point, in light and in heavy wind.
+    pub(crate) lw_recovery_minutes: f64,
+    pub(crate) hw_recovery_minutes: f64,
+    /// Former place of `WindThresholds::recovery_lws` and `recovery_hws`, still taking precedence when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) recovery_lws: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) recovery_hws: Option<f64>,
+    /// Coefficient of the penalty durations at full and at no stamina.
+    pub(crate) rested_penalty_coef: f64,
+    pub(crate) exhausted_penalty_coef: f64,
+}
+
+impl Default for StaminaModel {
+    fn default() -> Self {
+        Self {
+            tack: 10.0,
+            gybe: 10.0,
+            sail_change: 20.0,
+            tiredness: Tiredness::Coefs(vec![1.0, 1.25, 1.5, 2.0]),
+            lw_recovery_minutes: 5.0,
+            hw_recovery_minutes: 15.0,
+            recovery_lws: None,
+            recovery_hws: None,
+            rested_penalty_coef: 0.5,
+            exhausted_penalty_coef: 2.0,
+        }
+    }
+}
+
+/// Wind coefficients of the stamina losses, by increasing tws.
+#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(untagged)]
+pub(crate) enum Tiredness {
+    /// `(tws, coef)` stops.
+    Stops(Vec<(f64, f64)>),
+    /// Coefficient at each `WindThresholds::tiredness` stop, as many as there are stops.
+    Coefs(Vec<f64>),
+}
+
+impl StaminaModel {
+    fn tiredness_coef(&self, thresholds: &WindThresholds, wind_speed: &Speed) -> f64 {
+        let tws = wind_speed.kts();
+        let stops = match &self.tiredness {
+            Tiredness::Stops(stops) => stops.clone(),
+            Tiredness::Coefs(coefs) => thresholds.tiredness.iter().cloned().zip(coefs.iter().cloned()).collect::<Vec<_>>(),
+        };
+
+        match stops.iter().position(|(stop, _)| *stop >= tws) {
+            None => stops.last().map_or(1.0, |(_, coef)| *coef),
+            Some(0) => stops[0].1,
+            Some(i) => {
+                let ((low, low_coef), (high, high_coef)) = (stops[i - 1], stops[i]);
+                low_coef + (tws - low) / (high - low) * (high_coef - low_coef)
+            }
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Tsify)]
@@ -1,22 +1,77 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::ops::Sub;
+use anyhow::{bail, Result};
 use chrono::Duration;
 use serde::{Serialize, Deserialize, Serializer, de, Deserializer};
 use serde::de::Visitor;
 use tsify_next::Tsify;
 use wasm_bindgen::prelude::*;
+use crate::algorithm::Algorithm;
+use crate::algorithm::great_circle::GreatCircle;
+use crate::algorithm::spherical::Spherical;
 use crate::polar::Vmgs;
 use crate::router;
-use crate::utils::Speed;
+use crate::utils::{Distance, Speed};
 use crate::wind::Wind;
 
 
 #[derive(Clone, Default, Debug, Serialize, Tsify, Deserialize, PartialEq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub(crate) struct Coords {
-    pub(crate) lat: f64,
-    pub(crate) lon: f64,
+pub struct Coords {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Coords {
+    /// Fails if `lat` is outside [-90, 90], normalizes `lon` into [-180, 180).
+    pub(crate) fn new(lat: f64, lon: f64) -> Result<Coords> {
+        if !(-90.0..=90.0).contains(&lat) {
+            bail!("Latitude {lat} out of [-90, 90]");
+        }
+
+        Ok(Coords { lat, lon }.normalized())
+    }
+
+    pub(crate) fn normalized(self) -> Coords {
+        Coords {
+            lat: self.lat,
+            lon: (self.lon + 180.0).rem_euclid(360.0) - 180.0,
+        }
+    }
+
+    /// Point reached following the rhumb line `heading` for `distance`.
+    pub fn rhumb_destination(&self, heading: f64, distance: Distance) -> Coords {
+        Spherical {}.destination(self, heading, &distance)
+    }
+
+    /// Constant bearing of the rhumb line to `to`.
+    pub fn rhumb_bearing_to(&self, to: &Coords) -> f64 {
+        Spherical {}.heading_to(self, to)
+    }
+
+    pub fn rhumb_distance_to(&self, to: &Coords) -> Distance {
+        Spherical {}.distance_to(self, to)
+    }
+
+    /// Point reached following the great circle of initial `heading` for `distance`.
+    pub fn great_circle_destination(&self, heading: f64, distance: Distance) -> Coords {
+        GreatCircle {}.destination(self, heading, &distance)
+    }
+
+    /// Initial bearing of the great circle to `to`.
+    pub fn great_circle_bearing_to(&self, to: &Coords) -> f64 {
+        GreatCircle {}.heading_to(self, to)
+    }
+
+    pub fn great_circle_distance_to(&self, to: &Coords) -> Distance {
+        GreatCircle {}.distance_to(self, to)
+    }
+
+    /// Bearing of the great circle to `to` when arriving there.
+    pub fn great_circle_final_bearing_to(&self, to: &Coords) -> f64 {
+        (GreatCircle {}.heading_to(to, self) + 180.0).rem_euclid(360.0)
+    }
 }
 
 impl From<(f64, f64)> for Coords {
@@ -24,7 +79,7 @@ impl From<(f64, f64)> for Coords {
         Coords {
             lat: latlon.0,
             lon: latlon.1,
-        }
+        }.normalized()
     }
 }
 
@@ -33,7 +88,7 @@ impl From<[f64; 2]> for Coords {
         Coords {
             lat: latlon[0],
             lon: latlon[1],
-        }
+        }.normalized()
     }
 }
 
@@ -132,34 +187,43 @@ pub(crate) struct Penalties {
     pub(crate) tack: Option<Penalty>,
 }
 
+/// Kinds of penalty a boat can carry, in the order they are merged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PenaltyKind {
+    Gybe,
+    SailChange,
+    Tack,
+}
+
+impl PenaltyKind {
+    pub(crate) const ALL: [PenaltyKind; 3] = [PenaltyKind::Gybe, PenaltyKind::SailChange, PenaltyKind::Tack];
+}
+
 impl Into<Vec<router::Penalty>> for Penalties {
     fn into(self) -> Vec<router::Penalty> {
 
-        let mut res = Vec::new();
+        let mut res = self.iter()
+            .filter(|(_, penalty)| penalty.duration > Duration::zero())
+            .map(|(kind, penalty)| router::Penalty::from((kind, penalty.clone())))
+            .collect::<Vec<_>>();
 
-        self.gybe.map(|g| if g.duration > Duration::zero() {
-            res.push(router::Penalty {
-                duration: g.duration.clone(),
-                ratio: g.ratio,
-                typ: 1
-            });
-        });
-
-        self.tack.map(|g| if g.duration > Duration::zero() {
-            res.push(router::Penalty {
-                duration: g.duration.clone(),
-                ratio: g.ratio,
-                typ: 2
-            });
-        });
+        res.sort_by_key(|penalty| penalty.typ);
+        res
+    }
+}
 
-        self.sail_change.map(|g| if g.duration > Duration::zero() {
-            res.push(router::Penalty {
-                duration: g.duration.clone(),
-                ratio: g.ratio,
-                typ: 4
-            });
-        });
+impl From<Vec<router::Penalty>> for Penalties {
+    fn from(penalties: Vec<router::Penalty>) -> Self {
+        let mut res = Penalties::new();
+
+        for penalty in penalties {
+            let kind = match penalty.typ {
+                router::PenaltyType::Gybe => PenaltyKind::Gybe,
+                router::PenaltyType::Tack => PenaltyKind::Tack,
+                router::PenaltyType::SailChange => PenaltyKind::SailChange,
+            };
+            *res.get_mut(kind) = Some(Penalty { duration: penalty.duration, ratio: penalty.ratio });
+        }
 
         res
     }
@@ -174,59 +238,61 @@ impl Penalties {
         }
     }
 
-    pub(crate) fn is_some(&self) -> bool {
-        self.gybe.as_ref().is_some_and(|p| !p.duration.is_zero()) || self.sail_change.as_ref().is_some_and(|p| !p.duration.is_zero()) || self.tack.as_ref().is_some_and(|p| !p.duration.is_zero())
+    pub(crate) fn get(&self, kind: PenaltyKind) -> Option<&Penalty> {
+        match kind {
+            PenaltyKind::Gybe => self.gybe.as_ref(),
+            PenaltyKind::SailChange => self.sail_change.as_ref(),
+            PenaltyKind::Tack => self.tack.as_ref(),
+        }
     }
 
-    pub(crate) fn min_penalty_duration(&self) -> Option<Duration> {
-
-        let mut min = None;
+    fn get_mut(&mut self, kind: PenaltyKind) -> &mut Option<Penalty> {
+        match kind {
+            PenaltyKind::Gybe => &mut self.gybe,
+            PenaltyKind::SailChange => &mut self.sail_change,
+            PenaltyKind::Tack => &mut self.tack,
+        }
+    }
 
-        self.gybe.as_ref().map(|gybe| {
-            if !min.as_ref().is_some_and(|min| min <= &gybe.duration) {
-                min.replace(gybe.duration.clone());
-            }
-        });
+    /// Present penalties, in `PenaltyKind::ALL` order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (PenaltyKind, &Penalty)> + '_ {
+        PenaltyKind::ALL.into_iter().filter_map(move |kind| self.get(kind).map(|penalty| (kind, penalty)))
+    }
 
-        self.sail_change.as_ref().map(|sail_change| {
-            if !min.as_ref().is_some_and(|min| min <= &sail_change.duration) {
-                min.replace(sail_change.duration.clone());
-            }
-        });
+    fn map(&self, mut f: impl FnMut(&Penalty) -> Option<Penalty>) -> Self {
+        let mut res = Self::new();
+        for (kind, penalty) in self.iter() {
+            *res.get_mut(kind) = f(penalty);
+        }
+        res
+    }
 
-        self.tack.as_ref().map(|tack| {
-            if !min.as_ref().is_some_and(|min| min <= &tack.duration) {
-                min.replace(tack.duration.clone());
-            }
-        });
+    pub(crate) fn is_some(&self) -> bool {
+        self.iter().any(|(_, p)| !p.duration.is_zero())
+    }
 
-        min
+    pub(crate) fn min_penalty_duration(&self) -> Option<Duration> {
+        self.iter().map(|(_, p)| p.duration.clone()).min()
     }
 
     pub(crate) fn duration(&self) -> Duration {
-        self.gybe.as_ref().map_or(Duration::zero(), |p| p.duration.clone()).max(
-            self.sail_change.as_ref().map_or(Duration::zero(), |p| p.duration.clone()).max(
-            self.tack.as_ref().map_or(Duration::zero(), |p| p.duration.clone())))
+        self.iter().map(|(_, p)| p.duration.clone()).fold(Duration::zero(), Duration::max)
     }
 
     pub(crate) fn navigate(&self, duration: Duration) -> (Self, f64) {
 
         let mut ratio = 1.0;
 
-        (Self {
-            gybe: self.gybe.as_ref().and_then(|gybe| { ratio *= gybe.ratio; if gybe.duration <= duration { None } else { Some(Penalty { duration: gybe.duration - duration, ratio: gybe.ratio })}}),
-            sail_change: self.sail_change.as_ref().and_then(|sail_change| { ratio *= sail_change.ratio; if sail_change.duration <= duration { None } else { Some(Penalty { duration: sail_change.duration - duration, ratio: sail_change.ratio })}}),
-            tack: self.tack.as_ref().and_then(|tack| { ratio *= tack.ratio; if tack.duration <= duration { None } else { Some(Penalty { duration: tack.duration - duration, ratio: tack.ratio })}}),
-        }, ratio)
+        let penalties = self.map(|penalty| {
+            ratio *= penalty.ratio;
+            penalty.elapse(duration)
+        });
+
+        (penalties, ratio)
     }
 
     pub(crate) fn to_vec(&self) -> Vec<Penalty> {
-
-        let penalties = Self::merge_penalty(Vec::new(), 0, self.gybe.clone());
-        let penalties = Self::merge_penalty(penalties, 0, self.sail_change.clone());
-        let penalties = Self::merge_penalty(penalties, 0, self.tack.clone());
-
-        penalties
+        self.iter().fold(Vec::new(), |penalties, (_, penalty)| Self::merge_penalty(penalties, 0, Some(penalty.clone())))
     }
 
     fn merge_penalty(penalties: Vec<Penalty>, index: usize, penalty: Option<Penalty>) -> Vec<Penalty> {
@@ -259,7 +325,7 @@ impl Penalties {
     }
 
     pub(crate) fn _total(&self) -> Duration {
-        self.gybe.as_ref().map_or(Duration::zero(), |p| p.duration.clone()) + self.sail_change.as_ref().map_or(Duration::zero(), |p| p.duration.clone()) + self.tack.as_ref().map_or(Duration::zero(), |p| p.duration.clone())
+        self.iter().fold(Duration::zero(), |total, (_, p)| total + p.duration)
     }
 }
 
@@ -267,12 +333,7 @@ impl Sub<Duration> for Penalties {
     type Output = Self;
 
     fn sub(self, rhs: Duration) -> Self::Output {
-
-        Self {
-            gybe: self.gybe.and_then(|p| if p.duration <= rhs { None } else { Some(Penalty { duration: p.duration - rhs, ratio: p.ratio }) } ),
-            sail_change: self.sail_change.and_then(|p| if p.duration <= rhs { None } else { Some(Penalty { duration: p.duration - rhs, ratio: p.ratio }) } ),
-            tack: self.tack.and_then(|p| if p.duration <= rhs { None } else { Some(Penalty { duration: p.duration - rhs, ratio: p.ratio }) } )
-        }
+        self.map(|p| p.elapse(rhs))
     }
 }
 
@@ -284,6 +345,17 @@ pub(crate) struct Penalty {
     pub(crate) ratio: f64,
 }
 
+impl Penalty {
+    /// Remaining penalty once `duration` has elapsed.
+    fn elapse(&self, duration: Duration) -> Option<Penalty> {
+        if self.duration <= duration {
+            None
+        } else {
+            Some(Penalty { duration: self.duration - duration, ratio: self.ratio })
+        }
+    }
+}
+
 fn duration_to_seconds<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer {
     serializer.serialize_i64(duration.num_seconds())
@@ -362,6 +434,10 @@ fn seconds_to_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     deserializer.deserialize_i64(DurationVisitor)
 }
 
+/// Course to steer, either a true heading in [0, 360) or a twa.
+///
+/// Twa are always read in (-180, 180] : dead downwind is +180, never -180,
+/// so that mirroring with `abs()` gives a polar twa in [0, 180].
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[serde(rename_all = "lowercase")]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -398,18 +474,18 @@ impl Heading {
 
     pub(crate) fn twa(&self, twd: f64) -> f64 {
         match self {
-            Heading::HEADING(heading) => {
-                let mut twa = twd - heading;
-                if twa <= -180.0 {
-                    twa += 360.0
-                }
-                if twa > 180.0 {
-                    twa -= 360.0
-                }
+            Heading::HEADING(heading) => Self::normalize_twa(twd - heading),
+            Heading::TWA(twa) => Self::normalize_twa(*twa),
+        }
+    }
 
-                twa
-            },
-            Heading::TWA(twa) => twa.clone(),
+    /// Brings any angle into (-180, 180].
+    fn normalize_twa(twa: f64) -> f64 {
+        let twa = twa.rem_euclid(360.0);
+        if twa > 180.0 {
+            twa - 360.0
+        } else {
+            twa
         }
     }
 }
@@ -450,6 +526,9 @@ impl Display for Heading {
 impl Display for Sail {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let sails = ["Jib", "Spi", "Staysail", "LightJib", "Code0", "HeavyGnk", "LightGnk"];
-        write!(f, "{}{}", sails[self.index], if self.auto {"*"} else {""})
+        match sails.get(self.index) {
+            Some(name) => write!(f, "{}{}", name, if self.auto {"*"} else {""}),
+            None => write!(f, "Sail{}{}", self.index, if self.auto {"*"} else {""}),
+        }
     }
 }
@@ -1,12 +1,15 @@
 use std::collections::HashMap;
+use std::io::Read;
 use std::sync::{Arc, RwLock};
 
 use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
-use log::info;
-use serde::{Serialize, Deserialize};
+use chrono::serde::ts_milliseconds_option;
+use log::{debug, info};
+use serde::{de, Serialize, Deserialize, Deserializer};
 use tsify_next::Tsify;
 use crate::algorithm::Algorithm;
+use crate::error::PhtError;
 use crate::algorithm::spherical::Spherical;
 use crate::position::Coords;
 use crate::utils::Distance;
@@ -21,6 +24,10 @@ pub(crate) trait RacesSpec {
     fn get(&self, name: &String) -> Result<Race>;
 
     fn set(&self, name: String, race: Race);
+
+    fn remove(&self, name: &String) -> Result<()>;
+
+    fn clear(&self);
 }
 
 impl RacesSpec for Races {
@@ -37,7 +44,7 @@ impl RacesSpec for Races {
         let races = self.read().unwrap();
         match races.get(name) {
             Some(race) => Ok(race.clone()),
-            None => bail!("Race {name} not found"),
+            None => bail!(PhtError::RaceNotFound(name.clone())),
         }
     }
     
@@ -46,10 +53,21 @@ impl RacesSpec for Races {
         races.insert(name, race);
     }
 
-    
+    fn remove(&self, name: &String) -> Result<()> {
+        let mut races = self.write().unwrap();
+        match races.remove(name) {
+            Some(_) => Ok(()),
+            None => bail!(PhtError::RaceNotFound(name.clone())),
+        }
+    }
+
+    fn clear(&self) {
+        let mut races = self.write().unwrap();
+        races.clear();
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct Race {
     pub(crate) id: String,
@@ -72,7 +90,7 @@ pub struct Race {
     pub(crate) ice_limits: Option<Limits>,
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub(crate) struct Limits {
     pub(crate) north: Vec<Coords>,
@@ -83,7 +101,62 @@ pub(crate) struct Limits {
     pub(crate) min_lat: f64,
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+impl Limits {
+    pub(crate) fn is_in(&self, pos: &Coords) -> bool {
+        if pos.lat > self.max_lat || pos.lat < self.min_lat {
+            return true
+        }
+
+        let lon = Self::normalize_lon(pos.lon);
+
+        if Self::lat_at(&self.north, lon).is_some_and(|lat| pos.lat > lat) {
+            return true
+        }
+
+        Self::lat_at(&self.south, lon).is_some_and(|lat| pos.lat < lat)
+    }
+
+    /// Whether the segment from `from` to `to` crosses the north or the south line.
+    pub(crate) fn crosses(&self, from: &Coords, to: &Coords, algorithm: &dyn Algorithm) -> bool {
+        self.north.windows(2).chain(self.south.windows(2))
+            .any(|segment| segments_cross((from, to), (&segment[0], &segment[1]), algorithm))
+    }
+
+    fn lat_at(line: &[Coords], lon: f64) -> Option<f64> {
+        for segment in line.windows(2) {
+            let (west, east) = if segment[0].lon <= segment[1].lon {
+                (&segment[0], &segment[1])
+            } else {
+                (&segment[1], &segment[0])
+            };
+
+            if west.lon <= lon && lon <= east.lon {
+                if east.lon == west.lon {
+                    return Some(west.lat.min(east.lat))
+                }
+
+                return Some(west.lat + (lon - west.lon) * (east.lat - west.lat) / (east.lon - west.lon))
+            }
+        }
+
+        None
+    }
+
+    fn normalize_lon(lon: f64) -> f64 {
+        let mut lon = lon;
+        while lon < -180.0 {
+            lon += 360.0
+        }
+        while lon > 180.0 {
+            lon -= 360.0
+        }
+
+        lon
+    }
+}
+
+/// Internally tagged : `{"type": "Zone" | "Door" | "Waypoint", ...fields}`.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 #[serde(tag = "type")]
 pub(crate) enum Buoy {
@@ -101,6 +174,47 @@ impl Buoy {
         }
     }
 
+    pub(crate) fn destination(&self) -> &Coords {
+        match self {
+            Buoy::Zone(zone) => &zone.destination,
+            Buoy::Door(door) => &door.destination,
+            Buoy::Waypoint(waypoint) => &waypoint.destination,
+        }
+    }
+
+    /// Whether sailing from `from` to `to` passes the buoy.
+    pub(crate) fn is_passed_by(&self, from: &Coords, to: &Coords, algorithm: &dyn Algorithm) -> bool {
+        match self {
+            Buoy::Zone(zone) => zone.is_in(to),
+            Buoy::Door(door) => segments_cross((from, to), (&door.port, &door.starboard), algorithm),
+            Buoy::Waypoint(waypoint) => algorithm.distance_to(to, &waypoint.destination) < Distance::from_nm(0.01),
+        }
+    }
+
+    pub(crate) fn is_optional(&self) -> bool {
+        match self {
+            Buoy::Zone(zone) => zone.optional,
+            Buoy::Door(door) => door.optional,
+            Buoy::Waypoint(waypoint) => waypoint.optional,
+        }
+    }
+
+    pub(crate) fn accuracy_override(&self) -> Option<f64> {
+        match self {
+            Buoy::Zone(zone) => zone.accuracy_override,
+            Buoy::Door(door) => door.accuracy_override,
+            Buoy::Waypoint(waypoint) => waypoint.accuracy_override,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &String {
+        match self {
+            Buoy::Zone(zone) => &zone.name,
+            Buoy::Door(door) => &door.name,
+            Buoy::Waypoint(waypoint) => &waypoint.name,
+        }
+    }
+
     fn validate(&mut self) {
         match self {
             Buoy::Zone(circle) => circle.validated = true,
@@ -110,7 +224,52 @@ impl Buoy {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+/// Whether the great circle segments `a` and `b` cross each other, touching included.
+///
+/// Each segment has its ends on both sides of the other one, sides being the signs of the cross-track distances.
+pub(crate) fn segments_cross(a: (&Coords, &Coords), b: (&Coords, &Coords), algorithm: &dyn Algorithm) -> bool {
+    let straddles = |path: (&Coords, &Coords), ends: (&Coords, &Coords)| {
+        algorithm.cross_track_distance(path, ends.0).signed_m() * algorithm.cross_track_distance(path, ends.1).signed_m() <= 0.0
+    };
+
+    straddles(b, a) && straddles(a, b)
+}
+
+/// Twice the signed area of `(a, b, c)`, positive when counterclockwise in the (lat, lon) plane.
+fn cross(a: &Coords, b: &Coords, c: &Coords) -> f64 {
+    (b.lat - a.lat) * (c.lon - a.lon) - (b.lon - a.lon) * (c.lat - a.lat)
+}
+
+/// Below this area, in square degrees, a triangle is considered flat.
+const TRIANGLE_EPSILON: f64 = 1e-9;
+
+pub(crate) fn is_degenerate(triangle: &(Coords, Coords, Coords)) -> bool {
+    cross(&triangle.0, &triangle.1, &triangle.2).abs() / 2.0 < TRIANGLE_EPSILON
+}
+
+/// Whether `point` is in `triangle`, edges and vertices included.
+pub(crate) fn is_in_triangle(point: &Coords, triangle: &(Coords, Coords, Coords)) -> bool {
+    let (a, b, c) = triangle;
+    let sides = [cross(a, b, point), cross(b, c, point), cross(c, a, point)];
+
+    let negative = sides.iter().any(|side| *side < -TRIANGLE_EPSILON);
+    let positive = sides.iter().any(|side| *side > TRIANGLE_EPSILON);
+
+    !(negative && positive)
+}
+
+fn triangles<'de, D>(deserializer: D) -> Result<Vec<(Coords, Coords, Coords)>, D::Error>
+    where D: Deserializer<'de> {
+    let triangles = Vec::<(Coords, Coords, Coords)>::deserialize(deserializer)?;
+
+    if let Some(triangle) = triangles.iter().find(|triangle| is_degenerate(triangle)) {
+        return Err(de::Error::custom(format!("degenerate to_avoid triangle {:?}", triangle)));
+    }
+
+    Ok(triangles)
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub(crate) struct Door {
     pub(crate) name: String,
@@ -118,35 +277,78 @@ pub(crate) struct Door {
     pub(crate) starboard: Coords,
     pub(crate) departure: Coords,
     pub(crate) destination: Coords,
+    #[serde(deserialize_with = "triangles")]
     pub(crate) to_avoid: Vec<(Coords, Coords, Coords)>,
     pub(crate) validated: bool,
+    /// May be skipped when it can't be reached, routing on to the next buoy.
+    #[serde(default)]
+    pub(crate) optional: bool,
+    /// Replaces the router accuracy on the leg to this buoy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub(crate) accuracy_override: Option<f64>,
+}
+
+impl Door {
+    /// Whether `point` is past the door : facing its span, on the left looking from port to starboard.
+    pub(crate) fn is_behind(&self, point: &Coords, algorithm: &dyn Algorithm) -> bool {
+        let door = (&self.port, &self.starboard);
+        let along = algorithm.along_track_distance(door, point);
+
+        algorithm.cross_track_distance(door, point).is_negative()
+            && !along.is_negative() && along <= algorithm.distance_to(&self.port, &self.starboard)
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub(crate) struct Zone {
     pub(crate) name: String,
     pub(crate) destination: Coords,
     #[tsify(type = "number")]
     pub(crate) radius: Distance,
+    #[serde(deserialize_with = "triangles")]
     pub(crate) to_avoid: Vec<(Coords, Coords, Coords)>,
     pub(crate) validated: bool,
+    #[serde(default)]
+    pub(crate) optional: bool,
+    /// Bearings to the destination the zone must be entered with, clockwise from the first to the second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub(crate) entry_heading: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub(crate) accuracy_override: Option<f64>,
 }
 
 impl Zone {
     pub(crate) fn is_in(&self, pos: &Coords) -> bool {
         Spherical{}.distance_to(&self.destination, pos) <= self.radius
     }
+
+    /// Whether the zone may be entered heading `bearing` to its destination.
+    pub(crate) fn allows_entry(&self, bearing: f64) -> bool {
+        match self.entry_heading {
+            Some((from, to)) => (bearing - from).rem_euclid(360.0) <= (to - from).rem_euclid(360.0),
+            None => true,
+        }
+    }
 }
 
 
-#[derive(Clone, Deserialize, Serialize, Debug, Tsify)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub(crate) struct Waypoint {
     pub(crate) name: String,
     pub(crate) destination: Coords,
+    #[serde(deserialize_with = "triangles")]
     pub(crate) to_avoid: Vec<(Coords, Coords, Coords)>,
     pub(crate) validated: bool,
+    #[serde(default)]
+    pub(crate) optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub(crate) accuracy_override: Option<f64>,
 }
 
 impl Race {
@@ -155,9 +357,284 @@ impl Race {
         self.buoys.iter().filter(|w| !w.is_validated()).collect::<Vec<_>>().first().map(|w| w.clone().to_owned())
     }
 
-    pub(crate) fn validate_next_waypoint(&mut self) {
+    /// Destination of the first unvalidated buoy, `None` once finished.
+    pub fn next_destination(&self) -> Option<Coords> {
+        self.buoys.iter().find(|buoy| !buoy.is_validated()).map(|buoy| buoy.destination().clone())
+    }
+
+    /// Name of the first unvalidated buoy, `None` once finished.
+    pub fn next_buoy_name(&self) -> Option<String> {
+        self.buoys.iter().find(|buoy| !buoy.is_validated()).map(|buoy| buoy.name().clone())
+    }
+
+    /// Whether `point` is beyond the ice limits, `false` when the race has none.
+    pub fn in_ice_limits(&self, point: &Coords) -> bool {
+        self.ice_limits.as_ref().is_some_and(|limits| limits.is_in(point))
+    }
+
+    pub(crate) fn course_distance(&self, algorithm: &dyn Algorithm) -> Distance {
+        let mut distance = Distance::zero();
+        let mut from = &self.start;
+
+        for buoy in self.buoys.iter() {
+            distance = distance + algorithm.distance_to(from, buoy.destination());
+            if let Buoy::Zone(zone) = buoy {
+                distance = distance - &zone.radius;
+            }
+            from = buoy.destination();
+        }
+
+        distance
+    }
+
+    pub(crate) fn from_vr_json(bytes: &[u8]) -> Result<Race> {
+        let leg: VrLeg = serde_json::from_slice(bytes)?;
+
+        let mut checkpoints = leg.checkpoints;
+        checkpoints.sort_by(|a, b| a.group.cmp(&b.group).then(a.id.cmp(&b.id)));
+
+        let mut buoys = checkpoints.into_iter().map(|checkpoint| {
+            match checkpoint.end {
+                Some(end) if checkpoint.display == "gate" => {
+                    let middle = Coords {
+                        lat: (checkpoint.start.lat + end.lat) / 2.0,
+                        lon: (checkpoint.start.lon + end.lon) / 2.0,
+                    };
+
+                    Buoy::Door(Door {
+                        name: checkpoint.name,
+                        port: checkpoint.start,
+                        starboard: end,
+                        departure: middle.clone(),
+                        destination: middle,
+                        to_avoid: Vec::new(),
+                        validated: false,
+                        optional: false,
+                        accuracy_override: None,
+                    })
+                },
+                _ => Buoy::Waypoint(Waypoint {
+                    name: checkpoint.name,
+                    destination: checkpoint.start,
+                    to_avoid: Vec::new(),
+                    validated: false,
+                    optional: false,
+                    accuracy_override: None,
+                }),
+            }
+        }).collect::<Vec<_>>();
+
+        buoys.push(Buoy::Zone(Zone {
+            name: leg.end.name.unwrap_or("end".to_string()),
+            destination: Coords { lat: leg.end.lat, lon: leg.end.lon },
+            radius: Distance::from_nm(leg.end.radius),
+            to_avoid: Vec::new(),
+            validated: false,
+            optional: false,
+            entry_heading: None,
+            accuracy_override: None,
+        }));
+
+        Ok(Race {
+            id: leg.id.race_id.to_string(),
+            name: leg.name,
+            leg: leg.id.num,
+            short_name: None,
+            boat: leg.boat.polar_id.to_string(),
+            stamina: false,
+            start_time: leg.start.date,
+            end_time: leg.end.date,
+            start: Coords { lat: leg.start.lat, lon: leg.start.lon },
+            buoys,
+            ice_limits: leg.ice_limits,
+        })
+    }
+
+    pub(crate) fn from_gpx(mut reader: impl Read) -> Result<Race> {
+        let mut gpx = String::new();
+        reader.read_to_string(&mut gpx)?;
+
+        let name = Self::gpx_element(&gpx, "metadata")
+            .and_then(|metadata| Self::gpx_element(metadata, "name"))
+            .map_or("gpx".to_string(), |name| Self::gpx_unescape(name));
+
+        let mut waypoints = Vec::new();
+        let mut rest = gpx.as_str();
+        while let Some(begin) = rest.find("<wpt") {
+            rest = &rest[begin..];
+
+            let tag_end = match rest.find('>') {
+                Some(tag_end) => tag_end,
+                None => bail!("Unterminated wpt element"),
+            };
+            let tag = &rest[..tag_end];
+
+            let lat = Self::gpx_attribute(tag, "lat")?;
+            let lon = Self::gpx_attribute(tag, "lon")?;
+
+            let (content, next) = if tag.ends_with('/') {
+                ("", tag_end + 1)
+            } else {
+                match rest.find("</wpt>") {
+                    Some(end) => (&rest[tag_end + 1..end], end + "</wpt>".len()),
+                    None => bail!("Unterminated wpt element"),
+                }
+            };
+
+            let name = Self::gpx_element(content, "name").map_or(format!("wpt{}", waypoints.len()), |name| Self::gpx_unescape(name));
+
+            waypoints.push(Waypoint {
+                name,
+                destination: Coords { lat, lon },
+                to_avoid: Vec::new(),
+                validated: false,
+                optional: false,
+                accuracy_override: None,
+            });
+
+            rest = &rest[next..];
+        }
+
+        if waypoints.is_empty() {
+            bail!("No waypoint found in gpx");
+        }
+
+        let start = waypoints.remove(0).destination;
+
+        Ok(Race {
+            id: name.clone(),
+            name,
+            leg: 1,
+            short_name: None,
+            boat: String::new(),
+            stamina: false,
+            start_time: None,
+            end_time: None,
+            start,
+            buoys: waypoints.into_iter().map(|waypoint| Buoy::Waypoint(waypoint)).collect(),
+            ice_limits: None,
+        })
+    }
+
+    fn gpx_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+        let open = format!("<{name}>");
+        let close = format!("</{name}>");
+
+        let begin = xml.find(&open)? + open.len();
+        let end = xml[begin..].find(&close)? + begin;
+
+        Some(xml[begin..end].trim())
+    }
+
+    /// Value of the `name` attribute of `tag`, whatever the whitespace around its name and `=`.
+    fn gpx_attribute(tag: &str, name: &str) -> Result<f64> {
+        let mut rest = tag;
+        while let Some(begin) = rest.find(name) {
+            let preceded = rest[..begin].ends_with(char::is_whitespace);
+            rest = &rest[begin + name.len()..];
+
+            if let (true, Some(value)) = (preceded, rest.trim_start().strip_prefix('=')) {
+                let value = value.trim_start();
+                if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+                    if let Some(end) = value[1..].find(quote) {
+                        return Ok(value[1..end + 1].trim().parse::<f64>()?);
+                    }
+                }
+            }
+        }
+
+        bail!("Attribute {name} not found in {tag}>")
+    }
+
+    fn gpx_unescape(value: &str) -> String {
+        value.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    }
+
+    pub(crate) fn validate_next_waypoint(&mut self) -> Option<Buoy> {
 
         info!("Validate next waypoint");
-        self.buoys.iter_mut().filter(|w| !w.is_validated()).collect::<Vec<&mut Buoy>>().first_mut().map(|w| w.validate());
+        self.buoys.iter_mut().find(|w| !w.is_validated()).map(|w| {
+            w.validate();
+            w.clone()
+        })
+    }
+
+    /// Validates, in order, the buoys passed along `track`.
+    pub(crate) fn validate_passed(&mut self, track: &[Coords], algorithm: &dyn Algorithm) {
+        let mut segments = track.windows(2);
+
+        while let Some(buoy) = self.next_waypoint() {
+            if !segments.any(|segment| buoy.is_passed_by(&segment[0], &segment[1], algorithm)) {
+                break;
+            }
+
+            self.validate_next_waypoint();
+        }
     }
+
+    /// Validates, in order, the doors `from` is already beyond.
+    pub(crate) fn validate_doors_behind(&mut self, from: &Coords, algorithm: &dyn Algorithm) {
+        while let Some(Buoy::Door(door)) = self.next_waypoint() {
+            if !door.is_behind(from, algorithm) {
+                break;
+            }
+
+            debug!("Door {} already crossed at start", door.name);
+            self.validate_next_waypoint();
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.buoys.iter().all(|w| w.is_validated())
+    }
+}
+
+#[derive(Deserialize)]
+struct VrLeg {
+    #[serde(rename = "_id")]
+    id: VrLegId,
+    name: String,
+    boat: VrBoat,
+    start: VrPoint,
+    end: VrPoint,
+    #[serde(default)]
+    checkpoints: Vec<VrCheckpoint>,
+    ice_limits: Option<Limits>,
+}
+
+#[derive(Deserialize)]
+struct VrLegId {
+    race_id: u32,
+    num: u8,
+}
+
+#[derive(Deserialize)]
+struct VrBoat {
+    polar_id: u32,
+}
+
+#[derive(Deserialize)]
+struct VrPoint {
+    lat: f64,
+    lon: f64,
+    name: Option<String>,
+    #[serde(default)]
+    radius: f64,
+    #[serde(default, with = "ts_milliseconds_option")]
+    date: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct VrCheckpoint {
+    #[serde(rename = "_id")]
+    id: u32,
+    group: u32,
+    name: String,
+    start: Coords,
+    end: Option<Coords>,
+    display: String,
 }
@@ -14,14 +14,15 @@ use log::{debug, error, info};
 use rayon::prelude::*;
 
 use crate::race;
-use crate::{polar::Polar, polar::PolarCache, race::Race, router};
+use crate::{polar::Polar, polar::PolarCache, race::{Limits, Race}, router};
 use crate::algorithm::Algorithm;
-use crate::algorithm::spherical::Spherical;
+use crate::current::{self, CurrentProvider, InstantCurrent, NoCurrent};
+use crate::error::PhtError;
 use crate::phtheirichthys::BoatOptions;
 use crate::land::LandsProvider;
 use crate::position::{Heading, Penalties, Coords, Sail, BoatSettings, BoatStatus};
-use crate::router::{IsochroneSection, Router, RouteInfos, RouteRequest, RouteResult, WaypointStatus, Wind, Isochrone, IsochronePoint};
-use crate::utils::{Distance, Speed};
+use crate::router::{CancellationToken, LiveBest, IsochroneSection, Router, RouteInfos, RouteRequest, RouteResult, WaypointStatus, Wind, Isochrone, IsochronePoint};
+use crate::utils::{yield_now, Distance, Speed};
 use crate::wind::{InstantWind, Provider};
 
 pub(crate) struct Echeneis<A: 'static + Algorithm + Send + Sync> {
@@ -30,48 +31,132 @@ pub(crate) struct Echeneis<A: 'static + Algorithm + Send + Sync> {
     lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>,
     polar: Arc<Polar>,
     algorithm: Arc<A>,
+    currents: Arc<dyn CurrentProvider + Send + Sync>,
     config: EcheneisConfig,
+    on_isochrone: Option<IsochroneListener>,
+    live_best: Option<LiveBest>,
 }
 
-#[derive(Clone, Debug, Default)]
+/// Called with the section door and each isochrone as soon as it is computed.
+pub(crate) type IsochroneListener = Arc<dyn Fn(&str, &Isochrone) + Send + Sync>;
+
+#[derive(Clone, Debug)]
 pub(crate) struct EcheneisConfig {
     pub(crate) accuracy: f64,
     pub(crate) display_all_isochrones: bool,
     pub(crate) timeout: u64,
+    pub(crate) max_duration_hours: u64,
+    pub(crate) alternatives: usize,
+    pub(crate) simplify_tolerance: Option<Distance>,
+    pub(crate) exploration: ExplorationMode,
+    /// Emit the isochrones every so many minutes instead of every hour.
+    pub(crate) isochrone_interval_minutes: Option<i64>,
+    /// Angle between two explored twas or headings, in tenths of degree.
+    pub(crate) twa_step_tenths: u16,
+    /// Positions kept per azimuth at each step, the farthest ones.
+    pub(crate) max_points_per_azimuth: usize,
+    /// Navs reaching a buoy carried on to the next one, the earliest ones.
+    pub(crate) max_reachers: usize,
+    /// How far from the direct route to a buoy positions are explored.
+    pub(crate) corridor_policy: CorridorPolicy,
+    /// A buoy is sailed to straight once it is less than `arrival_time_factor` steps away,
+    /// from positions no farther than `arrival_distance_factor` times their last leg.
+    pub(crate) arrival_time_factor: f64,
+    pub(crate) arrival_distance_factor: f64,
+}
+
+/// Bound of the route through an explored position, start to position to buoy, from the leg length.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum CorridorPolicy {
+    /// Twice the leg under 100nm, one and a half times beyond.
+    #[default]
+    Auto,
+    /// The leg times the factor.
+    Factor(f64),
+    /// The leg plus a fixed margin.
+    Fixed(Distance),
+}
+
+impl CorridorPolicy {
+    pub(crate) fn max_radius(&self, leg: &Distance) -> Distance {
+        match self {
+            CorridorPolicy::Auto if leg < &Distance::from_nm(100.0) => leg.clone() * 2.0,
+            CorridorPolicy::Auto => leg.clone() * 1.5,
+            CorridorPolicy::Factor(factor) => leg.clone() * *factor,
+            CorridorPolicy::Fixed(margin) => leg.clone() + margin.clone(),
+        }
+    }
+}
+
+/// How the boat is steered when exploring from a position.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum ExplorationMode {
+    /// Every twa, or every heading near land.
+    #[default]
+    Twa,
+    /// Every heading, wherever the boat is.
+    Heading,
+    /// Every twa and every heading, keeping the best of both.
+    Both,
+}
+
+impl Default for EcheneisConfig {
+    fn default() -> Self {
+        Self {
+            accuracy: 1.0,
+            display_all_isochrones: false,
+            timeout: 60,
+            max_duration_hours: 20 * 24,
+            alternatives: 0,
+            simplify_tolerance: None,
+            exploration: ExplorationMode::default(),
+            isochrone_interval_minutes: None,
+            twa_step_tenths: 10,
+            max_points_per_azimuth: usize::MAX,
+            max_reachers: usize::MAX,
+            corridor_policy: CorridorPolicy::default(),
+            arrival_time_factor: 1.5,
+            arrival_distance_factor: 10.0,
+        }
+    }
 }
 
 #[async_trait]
 impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
 
-    async fn route(&self, race: &Race, boat_options: BoatOptions, request: RouteRequest, routing_timeout: Option<Duration>) -> Result<RouteResult> {
+    async fn route(&self, race: &Race, boat_options: BoatOptions, request: RouteRequest, routing_timeout: Option<Duration>, cancellation: Option<CancellationToken>) -> Result<RouteResult> {
 
         let start_routing = Utc::now();
 
         debug!("Route asked : {:?}", request);
 
+        request.validate()?;
+
+        // doors the route begins beyond would never be crossed
+        let mut race = race.clone();
+        race.validate_doors_behind(&request.from, self.algorithm.as_ref());
+        let race = &race;
+
         let boat_options = Arc::new(boat_options);
 
-        let max_duration: Duration = Duration::hours(20*24); //Duration::minutes(25); //
+        let max_duration: Duration = Duration::hours(self.config.max_duration_hours as i64);
 
         let steps = request.steps.clone();
 
         let start = request.start_time;
 
         let mut from = request.from.clone();
-        let mut froms = Nav {
-            absolute_duration: Duration::zero(),
-            min: None,
-            alternatives: BTreeMap::from([(0, request.clone().into())]),
-            reached_by_way: false,
-            crossed: false,
-        };
+        let mut froms = Nav::start(&request);
         let mut now = start.clone();
         let mut duration = Duration::zero();
 
         let mut sections = Vec::new();
 
-        let mut best = None;
+        let mut best: Option<Arc<Position>> = None;
         let mut best_dist_to: Distance;
+        if let Some(live_best) = &self.live_best {
+            live_best.set(None);
+        }
 
         let mut success = true;
 
@@ -79,20 +164,19 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
 
         let mut deb = Vec::new();
 
-        let mut buoys = get_buoys(race, from.clone()).peekable();
+        let ice_limits = Arc::new(race.ice_limits.clone());
+
+        let mut buoys = get_buoys(race, from.clone(), self.algorithm.clone()).peekable();
         let mut max = BTreeMap::new();
 
         while let Some(mut destination) = buoys.next() {
 
+            // where to route on from if this optional buoy can't be reached
+            let skipped = destination.is_optional().then(|| (from.clone(), froms.clone(), future_navs.clone(), duration, now, best.clone()));
+
             let mut reached = false;
-            let min = destination.distance(&from);
-            let max_radius = if min.clone() / 1000.0 < Distance::from_nm(1000.0) {
-                min.clone() * 1.5
-            } else if min.clone() / 1000.0 < Distance::from_nm(100.0) {
-                min.clone() * 2.0
-            } else {
-                min.clone() * 1.5
-            };
+            let min = destination.distance(&from).abs();
+            let max_radius = self.config.corridor_policy.max_radius(&min);
 
             best_dist_to = min.clone();
 
@@ -107,6 +191,13 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
 
             while !reached && success && duration < max_duration && !routing_timeout.is_some_and(|timeout| Utc::now() > start_routing.add(timeout.clone())) {
 
+                // lets a cancellation requested meanwhile be seen, nothing else yields on a single-threaded runtime
+                yield_now().await;
+
+                if cancellation.as_ref().is_some_and(|cancellation| cancellation.is_cancelled()) {
+                    bail!(PhtError::Cancelled);
+                }
+
                 let (_, step) = steps.iter().filter(|(d, _)| d > &duration).next().unwrap_or(steps.last().unwrap());
 
                 // prepare
@@ -127,7 +218,7 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
 
                 // let mut navs = match timeout(
                     // std::time::Duration::from_secs(self.config.timeout),
-                let mut navs = self.navigate2(&boat_options, &from, &now, froms, &mut destination, step.clone(), factor, &mut max, &max_radius, future_navs.to_owned()).await;
+                let mut navs = self.navigate2(&boat_options, &ice_limits, &from, &now, froms, &mut destination, step.clone(), factor, &mut max, &max_radius, future_navs.to_owned()).await;
                 // ).await {
                 //     Err(_) => {
                 //         bail!("timeout while navigate");
@@ -148,8 +239,9 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
                     // Generate isochrone for ui
                     {
                         let hours = duration.num_minutes();
+                        let interval = self.config.isochrone_interval_minutes.filter(|interval| *interval > 0).unwrap_or(60);
 
-                        if self.config.display_all_isochrones || hours % 60 < step.num_minutes() {
+                        if self.config.display_all_isochrones || hours % interval < step.num_minutes() {
                             let color = if hours % 1440 < step.num_minutes() {
                                 "%24".to_string()
                             } else if hours % 360 < step.num_minutes() {
@@ -160,10 +252,16 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
                                 "%0".to_string()
                             };
 
-                            section.isochrones.push(Isochrone {
+                            let isochrone = Isochrone {
                                 color,
                                 paths: nav.to_isochrone(self.config.display_all_isochrones),
-                            });
+                            };
+
+                            if let Some(on_isochrone) = &self.on_isochrone {
+                                on_isochrone(&section.door, &isochrone);
+                            }
+
+                            section.isochrones.push(isochrone);
                         }
                     }
 
@@ -189,6 +287,10 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
                                 }
                             }
                         }
+
+                        if let Some(live_best) = &self.live_best {
+                            live_best.set(best.as_deref().map(Self::last_waypoint));
+                        }
                     }
 
                     // Is boat arrived
@@ -204,7 +306,7 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
                     froms = nav;
 
                     if reached {
-                        let reachers = destination.reachers();
+                        let reachers = destination.reachers(self.config.max_reachers);
 
                         self.debug(format!("reached buoy : {:?}", reachers.iter().map(|r| format!("{}:{}", r.size(), HumanTime::from(r.absolute_duration.clone()).to_text_en(Accuracy::Rough, Tense::Present))).collect::<Vec<String>>()));
 
@@ -280,74 +382,48 @@ impl<A: Algorithm + Send + Sync> Router for Echeneis<A> {
             sections.push(section);
 
             if !reached {
-                break;
-            }
-        }
-
-        let mut way = Vec::new();
-
-        if let Some(last) = best {
-            way.push(router::RouteWaypoint {
-                from: last.point.clone(),
-                duration: last.duration.absolute.clone(),
-                way_duration: Duration::zero(),
-                boat_settings: Default::default(),
-                status: WaypointStatus {
-                    boat_speed: Default::default(),
-                    wind: Default::default(),
-                    foil: 0,
-                    boost: 0,
-                    best_ratio: 0.0,
-                    ice: false,
-                    change: false,
-                    penalties: Vec::new(),
-                    remaining_penalties: Vec::new(),
-                    stamina: 0.0,
-                    remaining_stamina: 0.0,
-                }
-            });
-
-            let mut next = last;
-            while let Some(last) = next.previous.as_ref() {
-                way.push(router::RouteWaypoint {
-                    from: last.point.clone(),
-                    duration: last.duration.absolute,
-                    way_duration: next.duration.relative.clone(),
-                    boat_settings: next.settings.clone(),
-                    status: WaypointStatus {
-                        boat_speed: next.status.boat_speed.clone(),
-                        wind: next.status.wind.clone(),
-                        foil: next.status.foil,
-                        boost: next.status.boost,
-                        best_ratio: next.status.best_ratio,
-                        ice: false,
-                        change: false,
-                        penalties: next.status.penalties.clone().into(),
-                        remaining_penalties: next.remaining_penalties.clone().into(),
-                        stamina: next.status.stamina,
-                        remaining_stamina: next.remaining_stamina,
+                match skipped {
+                    Some(skipped) if buoys.peek().is_some() => {
+                        self.debug(format!("skip optional buoy {}", destination.name()));
+                        (from, froms, future_navs, duration, now, best) = skipped;
+                        max.clear();
+                        success = true;
                     }
-                });
-                next = last.clone();
+                    _ => break,
+                }
             }
-        } else {
-            bail!("Routing failed");
         }
 
-        way.sort_by(|a, b| a.duration.cmp(&b.duration));
+        let way = match &best {
+            Some(last) => Self::way_to(self.algorithm.as_ref(), last.clone()),
+            None => bail!("Routing failed"),
+        };
+
+        let alternatives = match &best {
+            Some(best) if self.config.alternatives > 0 => self.alternative_ways(&froms, best),
+            _ => Vec::new(),
+        };
 
-        Ok(RouteResult {
+        let mut result = RouteResult {
             infos: RouteInfos {
                 start,
                 duration: 0.0,
                 success,
                 sails_duration: HashMap::new(),
-                foil_duration: 0.0
+                foil_duration: 0.0,
+                crosses_land: None,
             },
             way,
+            alternatives,
             sections,
             debug: deb,
-        })
+        };
+
+        if let Some(tolerance) = &self.config.simplify_tolerance {
+            result.simplify(tolerance.clone(), self.algorithm.as_ref());
+        }
+
+        Ok(result)
     }
 }
 
@@ -361,12 +437,151 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
             lands_provider,
             polar,
             algorithm,
+            currents: Arc::new(NoCurrent),
             config,
+            on_isochrone: None,
+            live_best: None,
         }
     }
 
+    pub(crate) fn with_isochrone_listener(mut self, on_isochrone: IsochroneListener) -> Self {
+        self.on_isochrone = Some(on_isochrone);
+        self
+    }
+
+    /// Keeps `live_best` updated with the best position of the routing in progress, at each step.
+    pub(crate) fn with_live_best(mut self, live_best: LiveBest) -> Self {
+        self.live_best = Some(live_best);
+        self
+    }
+
+    /// Routes again from `new_from` at `new_time`, starting with the settings and status of the nearest waypoint of `previous`.
+    ///
+    /// The buoys `previous` passed before that waypoint are considered validated, and the penalties and stamina left there
+    /// go on elapsing and recovering until `new_time`. Isochrones are computed again, `previous` not keeping the positions explored.
+    pub(crate) async fn reroute(&self, race: &Race, boat_options: BoatOptions, previous: &RouteResult, new_from: Coords, new_time: DateTime<Utc>, timeout: Option<Duration>) -> Result<RouteResult> {
+        let algorithm = self.algorithm.as_ref();
+
+        let nearest = match previous.way.iter().enumerate().min_by(|(_, a), (_, b)| algorithm.distance_to(&a.from, &new_from).cmp(&algorithm.distance_to(&b.from, &new_from))) {
+            Some((nearest, _)) => nearest,
+            None => bail!("No previous way to reroute from"),
+        };
+
+        let mut race = race.clone();
+        let track = previous.way[..=nearest].iter().map(|waypoint| waypoint.from.clone()).collect::<Vec<_>>();
+        race.validate_passed(&track, algorithm);
+
+        let waypoint = &previous.way[nearest];
+        let elapsed = (new_time - (previous.infos.start + waypoint.duration)).max(Duration::zero());
+        let status: BoatStatus = waypoint.status.clone().into();
+        let status = BoatStatus {
+            penalties: Penalties::from(waypoint.status.remaining_penalties.clone()) - elapsed,
+            stamina: self.polar.recovers(waypoint.status.remaining_stamina, &elapsed, &waypoint.status.wind.speed),
+            ..status
+        };
+
+        let request = RouteRequest {
+            from: new_from,
+            start_time: new_time,
+            boat_settings: waypoint.boat_settings.clone(),
+            status,
+            steps: router::default_steps(),
+        };
+
+        self.route(&race, boat_options, request, timeout, None).await
+    }
+
+    /// Waypoints from the start to `last`, each one carrying the status of the leg it starts, the wind included.
+    ///
+    /// The last one has no leg of its own and keeps the status of the leg reaching it.
+    /// Bearings are computed with `algorithm`.
+    pub(crate) fn way_to(algorithm: &dyn Algorithm, last: Arc<Position>) -> Vec<router::RouteWaypoint> {
+        let mut way = Vec::new();
+
+        way.push(Self::last_waypoint(&last));
+
+        let mut next = last;
+        while let Some(last) = next.previous.as_ref() {
+            way.push(router::RouteWaypoint {
+                from: last.point.clone(),
+                duration: last.duration.absolute,
+                way_duration: next.duration.relative.clone(),
+                bearing: next.heading_to_next(algorithm),
+                boat_settings: next.settings.clone(),
+                status: WaypointStatus {
+                    boat_speed: next.status.boat_speed.clone(),
+                    wind: next.status.wind.clone(),
+                    foil: next.status.foil,
+                    boost: next.status.boost,
+                    best_ratio: next.status.best_ratio,
+                    ice: next.is_in_ice_limits,
+                    change: next.settings.sail != last.settings.sail,
+                    penalties: next.status.penalties.clone().into(),
+                    remaining_penalties: next.remaining_penalties.clone().into(),
+                    stamina: next.status.stamina,
+                    remaining_stamina: next.remaining_stamina,
+                }
+            });
+            next = last.clone();
+        }
+
+        way.sort_by(|a, b| a.duration.cmp(&b.duration));
+        way
+    }
+
+    /// Waypoint of `last` itself, with no leg of its own.
+    fn last_waypoint(last: &Position) -> router::RouteWaypoint {
+        router::RouteWaypoint {
+            from: last.point.clone(),
+            duration: last.duration.absolute.clone(),
+            way_duration: Duration::zero(),
+            bearing: None,
+            boat_settings: Default::default(),
+            status: WaypointStatus {
+                boat_speed: last.status.boat_speed.clone(),
+                wind: last.status.wind.clone(),
+                foil: last.status.foil,
+                boost: last.status.boost,
+                best_ratio: last.status.best_ratio,
+                ice: last.is_in_ice_limits,
+                change: false,
+                penalties: Vec::new(),
+                remaining_penalties: last.remaining_penalties.clone().into(),
+                stamina: last.status.stamina,
+                remaining_stamina: last.remaining_stamina,
+            }
+        }
+    }
+
+    /// Ways to the next best positions of `nav`, skipping those sharing most of their history with `best` or another kept one.
+    fn alternative_ways(&self, nav: &Nav, best: &Arc<Position>) -> Vec<Vec<router::RouteWaypoint>> {
+        let mut candidates = nav.alternatives.values()
+            .flat_map(|alternative| alternative.variants.iter().flatten())
+            .map(|pos| Arc::new(pos.clone()))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.dist_to.cmp(&b.dist_to).then(a.duration.absolute.cmp(&b.duration.absolute)));
+
+        let mut kept = vec![best.clone()];
+        for candidate in candidates {
+            if kept.len() > self.config.alternatives {
+                break;
+            }
+            if kept.iter().all(|pos| !pos.shares_history(&candidate)) {
+                kept.push(candidate);
+            }
+        }
+
+        kept.into_iter().skip(1).map(|last| Self::way_to(self.algorithm.as_ref(), last)).collect()
+    }
+
+    pub(crate) fn with_currents(mut self, currents: Arc<dyn CurrentProvider + Send + Sync>) -> Self {
+        self.currents = currents;
+        self
+    }
+
     pub(crate) fn jump2(algorithm: &Arc<A>,
                         lands_provider: Option<&Arc<Box<dyn LandsProvider + Send + Sync>>>,
+                        ice_limits: Option<&Limits>,
                         polar: &mut PolarCache,
                         boat_options: &Arc<BoatOptions>,
                         start: &Arc<Coords>,
@@ -375,6 +590,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                         heading: &Heading,
                         duration: Duration,
                         wind: &Wind,
+                        current: Option<&(f64, Speed)>,
                         factor: f64,
                         snake: bool,) -> Vec<(i32, Position)> {
 
@@ -383,7 +599,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
             return Vec::new()
         }
 
-        polar.get_boat_speeds(&heading, wind, &from.settings.sail, from.is_in_ice_limits, false).into_iter().map(|polar_result| {
+        polar.get_boat_speeds(&heading, wind, &from.settings.sail, from.is_in_ice_limits, polar.sail_filter(), !boat_options.manual_sails).into_iter().map(|polar_result| {
             let penalties = polar.add_penalties(boat_options, from.remaining_penalties.clone(), from.remaining_stamina,
                                                 from.settings.heading.twa(from.status.wind.direction), twa,
                                                 &from.settings.sail, &polar_result.sail,
@@ -403,16 +619,29 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 
             let remaining_stamina = polar.recovers(stamina, &jump_duration, &wind.speed);
 
-            let point = algorithm.destination(&from.point, heading.heading(wind.direction), &distance);
+            let point = match current {
+                Some((direction, speed)) => {
+                    let (heading, distance) = current::over_ground(heading.heading(wind.direction), &distance, *direction, &(speed.clone() * jump_duration));
+                    algorithm.destination(&from.point, heading, &distance)
+                },
+                None => algorithm.destination(&from.point, heading.heading(wind.direction), &distance),
+            };
 
             if lands_provider.is_some() && lands_provider.unwrap().is_land(point.lat, point.lon) {
                 return None;
             }
 
+            // entering the ice limits, leaving them is always allowed
+            if !from.is_in_ice_limits && ice_limits.is_some_and(|limits| limits.crosses(&from.point, &point, algorithm.as_ref())) {
+                return None;
+            }
+
             let (from_dist, az) = algorithm.distance_and_heading_to(&*start, &point);
 
             let dist_to = to.as_ref().map_or(Distance::zero(), |to| to.distance(&point));
 
+            let is_in_ice_limits = ice_limits.is_some_and(|limits| limits.is_in(&point));
+
             let az = (az * factor).round() as i32;
             Some((az, Position {
                 az,
@@ -426,7 +655,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                     heading: heading.clone(),
                     sail: polar_result.sail,
                 },
-                status: BoatStatus {
+                status: Arc::new(BoatStatus {
                     aground: false,
                     boat_speed,
                     wind: wind.clone(),
@@ -437,18 +666,20 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                     vmgs: None,
                     penalties,
                     stamina,
-                },
+                }),
                 previous: Some(from.clone()),
-                is_in_ice_limits: false, //TODO manage ice
+                is_in_ice_limits,
                 remaining_penalties,
                 remaining_stamina,
             }))
         }).filter(|alt| alt.is_some()).map(|alt| alt.unwrap()).collect()
     }
 
-    fn buoy_reached(algorithm: &Arc<A>, polar: &mut PolarCache, boat_options: &Arc<BoatOptions>, start: &Arc<Coords>, from: &Arc<Position>, to: &Arc<Buoy>, duration: Duration, wind: &Wind, factor: f64) -> Option<(i32, Position)> {
+    /// Position at `to` sailing straight from `from`, if within `arrival_time_factor` steps of `duration`,
+    /// tried only when `from` is no farther from `to` than `arrival_distance_factor` times its last leg.
+    fn buoy_reached(algorithm: &Arc<A>, ice_limits: Option<&Limits>, polar: &mut PolarCache, boat_options: &Arc<BoatOptions>, start: &Arc<Coords>, from: &Arc<Position>, to: &Arc<Buoy>, duration: Duration, wind: &Wind, factor: f64, arrival_time_factor: f64, arrival_distance_factor: f64) -> Option<(i32, Position)> {
 
-        if from.dist_to > from.distance.clone() * 10.0 {
+        if from.dist_to > from.distance.clone() * arrival_distance_factor {
             return None;
         }
 
@@ -458,7 +689,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 
         let mut results = Vec::new();
 
-        for polar_result in polar.get_boat_speeds(&heading, wind, &from.settings.sail, from.is_in_ice_limits, false).into_iter() {
+        for polar_result in polar.get_boat_speeds(&heading, wind, &from.settings.sail, from.is_in_ice_limits, polar.sail_filter(), !boat_options.manual_sails).into_iter() {
             let penalties = polar.add_penalties(boat_options, from.remaining_penalties.clone(), from.remaining_stamina,
                                                 from.settings.heading.twa(from.status.wind.direction), heading.twa(wind.direction),
                                                 &from.settings.sail, &polar_result.sail,
@@ -475,7 +706,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 
             let remaining_stamina = polar.recovers(stamina, &duration_to_buoy, &wind.speed);
 
-            if duration_to_buoy.num_seconds() as f64 <= duration.num_seconds() as f64 * 1.5 {
+            if duration_to_buoy.num_seconds() as f64 <= duration.num_seconds() as f64 * arrival_time_factor {
 
                 let (from_dist, az) = algorithm.distance_and_heading_to(&*start, &to.destination());
 
@@ -492,7 +723,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                         heading: heading.clone(),
                         sail: polar_result.sail,
                     },
-                    status: BoatStatus {
+                    status: Arc::new(BoatStatus {
                         aground: false,
                         boat_speed,
                         wind: wind.clone(),
@@ -503,9 +734,9 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                         vmgs: None,
                         penalties,
                         stamina,
-                    },
+                    }),
                     previous: Some(from.clone()),
-                    is_in_ice_limits: false,
+                    is_in_ice_limits: ice_limits.is_some_and(|limits| limits.is_in(&to.destination())),
                     remaining_penalties,
                     remaining_stamina,
                 }));
@@ -519,17 +750,23 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 
     fn way2(algorithm: Arc<A>,
             lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>,
+            ice_limits: Arc<Option<Limits>>,
             polar: &mut PolarCache,
             boat_options: Arc<BoatOptions>,
             start: Arc<Coords>,
             from: Arc<Position>, to: &Option<Arc<Buoy>>,
             duration: Duration,
             wind: &Wind,
-            factor: f64) -> Vec<Nav> {
+            current: &(f64, Speed),
+            factor: f64,
+            exploration: ExplorationMode,
+            step_tenths: u16,
+            arrival_time_factor: f64,
+            arrival_distance_factor: f64) -> Vec<Nav> {
 
         if to.is_some() {
             let to = to.as_ref().unwrap();
-            let reached = Self::buoy_reached(&algorithm, polar, &boat_options, &start, &from, to, duration, wind, factor);
+            let reached = Self::buoy_reached(&algorithm, ice_limits.as_ref().as_ref(), polar, &boat_options, &start, &from, to, duration, wind, factor, arrival_time_factor, arrival_distance_factor);
             if let Some((_, pos)) = reached {
                 return vec!(Nav{
                     absolute_duration: pos.duration.absolute,
@@ -544,43 +781,27 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
         let mut navs:  BTreeMap<Duration, Nav> = BTreeMap::new();
         let mut default_nav = Nav::from((from.duration.clone() + duration).absolute);
 
-        // If near land : heading. Else twa
-        // if lands_provider.near_land(from.point.lat, from.point.lon) {
-        //     for heading in 0..360 {
-        //         let heading = Heading::HEADING(heading as f64);
-        //         let positions = Self::jump2(&algorithm, Some(&lands_provider), &polar, &boat_options, &start, &from, to, &heading, duration, wind, factor);
-        //
-        //         for (az, pos) in positions {
-        //             let nav = navs.entry(pos.duration.absolute).or_insert_with(|| Nav::from(pos.duration.absolute));
-        //             nav.min = nav.min.to_owned().or(Some(pos.dist_to.clone())).and_then(|min| if min < pos.dist_to { Some(min) } else { Some(pos.dist_to.clone()) });
-        //             let alternative = nav.alternatives.entry(az).or_insert_with(|| Alternative::empty());
-        //             alternative.merge_fast(pos);
-        //         }
-        //     }
-        // } else {
-            for twa in -180..180 {
-                let heading = Heading::TWA(twa as f64);
-                let positions = Self::jump2(&algorithm, Some(&lands_provider), polar, &boat_options, &start, &from, to, &heading, duration, wind, factor, false);
-
-                for (az, pos) in positions {
-                    let nav = if pos.duration.relative == duration { &mut default_nav } else { navs.entry(pos.duration.absolute).or_insert_with(|| Nav::from(pos.duration.absolute)) };
-                    {
-                        // nav.min = nav.min.to_owned().or(Some(pos.dist_to.clone())).and_then(|min| if min < pos.dist_to { Some(min) } else { Some(pos.dist_to.clone()) });
-                        let new_min = match &nav.min {
-                            None => { true }
-                            Some(min) if { min > &pos.dist_to } => { true }
-                            _ => { false }
-                        };
-
-                        if new_min {
-                            nav.min = Some(pos.dist_to.clone())
-                        }
+        for heading in exploration_headings(lands_provider.as_ref().as_ref(), &from.point, exploration, step_tenths) {
+            let positions = Self::jump2(&algorithm, Some(&lands_provider), ice_limits.as_ref().as_ref(), polar, &boat_options, &start, &from, to, &heading, duration, wind, Some(current), factor, false);
+
+            for (az, pos) in positions {
+                let nav = if pos.duration.relative == duration { &mut default_nav } else { navs.entry(pos.duration.absolute).or_insert_with(|| Nav::from(pos.duration.absolute)) };
+                {
+                    // nav.min = nav.min.to_owned().or(Some(pos.dist_to.clone())).and_then(|min| if min < pos.dist_to { Some(min) } else { Some(pos.dist_to.clone()) });
+                    let new_min = match &nav.min {
+                        None => { true }
+                        Some(min) if { min > &pos.dist_to } => { true }
+                        _ => { false }
+                    };
+
+                    if new_min {
+                        nav.min = Some(pos.dist_to.clone())
                     }
-                    let alternative = nav.alternatives.entry(az).or_insert_with(|| Alternative::empty());
-                    alternative.merge_fast(pos);
                 }
+                let alternative = nav.alternatives.entry(az).or_insert_with(|| Alternative::empty());
+                alternative.merge_fast(pos);
             }
-        // }
+        }
 
         let mut navs = if navs.len() > 0 {
             let mut navs = navs.iter().map(|(_, nav)| nav.to_owned()).collect::<Vec<Nav>>();
@@ -593,18 +814,20 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
         navs
     }
 
-    async fn navigate2(&self, boat_options: &Arc<BoatOptions>, start: &Coords, now: &DateTime<Utc>, from: Nav, to: &mut Buoy, duration: Duration, factor: f64, max: &mut BTreeMap<i32, [Distance;8]>, max_radius: &Distance, navs: VecDeque<Nav>) -> VecDeque<Nav> {
+    pub(crate) async fn navigate2(&self, boat_options: &Arc<BoatOptions>, ice_limits: &Arc<Option<Limits>>, start: &Coords, now: &DateTime<Utc>, from: Nav, to: &mut Buoy, duration: Duration, factor: f64, max: &mut BTreeMap<i32, [Distance;8]>, max_radius: &Distance, navs: VecDeque<Nav>) -> VecDeque<Nav> {
 
         let navs = Arc::new(Mutex::new(navs.into_iter().map(|nav| (nav.absolute_duration, nav)).collect::<HashMap<Duration, Nav>>()));
 
         let winds = Arc::new(self.winds.find(now));
+        let currents = Arc::new(InstantCurrent::new(self.currents.clone(), now.clone()));
         let algorithm = self.algorithm.clone();
         let lands_provider = self.lands_provider.clone();
         let polar = self.polar.clone();
         let boat_options = boat_options.clone();
+        let ice_limits = ice_limits.clone();
         let start = Arc::new(start.clone());
 
-        Self::navigate_from_all(from, to, duration, factor, &navs, winds, algorithm, lands_provider, polar, boat_options, start).await;
+        Self::navigate_from_all(from, to, duration, factor, &navs, winds, currents, algorithm, lands_provider, ice_limits, polar, boat_options, start, self.config.exploration, self.config.twa_step_tenths, self.config.arrival_time_factor, self.config.arrival_distance_factor).await;
 
         let navs = navs.lock().unwrap();
         debug!("{:?}", navs.keys());
@@ -616,7 +839,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
             if nav.reached_by_way && (to.is_door() || to.is_zone()) {
                 // check if the buoy was cross
                 // if not : add the destination as point from were to go
-                if to.reachers().len() == 0 {
+                if to.reachers.is_empty() {
                     match nav.alternatives.get(&-1) {
                         Some(alternative) => {
                             match alternative.best() {
@@ -668,7 +891,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                                 }
 
                                 // check if too far from route
-                                if pos.from_dist.m() + pos.dist_to.m() > max_radius.m() {
+                                if (pos.from_dist.clone() + pos.dist_to.clone().abs()) > max_radius {
                                     alternative.variants[s] = None;
                                     size -= 1;
                                     continue;
@@ -699,6 +922,8 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                         }
                     }
 
+                    size -= alternative.keep_best(self.config.max_points_per_azimuth);
+
                 }
 
                 nav.alternatives.retain(|_, alternative| {
@@ -761,7 +986,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
     }
 
     #[cfg(feature = "rayon")]
-    async fn navigate_from_all(from: Nav, to: &mut Buoy, duration: Duration, factor: f64, navs: &Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>) {
+    async fn navigate_from_all(from: Nav, to: &mut Buoy, duration: Duration, factor: f64, navs: &Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, currents: Arc<InstantCurrent>, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, ice_limits: Arc<Option<Limits>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>, exploration: ExplorationMode, step_tenths: u16, arrival_time_factor: f64, arrival_distance_factor: f64) {
         let (send, recv) = tokio::sync::oneshot::channel();
         {
             let navs = navs.clone();
@@ -770,7 +995,7 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 
             rayon::spawn(move || {
                 from.alternatives.par_iter().for_each(|(_, alternative)| {
-                    Self::navigate_from_alternative(duration, factor, algorithm.clone(), lands_provider.clone(), polar.clone(), boat_options.clone(), start.clone(), navs.clone(), winds.clone(), to.clone(), alternative);
+                    Self::navigate_from_alternative(duration, factor, algorithm.clone(), lands_provider.clone(), ice_limits.clone(), polar.clone(), boat_options.clone(), start.clone(), navs.clone(), winds.clone(), currents.clone(), to.clone(), alternative, exploration, step_tenths, arrival_time_factor, arrival_distance_factor);
                 });
 
                 let _ = send.send(());
@@ -781,17 +1006,17 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
     }
 
     #[cfg(not(feature = "rayon"))]
-    async fn navigate_from_all(from: Nav, to: &mut Buoy, duration: Duration, factor: f64, navs: &Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>) {
+    async fn navigate_from_all(from: Nav, to: &mut Buoy, duration: Duration, factor: f64, navs: &Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, currents: Arc<InstantCurrent>, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, ice_limits: Arc<Option<Limits>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>, exploration: ExplorationMode, step_tenths: u16, arrival_time_factor: f64, arrival_distance_factor: f64) {
         let navs = navs.clone();
         let winds = winds.clone();
         let to = Arc::new(to.clone());
 
         from.alternatives.iter().for_each(|(_, alternative)| {
-            Self::navigate_from_alternative(duration, factor, algorithm.clone(), lands_provider.clone(), polar.clone(), boat_options.clone(), start.clone(), navs.clone(), winds.clone(), to.clone(), alternative);
+            Self::navigate_from_alternative(duration, factor, algorithm.clone(), lands_provider.clone(), ice_limits.clone(), polar.clone(), boat_options.clone(), start.clone(), navs.clone(), winds.clone(), currents.clone(), to.clone(), alternative, exploration, step_tenths, arrival_time_factor, arrival_distance_factor);
         });
     }
 
-    fn navigate_from_alternative(duration: Duration, factor: f64, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>, navs: Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, to: Arc<Buoy>, alternative: &Alternative) {
+    fn navigate_from_alternative(duration: Duration, factor: f64, algorithm: Arc<A>, lands_provider: Arc<Box<dyn LandsProvider + Send + Sync>>, ice_limits: Arc<Option<Limits>>, polar: Arc<Polar>, boat_options: Arc<BoatOptions>, start: Arc<Coords>, navs: Arc<Mutex<HashMap<Duration, Nav>>>, winds: Arc<Box<dyn InstantWind + Send + Sync>>, currents: Arc<InstantCurrent>, to: Arc<Buoy>, alternative: &Alternative, exploration: ExplorationMode, step_tenths: u16, arrival_time_factor: f64, arrival_distance_factor: f64) {
         let mut polar = PolarCache::new(polar);
 
         alternative.variants.iter().for_each(|variant| {
@@ -801,13 +1026,15 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
                 // let variant = variant.clone();
                 let algorithm = algorithm.clone();
                 let lands_provider = lands_provider.clone();
+                let ice_limits = ice_limits.clone();
                 let boat_options = boat_options.clone();
                 let start = start.clone();
                 let to = to.clone();
 
                 let wind = winds.interpolate(&variant.point);
+                let current = currents.interpolate(&variant.point);
 
-                let way_navs = Self::way2(algorithm, lands_provider, &mut polar, boat_options, start, Arc::new(variant.clone()), &Some(to), duration, &wind, factor);
+                let way_navs = Self::way2(algorithm, lands_provider, ice_limits, &mut polar, boat_options, start, Arc::new(variant.clone()), &Some(to), duration, &wind, &current, factor, exploration, step_tenths, arrival_time_factor, arrival_distance_factor);
 
                 for way_nav in way_navs {
                     if way_nav.reached_by_way {
@@ -851,12 +1078,14 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
         });
     }
 
-    fn get_factor(&self, from: &Coords, to: &Buoy) -> f64 {
+    pub(crate) fn get_factor(&self, from: &Coords, to: &Buoy) -> f64 {
         let dist = to.distance(from);
-        let polar_result = self.polar.get_boat_speed(&Heading::TWA(90.0), &Wind { direction: 0.0 ,speed: Speed::from_kts(10.0) }, Some(&Sail::from_index(0)), &Sail::from_index(0), false);
+        let polar_result = self.polar.get_boat_speed(&Heading::TWA(90.0), &Wind { direction: 0.0 ,speed: Speed::from_kts(10.0), gust: None }, Some(&Sail::from_index(0)), &Sail::from_index(0), false);
         let dist_between_points = polar_result.speed.km_h() * 3.0 * 1000.0;
         
-        self.config.accuracy + ((PI/180.0)/(dist_between_points /dist.m()).clamp(-1.0, 1.0).asin()).round()
+        let accuracy = to.inner.accuracy_override().unwrap_or(self.config.accuracy);
+
+        accuracy + ((PI/180.0)/(dist_between_points /dist.m()).clamp(-1.0, 1.0).asin()).round()
     }
 
     fn debug(&self, msg: String) {
@@ -873,8 +1102,8 @@ impl<A: 'static + Algorithm + Send + Sync> Echeneis<A> {
 }
 
 #[derive(Clone, Debug)]
-struct Nav {
-    absolute_duration: Duration,
+pub(crate) struct Nav {
+    pub(crate) absolute_duration: Duration,
     min: Option<Distance>,
     alternatives: BTreeMap<i32, Alternative>,
     reached_by_way: bool,
@@ -883,6 +1112,17 @@ struct Nav {
 
 impl Nav {
 
+    /// Single position of the request, where the routing starts.
+    pub(crate) fn start(request: &RouteRequest) -> Self {
+        Nav {
+            absolute_duration: Duration::zero(),
+            min: None,
+            alternatives: BTreeMap::from([(0, request.clone().into())]),
+            reached_by_way: false,
+            crossed: false,
+        }
+    }
+
     fn from(absolute_duration: Duration) -> Self {
         Nav {
             absolute_duration,
@@ -893,6 +1133,12 @@ impl Nav {
         }
     }
 
+    /// Most positions kept for a single azimuth.
+    #[cfg(test)]
+    pub(crate) fn points_per_azimuth(&self) -> usize {
+        self.alternatives.values().map(|alternative| alternative.variants.iter().flatten().count()).max().unwrap_or(0)
+    }
+
     fn size(&self) -> usize {
 
         let mut size = 0;
@@ -996,12 +1242,12 @@ impl Add<Duration> for NavDuration {
 }
 
 #[derive(Clone, Debug)]
-struct Alternative {
+pub(crate) struct Alternative {
     variants: [Option<Position>;8],
 }
 
 impl Alternative {
-    fn empty() -> Self {
+    pub(crate) fn empty() -> Self {
         Alternative {
             variants: [None, None, None, None, None, None, None, None],
         }
@@ -1019,17 +1265,17 @@ impl Alternative {
         res
     }
 
-    fn merge_all(&mut self, alternative: Alternative) {
+    pub(crate) fn merge_all(&mut self, alternative: Alternative) {
 
-        for s in 0..8 {
-            if let Some(pos) = &alternative.variants[s] {
+        for (s, pos) in IntoIterator::into_iter(alternative.variants).enumerate() {
+            if let Some(pos) = pos {
                 if self.variants[s].is_none() || pos.better_than(self.variants[s].as_ref().unwrap()) {
                     /*if pos.az == 679 && pos.settings.sail.index == 6 {
                         let previous_from_dist = pos.previous.as_ref().map_or(&pos.from_dist, |p| &p.from_dist);
                         debug!("NEW BEST {:?}({} - {} - {})", pos, pos.from_dist, previous_from_dist, pos.remaining_penalties.total().num_seconds());
                     }*/
 
-                    self.variants[s] = Some(pos.clone());
+                    self.variants[s] = Some(pos);
                 } else {
                     /*if pos.az == 679 && pos.settings.sail.index == 6 {
                         let previous_from_dist = pos.previous.as_ref().map_or(&pos.from_dist, |p| &p.from_dist);
@@ -1042,18 +1288,17 @@ impl Alternative {
 
     fn merge_all_by_duration(&mut self, alternative: Alternative) {
 
-        for s in 0..8 {
-            if let Some(pos) = &alternative.variants[s] {
+        for (s, pos) in IntoIterator::into_iter(alternative.variants).enumerate() {
+            if let Some(pos) = pos {
                 if !self.variants[s].as_ref().is_some_and(|a| a.duration <= pos.duration) {
-                    self.variants[s] = Some(pos.clone());
+                    self.variants[s] = Some(pos);
                 }
             }
         }
     }
 
-    fn merge_fast(&mut self, pos: Position) {
-        let sail_index = pos.settings.sail.index.clone();
-        let sail_index = 0;
+    pub(crate) fn merge_fast(&mut self, pos: Position) {
+        let sail_index = Self::slot(&pos.settings.sail);
 
         let renew = match &self.variants[sail_index] {
             None => { true }
@@ -1067,42 +1312,55 @@ impl Alternative {
     }
 
     fn merge(&mut self, pos: Position) {
-        let sail_index = pos.settings.sail.index.clone();
-        let sail_index = 0;
+        let sail_index = Self::slot(&pos.settings.sail);
 
         if self.variants[sail_index].is_none() || pos.better_than(self.variants[sail_index].as_ref().unwrap()) {
             self.variants[sail_index] = Some(pos);
         }
     }
 
-    fn best(&self) -> Option<Position> {
+    pub(crate) fn best(&self) -> Option<Position> {
 
-        let mut best = None;
+        let mut best: Option<&Position> = None;
 
-        for s in 0..8 {
-            self.variants[s].as_ref().map(|v| {
-                let best = best.get_or_insert_with(|| self.variants[s].clone().unwrap());
-                if v.from_dist > best.from_dist {
-                    *best = v.clone();
-                }
-            });
+        for v in self.variants.iter().flatten() {
+            if best.is_none_or(|best| v.better_than(best)) {
+                best = Some(v);
+            }
         }
 
-        best
+        best.cloned()
     }
 
-    fn _get(&self, sail: usize) -> Option<Position> {
+    /// Drops the worst variants beyond `max`, returning how many were dropped.
+    pub(crate) fn keep_best(&mut self, max: usize) -> usize {
+        let mut dropped = 0;
 
-        let mut best = None;
+        while self.variants.iter().flatten().count() > max {
+            let worst = self.variants.iter().enumerate()
+                .filter_map(|(s, v)| v.as_ref().map(|v| (s, v)))
+                .reduce(|worst, (s, v)| if worst.1.better_than(v) { (s, v) } else { worst })
+                .map(|(s, _)| s);
 
-        self.variants[sail].as_ref().map(|v| {
-            let best = best.get_or_insert_with(|| self.variants[sail].clone().unwrap());
-            if v.from_dist > best.from_dist {
-                *best = v.clone();
+            match worst {
+                Some(s) => self.variants[s] = None,
+                None => break,
             }
-        });
+            dropped += 1;
+        }
 
-        best
+        dropped
+    }
+
+    /// Position kept for the `sail` index.
+    #[cfg(test)]
+    pub(crate) fn get(&self, sail: usize) -> Option<&Position> {
+        self.variants.get(sail).and_then(Option::as_ref)
+    }
+
+    /// Variant slot of `sail`, the last one gathering any extra sail.
+    fn slot(sail: &Sail) -> usize {
+        sail.index.min(7)
     }
 }
 
@@ -1110,7 +1368,7 @@ impl From<RouteRequest> for Alternative {
     fn from(route_request: RouteRequest) -> Self {
 
         let mut variants = [None, None, None, None, None, None, None, None];
-        let sail_index = route_request.boat_settings.sail.index;
+        let sail_index = Alternative::slot(&route_request.boat_settings.sail);
         variants[sail_index] = Some(route_request.into());
 
         Alternative {
@@ -1120,6 +1378,7 @@ impl From<RouteRequest> for Alternative {
 }
 
 
+/// Only the `status` is shared between copies : the settings, the penalties left and the `reached` name are still cloned with the position.
 #[derive(Clone)]
 pub(crate) struct Position {
     pub(crate) az: i32,
@@ -1130,7 +1389,8 @@ pub(crate) struct Position {
     pub(crate) distance: Distance,
     pub(crate) reached: Option<String>,
     pub(crate) settings: BoatSettings,
-    pub(crate) status: BoatStatus,
+    /// Shared with the copies made while merging alternatives.
+    pub(crate) status: Arc<BoatStatus>,
     pub(crate) previous: Option<Arc<Position>>,
     pub(crate) is_in_ice_limits: bool,
     pub(crate) remaining_penalties: Penalties,
@@ -1159,6 +1419,26 @@ unsafe impl Send for Position {}
 unsafe impl Sync for Position {}
 
 impl Position {
+    /// Ancestors, from the start.
+    fn history(&self) -> Vec<*const Position> {
+        let mut history = Vec::new();
+        let mut p = self;
+        while let Some(previous) = p.previous.as_ref() {
+            history.push(Arc::as_ptr(previous));
+            p = previous;
+        }
+        history.reverse();
+        history
+    }
+
+    /// Whether both positions share more than 90% of their ancestors.
+    fn shares_history(&self, other: &Position) -> bool {
+        let (history, other_history) = (self.history(), other.history());
+        let common = history.iter().zip(other_history.iter()).take_while(|(a, b)| a == b).count();
+
+        common * 10 > history.len().min(other_history.len()) * 9
+    }
+
     fn reached(&self, buoy: &Buoy) -> Self {
         let mut reached = self.clone();
         reached.reached = Some(buoy.name().clone());
@@ -1215,7 +1495,21 @@ impl Position {
         }
         */
 
-        self.from_dist > other.from_dist
+        self.ranking(other) == Ordering::Greater
+    }
+
+    /// Total order of positions : farthest first, then fewer remaining penalty seconds,
+    /// lower sail index and lower az, so that ties don't depend on merge order.
+    fn ranking(&self, other: &Position) -> Ordering {
+        self.from_dist.cmp(&other.from_dist)
+            .then_with(|| other.remaining_penalties.duration().num_seconds().cmp(&self.remaining_penalties.duration().num_seconds()))
+            .then_with(|| other.settings.sail.index.cmp(&self.settings.sail.index))
+            .then_with(|| other.az.cmp(&self.az))
+    }
+
+    /// Bearing followed from the previous position to this one according to `algorithm`, `None` at start.
+    pub(crate) fn heading_to_next(&self, algorithm: &dyn Algorithm) -> Option<f64> {
+        self.previous.as_ref().map(|previous| algorithm.heading_to(&previous.point, &self.point))
     }
 
     fn _common_ancestor(&self, other: &Arc<Position>) -> Option<Arc<Position>> {
@@ -1241,7 +1535,7 @@ impl From<RouteRequest> for Position {
             distance: Distance::zero(),
             reached: None,
             settings: route_request.boat_settings.clone(),
-            status: route_request.status.clone(),
+            status: Arc::new(route_request.status.clone()),
             previous: None,
             is_in_ice_limits: false,
             remaining_penalties: route_request.status.penalties.clone(),
@@ -1250,28 +1544,53 @@ impl From<RouteRequest> for Position {
     }
 }
 
+/// Headings explored from `point` according to `mode`, every `step_tenths` tenths of degree.
+///
+/// In `Twa` mode, every heading is explored near land to thread between obstacles.
+pub(crate) fn exploration_headings(lands_provider: &(dyn LandsProvider + Send + Sync), point: &Coords, mode: ExplorationMode, step_tenths: u16) -> impl Iterator<Item = Heading> {
+    let (twas, headings) = match mode {
+        ExplorationMode::Twa if lands_provider.near_land(point.lat, point.lon) => (false, true),
+        ExplorationMode::Twa => (true, false),
+        ExplorationMode::Heading => (false, true),
+        ExplorationMode::Both => (true, true),
+    };
+
+    let step = step_tenths.max(1) as usize;
+    let angles = move || (0..3600).step_by(step).map(|tenths| tenths as f64 / 10.0);
 
-fn get_buoys(race: &Race, boat: Coords) -> impl Iterator<Item = Buoy> {
+    let twas = angles().filter(move |_| twas).map(|angle| Heading::TWA(angle - 180.0));
+    let headings = angles().filter(move |_| headings).map(Heading::HEADING);
+
+    twas.chain(headings)
+}
+
+pub(crate) fn get_buoys(race: &Race, boat: Coords, algorithm: Arc<dyn Algorithm + Send + Sync>) -> impl Iterator<Item = Buoy> {
     let w = race.buoys.clone();
     w.into_iter().filter(|w| !w.is_validated())
-        .map(move |w| Buoy::from(w, boat.clone()))
+        .map(move |w| Buoy::from(w, boat.clone(), algorithm.clone()))
 }
 
 #[derive(Clone)]
 pub(crate) struct Buoy {
     inner: race::Buoy,
     reachers: Vec<Nav>,
+    algorithm: Arc<dyn Algorithm + Send + Sync>,
 }
 
 impl Buoy {
 
-    fn from(buoy: race::Buoy, _boat: Coords) -> Self {
+    fn from(buoy: race::Buoy, _boat: Coords, algorithm: Arc<dyn Algorithm + Send + Sync>) -> Self {
         Self {
             inner: buoy,
             reachers: Vec::new(),
+            algorithm,
         }
     }
 
+    fn is_optional(&self) -> bool {
+        self.inner.is_optional()
+    }
+
     fn departure(&self) -> Coords {
         match &self.inner {
             race::Buoy::Door(door) => {
@@ -1298,43 +1617,26 @@ impl Buoy {
         }
     }
 
-    fn is_to_avoid(&self, point: &Coords) -> bool {
+    pub(crate) fn is_to_avoid(&self, point: &Coords) -> bool {
         let to_avoids = match &self.inner {
             race::Buoy::Door(door) => { &door.to_avoid }
             race::Buoy::Waypoint(waypoint) => { &waypoint.to_avoid }
             race::Buoy::Zone(zone) => { &zone.to_avoid }
         };
 
-        for t in to_avoids {
-            let as_x = point.lat - t.0.lat;
-            let as_y = point.lon - t.0.lon;
-
-            let s_ab = (t.1.lat-t.0.lat)*as_y-(t.1.lon-t.0.lon)*as_x > 0.0;
-
-            if ((t.2.lat-t.0.lat)*as_y-(t.2.lon-t.0.lon)*as_x > 0.0) == s_ab {
-                continue
-            }
-
-            if ((t.2.lat-t.1.lat)*(point.lon-t.1.lon)-(t.2.lon-t.1.lon)*(point.lat-t.1.lat) > 0.0) != s_ab {
-                continue
-            }
-
-            return true
-        }
-
-        false
+        to_avoids.iter().any(|t| race::is_in_triangle(point, t))
     }
 
     fn distance(&self, to: &Coords) -> Distance {
         match &self.inner {
             race::Buoy::Door(door) => {
-                Spherical{}.distance_to(&door.destination, to)
+                self.algorithm.distance_to(&door.destination, to)
             }
             race::Buoy::Waypoint(waypoint) => {
-                Spherical{}.distance_to(&waypoint.destination, to)
+                self.algorithm.distance_to(&waypoint.destination, to)
             }
             race::Buoy::Zone(zone) => {
-                Spherical{}.distance_to(&zone.destination, to) - &zone.radius
+                self.algorithm.distance_to(&zone.destination, to) - &zone.radius
             }
         }
     }
@@ -1342,20 +1644,20 @@ impl Buoy {
     fn _distance_and_heading_to(&self, to: &Coords) -> (Distance, f64) {
         match &self.inner {
             race::Buoy::Door(door) => {
-                Spherical{}.distance_and_heading_to(&door.destination, to)
+                self.algorithm.distance_and_heading_to(&door.destination, to)
             }
             race::Buoy::Waypoint(waypoint) => {
-                Spherical{}.distance_and_heading_to(&waypoint.destination, to)
+                self.algorithm.distance_and_heading_to(&waypoint.destination, to)
             }
             race::Buoy::Zone(zone) => {
-                let (distance, heading) = Spherical{}.distance_and_heading_to(&zone.destination, to);
+                let (distance, heading) = self.algorithm.distance_and_heading_to(&zone.destination, to);
                 (distance - &zone.radius, heading)
             }
         }
     }
 
-    fn crossed(&self, pos: &Position) -> bool {
-        let algorithm = Spherical{};
+    pub(crate) fn crossed(&self, pos: &Position) -> bool {
+        let algorithm = self.algorithm.as_ref();
         match &self.inner {
             race::Buoy::Door(door) => {
                 if let Some(src) = &pos.previous {
@@ -1389,7 +1691,8 @@ impl Buoy {
             },
             race::Buoy::Zone(zone) => {
                 if let Some(src) = &pos.previous {
-                    !zone.is_in(&src.point) && zone.is_in(&pos.point)
+                    let is_in = |point: &Coords| algorithm.distance_to(&zone.destination, point) <= zone.radius;
+                    !is_in(&src.point) && is_in(&pos.point) && zone.allows_entry(algorithm.heading_to(&src.point, &zone.destination))
                 } else {
                     false
                 }
@@ -1398,9 +1701,9 @@ impl Buoy {
         }
     }
 
-    fn reach(&mut self, pos: &Position, factor: f64) {
+    pub(crate) fn reach(&mut self, pos: &Position, factor: f64) {
 
-        let (dist, az) = Spherical{}.distance_and_heading_to(&self.departure(), &pos.point);
+        let (dist, az) = self.algorithm.distance_and_heading_to(&self.departure(), &pos.point);
 
         let reachers = match self.inner {
             race::Buoy::Waypoint(_) => {
@@ -1454,8 +1757,12 @@ impl Buoy {
         });
     }
 
-    fn reachers(&self) -> Vec<Nav> {
-        self.reachers.clone()
+    /// The `max` earliest navs that reached the buoy, by duration.
+    pub(crate) fn reachers(&self, max: usize) -> Vec<Nav> {
+        let mut reachers = self.reachers.clone();
+        reachers.sort_by_key(|reacher| reacher.absolute_duration);
+        reachers.truncate(max);
+        reachers
     }
 
     fn is_waypoint(&self) -> bool {
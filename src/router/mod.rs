@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use chrono_humanize::HumanTime;
@@ -9,17 +11,58 @@ use serde::{Serialize, Serializer, Deserialize};
 use tsify_next::Tsify;
 use wasm_bindgen::prelude::*;
 use crate::phtheirichthys::BoatOptions;
-use crate::position::{Heading, Penalties, Coords, BoatSettings, BoatStatus};
+use crate::land::LandsProvider;
+use crate::polar::Polar;
+use crate::position::{Heading, Penalties, PenaltyKind, Coords, BoatSettings, BoatStatus};
 use crate::wind::Wind;
 use crate::{position, race::Race};
-use crate::utils::Speed;
+use crate::utils::{Distance, Speed};
+use crate::algorithm::Algorithm;
+use crate::algorithm::great_circle::GreatCircle;
 
 // pub(crate) mod phtheirichthys;
 pub(crate) mod echeneis;
 
 #[async_trait]
 pub(crate) trait Router {
-  async fn route(&self, race: &Race, boat_options: BoatOptions, request: RouteRequest, timeout: Option<Duration>) -> Result<RouteResult>;
+  async fn route(&self, race: &Race, boat_options: BoatOptions, request: RouteRequest, timeout: Option<Duration>, cancellation: Option<CancellationToken>) -> Result<RouteResult>;
+}
+
+/// Shared flag used to abort an in-flight routing.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+/// Shared best waypoint of an in-flight routing, updated at each step for clients polling it.
+#[derive(Clone, Debug, Default)]
+pub struct LiveBest(Arc<Mutex<Option<RouteWaypoint>>>);
+
+impl LiveBest {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub(crate) fn set(&self, best: Option<RouteWaypoint>) {
+    *self.0.lock().unwrap() = best;
+  }
+
+  /// Snapshot of the best waypoint so far, `None` before the first step.
+  pub(crate) fn get(&self) -> Option<RouteWaypoint> {
+    self.0.lock().unwrap().clone()
+  }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Tsify)]
@@ -34,7 +77,23 @@ pub struct RouteRequest {
   pub steps: Vec<(Duration, Duration)>,
 }
 
-fn default_steps() -> Vec<(Duration, Duration)> {
+impl RouteRequest {
+  /// Checks the starting boat status : stamina in [0, 100] and no negative penalty.
+  pub(crate) fn validate(&self) -> Result<()> {
+    let stamina = self.status.stamina;
+    if !(0.0..=100.0).contains(&stamina) {
+      bail!("Invalid stamina {stamina} : should be in [0, 100]");
+    }
+
+    if let Some((kind, penalty)) = self.status.penalties.iter().find(|(_, penalty)| penalty.duration < Duration::zero()) {
+      bail!("Invalid {kind:?} penalty : negative duration {}", penalty.duration);
+    }
+
+    Ok(())
+  }
+}
+
+pub(crate) fn default_steps() -> Vec<(Duration, Duration)> {
   vec![
     (Duration::hours(1),    Duration::minutes(10)),
     (Duration::hours(6),    Duration::hours(1)),
@@ -49,29 +108,153 @@ fn default_steps() -> Vec<(Duration, Duration)> {
 pub(crate) struct RouteInfos {
   pub(crate) start: DateTime<Utc>,
   duration: f64,
-  success: bool,
+  pub(crate) success: bool,
   sails_duration: HashMap<usize, f64>,
   foil_duration: f64,
+  /// Index of the first waypoint whose segment to the next one crosses land, see `RouteResult::crosses_land`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub(crate) crosses_land: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[serde(rename_all = "camelCase")]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub(crate) struct RouteResult {
+pub struct RouteResult {
   pub(crate) infos: RouteInfos,
   pub(crate) way: Vec<RouteWaypoint>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub(crate) alternatives: Vec<Vec<RouteWaypoint>>,
   sections: Vec<IsochroneSection>,
   debug: Vec<IsochronePoint>,
 }
 
+impl RouteResult {
+  /// Drops waypoints closer than `tolerance` to the simplified way, see `simplify_way`.
+  pub(crate) fn simplify(&mut self, tolerance: Distance, algorithm: &dyn Algorithm) {
+    simplify_way(&mut self.way, &tolerance, algorithm);
+    for way in self.alternatives.iter_mut() {
+      simplify_way(way, &tolerance, algorithm);
+    }
+  }
+
+  /// Isochrones computed for each buoy, for the map.
+  pub fn sections(&self) -> &[IsochroneSection] {
+    &self.sections
+  }
+
+  /// Index of the first waypoint whose segment to the next one crosses land, if any.
+  pub(crate) fn crosses_land(&self, lands: &dyn LandsProvider) -> Option<usize> {
+    self.way.windows(2).position(|segment| lands.cross_next_land(&segment[0].from, &segment[1].from))
+  }
+
+  /// Time and wind of each waypoint of the way, for charting.
+  pub(crate) fn wind_profile(&self) -> Vec<(DateTime<Utc>, Wind)> {
+    self.way.iter()
+      .map(|waypoint| (self.infos.start + waypoint.duration, waypoint.status.wind.clone()))
+      .collect()
+  }
+
+  /// Great circle distance left at each waypoint of the way, through the destinations of the buoys not passed yet.
+  pub(crate) fn remaining_distances(&self, race: &Race) -> Vec<Distance> {
+    let algorithm = GreatCircle {};
+    let buoys = race.buoys.iter().filter(|buoy| !buoy.is_validated()).collect::<Vec<_>>();
+
+    // from the destination of each buoy to the end of the course
+    let mut beyond = vec![Distance::zero(); buoys.len()];
+    for i in (1..buoys.len()).rev() {
+      beyond[i - 1] = beyond[i].clone() + algorithm.distance_to(buoys[i - 1].destination(), buoys[i].destination());
+    }
+
+    let mut next = 0;
+    self.way.iter().enumerate().map(|(i, waypoint)| {
+      if i > 0 {
+        while next < buoys.len() && buoys[next].is_passed_by(&self.way[i - 1].from, &waypoint.from, &algorithm) {
+          next += 1;
+        }
+      }
+
+      match buoys.get(next) {
+        Some(buoy) => algorithm.distance_to(&waypoint.from, buoy.destination()) + &beyond[next],
+        None => Distance::zero(),
+      }
+    }).collect()
+  }
+
+  /// One telemetry row per waypoint of the way, sails being named after the `polar`'s labels.
+  pub(crate) fn telemetry(&self, polar: &Polar) -> Vec<Telemetry> {
+    self.way.iter().map(|waypoint| Telemetry::from((waypoint, polar))).collect()
+  }
+}
+
+/// Douglas-Peucker on the waypoints, keeping the ones where the sail or the heading mode change.
+///
+/// The `way_duration` of a dropped waypoint is added to the previous kept one.
+pub(crate) fn simplify_way(way: &mut Vec<RouteWaypoint>, tolerance: &Distance, algorithm: &dyn Algorithm) {
+  if way.len() < 3 {
+    return;
+  }
+
+  let mut keep = vec![false; way.len()];
+  keep[0] = true;
+  keep[way.len() - 1] = true;
+  for i in 1..way.len() {
+    let (previous, current) = (&way[i - 1].boat_settings, &way[i].boat_settings);
+    if previous.sail != current.sail || previous.heading.is_regulated() != current.heading.is_regulated() {
+      keep[i] = true;
+    }
+  }
+
+  let anchors = keep.iter().enumerate().filter(|(_, keep)| **keep).map(|(i, _)| i).collect::<Vec<_>>();
+  let mut stack = anchors.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>();
+
+  while let Some((first, last)) = stack.pop() {
+    if last - first < 2 {
+      continue;
+    }
+
+    let path = (&way[first].from, &way[last].from);
+    let (farthest, distance) = (first + 1..last)
+      .map(|i| (i, algorithm.cross_track_distance(path, &way[i].from).m().abs()))
+      .fold((first, 0.0), |farthest, candidate| if candidate.1 > farthest.1 { candidate } else { farthest });
+
+    if distance > tolerance.m() {
+      keep[farthest] = true;
+      stack.push((first, farthest));
+      stack.push((farthest, last));
+    }
+  }
+
+  let mut simplified: Vec<RouteWaypoint> = Vec::with_capacity(way.len());
+  for (waypoint, keep) in way.drain(..).zip(keep) {
+    match simplified.last_mut() {
+      Some(previous) if !keep => previous.way_duration = previous.way_duration + waypoint.way_duration,
+      _ => simplified.push(waypoint),
+    }
+  }
+
+  for i in 1..simplified.len() {
+    if simplified[i - 1].bearing.is_some() {
+      simplified[i - 1].bearing = Some(simplified[i - 1].bearing_to(&simplified[i].from, algorithm));
+    }
+  }
+
+  *way = simplified;
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub(crate) struct RouteWaypoint {
   pub(crate) from: Coords,
-  #[serde(serialize_with = "duration_to_seconds", deserialize_with = "seconds_to_duration")]
+  #[cfg_attr(not(feature = "iso-durations"), serde(serialize_with = "duration_to_seconds", deserialize_with = "seconds_to_duration"))]
+  #[cfg_attr(feature = "iso-durations", serde(with = "duration_iso8601"))]
   pub(crate) duration: Duration,
-  #[serde(serialize_with = "duration_to_seconds", deserialize_with = "seconds_to_duration")]
+  #[cfg_attr(not(feature = "iso-durations"), serde(serialize_with = "duration_to_seconds", deserialize_with = "seconds_to_duration"))]
+  #[cfg_attr(feature = "iso-durations", serde(with = "duration_iso8601"))]
   pub(crate) way_duration: Duration,
+  /// Bearing to the next waypoint, `None` on the last one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[tsify(optional)]
+  pub(crate) bearing: Option<f64>,
   pub(crate) boat_settings: BoatSettings,
   pub(crate) status: WaypointStatus,
 }
@@ -89,6 +272,93 @@ where D: serde::Deserializer<'de>
   Ok(Duration::seconds(buf))
 }
 
+/// ISO-8601 durations, as `PT1H10M`, to the second. Days are only read, never written.
+pub(crate) mod duration_iso8601 {
+  use chrono::Duration;
+  use serde::{de, Deserialize, Deserializer, Serializer};
+
+  pub(crate) fn format(duration: &Duration) -> String {
+    let seconds = duration.num_seconds();
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+
+    let (h, m, s) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    let mut iso = format!("{sign}PT");
+    if h > 0 {
+      iso.push_str(&format!("{h}H"));
+    }
+    if m > 0 {
+      iso.push_str(&format!("{m}M"));
+    }
+    if s > 0 || seconds == 0 {
+      iso.push_str(&format!("{s}S"));
+    }
+
+    iso
+  }
+
+  pub(crate) fn parse(iso: &str) -> Option<Duration> {
+    let (sign, iso) = match iso.strip_prefix('-') {
+      Some(iso) => (-1, iso),
+      None => (1, iso),
+    };
+    let iso = iso.strip_prefix('P')?;
+
+    let mut seconds = 0_i64;
+    let mut time = false;
+    let mut number = String::new();
+    for c in iso.chars() {
+      match c {
+        'T' if !time && number.is_empty() => time = true,
+        '0'..='9' => number.push(c),
+        unit => {
+          let value: i64 = number.parse().ok()?;
+          number.clear();
+          seconds += value * match (time, unit) {
+            (false, 'D') => 86_400,
+            (true, 'H') => 3_600,
+            (true, 'M') => 60,
+            (true, 'S') => 1,
+            _ => return None,
+          };
+        }
+      }
+    }
+
+    if !number.is_empty() {
+      return None;
+    }
+
+    Some(Duration::seconds(sign * seconds))
+  }
+
+  pub(crate) fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+    serializer.serialize_str(&format(duration))
+  }
+
+  pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where D: Deserializer<'de> {
+    let iso = String::deserialize(deserializer)?;
+
+    parse(&iso).ok_or_else(|| de::Error::custom(format!("invalid ISO-8601 duration `{iso}`")))
+  }
+}
+
+impl RouteWaypoint {
+  /// Bearing from this waypoint to `to` according to `algorithm`.
+  pub(crate) fn bearing_to(&self, to: &Coords, algorithm: &dyn Algorithm) -> f64 {
+    algorithm.heading_to(&self.from, to)
+  }
+
+  /// Name of the sail in the polar's own labels.
+  pub(crate) fn sail_label(&self, polar: &Polar) -> String {
+    let sail = &self.boat_settings.sail;
+    format!("{}{}", polar.sail_label(sail.index), if sail.auto {"*"} else {""})
+  }
+}
+
 impl Display for RouteWaypoint {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self.boat_settings.heading {
@@ -141,38 +411,108 @@ pub(crate) struct Penalty {
   #[serde(serialize_with = "duration_to_seconds", deserialize_with = "seconds_to_duration")]
   pub(crate) duration: Duration,
   pub(crate) ratio: f64,
-  pub(crate) typ: u8,
+  pub(crate) typ: PenaltyType,
 }
 
-impl From<position::Penalty> for Penalty {
-  fn from(penalty: position::Penalty) -> Self {
+/// Maneuver a penalty comes from, waypoint penalties being sorted in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub(crate) enum PenaltyType {
+  Gybe,
+  Tack,
+  SailChange,
+}
+
+impl From<PenaltyKind> for PenaltyType {
+  fn from(kind: PenaltyKind) -> Self {
+    match kind {
+      PenaltyKind::Gybe => PenaltyType::Gybe,
+      PenaltyKind::Tack => PenaltyType::Tack,
+      PenaltyKind::SailChange => PenaltyType::SailChange,
+    }
+  }
+}
+
+/// Flat summary of a waypoint, for logging.
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub(crate) struct Telemetry {
+  /// Seconds since the start of the route.
+  pub(crate) time: i64,
+  pub(crate) lat: f64,
+  pub(crate) lon: f64,
+  pub(crate) heading: f64,
+  pub(crate) twa: f64,
+  pub(crate) sail: String,
+  pub(crate) boat_speed: f64,
+  pub(crate) wind_direction: f64,
+  pub(crate) wind_speed: f64,
+  pub(crate) foil: u8,
+  pub(crate) boost: u8,
+  pub(crate) stamina: f64,
+  /// Total duration of the penalties, in seconds.
+  pub(crate) penalties: i64,
+}
+
+impl From<(&RouteWaypoint, &Polar)> for Telemetry {
+  fn from((waypoint, polar): (&RouteWaypoint, &Polar)) -> Self {
+    let status = &waypoint.status;
+    let twd = status.wind.direction;
+
+    Self {
+      time: waypoint.duration.num_seconds(),
+      lat: waypoint.from.lat,
+      lon: waypoint.from.lon,
+      heading: waypoint.boat_settings.heading.heading(twd),
+      twa: waypoint.boat_settings.heading.twa(twd),
+      sail: waypoint.sail_label(polar),
+      boat_speed: status.boat_speed.kts(),
+      wind_direction: twd,
+      wind_speed: status.wind.speed.kts(),
+      foil: status.foil,
+      boost: status.boost,
+      stamina: status.stamina,
+      penalties: status.penalties.iter().map(|penalty| penalty.duration.num_seconds()).sum(),
+    }
+  }
+}
+
+impl From<(PenaltyKind, position::Penalty)> for Penalty {
+  fn from((kind, penalty): (PenaltyKind, position::Penalty)) -> Self {
     Self {
       duration: penalty.duration,
       ratio: penalty.ratio,
-      typ: 0
+      typ: kind.into(),
     }
   }
 }
 
+/// Isochrones computed while sailing to a buoy.
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-struct IsochroneSection {
-  door: String,
-  isochrones: Vec<Isochrone>
+pub struct IsochroneSection {
+  /// Name of the buoy.
+  pub door: String,
+  pub isochrones: Vec<Isochrone>
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-struct Isochrone {
-  color: String,
-  paths: Vec<Vec<IsochronePoint>>,
+pub struct Isochrone {
+  /// `%24`, `%6` or `%1` on a whole day, 6 hours or hour since the start, `%0` otherwise.
+  pub color: String,
+  /// Points by increasing azimuth, split where the azimuths are too far apart.
+  pub paths: Vec<Vec<IsochronePoint>>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-struct IsochronePoint {
-  pub(crate) lat: f64,
-  pub(crate) lon: f64,
-  pub(crate) az: i32,
-  pub(crate) previous: i32,
+pub struct IsochronePoint {
+  pub lat: f64,
+  pub lon: f64,
+  /// Azimuth from the start, scaled by the routing accuracy.
+  pub az: i32,
+  /// `az` of the point it comes from on the previous displayed isochrone, -1 if none.
+  pub previous: i32,
 }
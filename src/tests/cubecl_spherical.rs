@@ -0,0 +1,120 @@
+use cubecl::prelude::*;
+use cubecl::wgpu::WgpuRuntime;
+
+use crate::algorithm::cubecl_spherical::{destination_array, heading_to_array};
+use crate::algorithm::spherical::Spherical;
+use crate::algorithm::Algorithm;
+use crate::position::Coords;
+use crate::utils::Distance;
+
+const CUBE_DIM: u32 = 64;
+
+/// Departures, headings and distances covering both hemispheres, the antimeridian and courses close to east and west.
+fn jumps() -> Vec<(Coords, f64, f64)> {
+    let mut jumps = Vec::new();
+    for lat in [-60.0, -30.0, 0.0, 30.0, 60.0] {
+        for lon in [-170.0, -10.0, 45.0, 179.5] {
+            for heading in (0..24).map(|i| i as f64 * 15.0).chain([89.9, 270.1]) {
+                for distance in [10_000.0, 100_000.0] {
+                    jumps.push((Coords { lat, lon }, heading, distance));
+                }
+            }
+        }
+    }
+    jumps
+}
+
+#[test]
+fn heading_to_array_matches_spherical() {
+    let Ok(client) = std::panic::catch_unwind(|| WgpuRuntime::client(&Default::default())) else {
+        eprintln!("No gpu adapter available, skipping");
+        return
+    };
+
+    let jumps = jumps();
+    let from: Vec<Coords> = jumps.iter().map(|(from, _, _)| from.clone()).collect();
+    let to: Vec<Coords> = jumps.iter().map(|(from, heading, distance)| Spherical {}.destination(from, *heading, &Distance::from_m(*distance))).collect();
+
+    let from_lat: Vec<f32> = from.iter().map(|c| c.lat as f32).collect();
+    let from_lon: Vec<f32> = from.iter().map(|c| c.lon as f32).collect();
+    let to_lat: Vec<f32> = to.iter().map(|c| c.lat as f32).collect();
+    let to_lon: Vec<f32> = to.iter().map(|c| c.lon as f32).collect();
+
+    let from_lat_handle = client.create(f32::as_bytes(&from_lat));
+    let from_lon_handle = client.create(f32::as_bytes(&from_lon));
+    let to_lat_handle = client.create(f32::as_bytes(&to_lat));
+    let to_lon_handle = client.create(f32::as_bytes(&to_lon));
+    let heading_handle = client.empty(jumps.len() * core::mem::size_of::<f32>());
+
+    unsafe {
+        heading_to_array::launch_unchecked::<F32, WgpuRuntime>(
+            &client,
+            CubeCount::Static((jumps.len() as u32).div_ceil(CUBE_DIM), 1, 1),
+            CubeDim::new(CUBE_DIM, 1, 1),
+            ArrayArg::from_raw_parts(&from_lat_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&from_lon_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&to_lat_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&to_lon_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&heading_handle, jumps.len(), 1),
+        )
+    };
+
+    let bytes = client.read(heading_handle.binding());
+    let headings = f32::from_bytes(&bytes);
+
+    for i in 0..jumps.len() {
+        // from the f32 inputs, as the kernel sees them
+        let expected = Spherical {}.heading_to(&Coords { lat: from_lat[i] as f64, lon: from_lon[i] as f64 }, &Coords { lat: to_lat[i] as f64, lon: to_lon[i] as f64 });
+        let gap = (expected - headings[i] as f64).rem_euclid(360.0);
+        assert!(gap.min(360.0 - gap) < 0.02, "{:?}: {} instead of {}", jumps[i], headings[i], expected);
+    }
+}
+
+#[test]
+fn destination_array_matches_spherical() {
+    let Ok(client) = std::panic::catch_unwind(|| WgpuRuntime::client(&Default::default())) else {
+        eprintln!("No gpu adapter available, skipping");
+        return
+    };
+
+    let jumps = jumps();
+    let from_lat: Vec<f32> = jumps.iter().map(|(from, _, _)| from.lat as f32).collect();
+    let from_lon: Vec<f32> = jumps.iter().map(|(from, _, _)| from.lon as f32).collect();
+    let heading: Vec<f32> = jumps.iter().map(|(_, heading, _)| *heading as f32).collect();
+    let distance: Vec<f32> = jumps.iter().map(|(_, _, distance)| *distance as f32).collect();
+
+    let from_lat_handle = client.create(f32::as_bytes(&from_lat));
+    let from_lon_handle = client.create(f32::as_bytes(&from_lon));
+    let heading_handle = client.create(f32::as_bytes(&heading));
+    let distance_handle = client.create(f32::as_bytes(&distance));
+    let to_lat_handle = client.empty(jumps.len() * core::mem::size_of::<f32>());
+    let to_lon_handle = client.empty(jumps.len() * core::mem::size_of::<f32>());
+
+    unsafe {
+        destination_array::launch_unchecked::<F32, WgpuRuntime>(
+            &client,
+            CubeCount::Static((jumps.len() as u32).div_ceil(CUBE_DIM), 1, 1),
+            CubeDim::new(CUBE_DIM, 1, 1),
+            ArrayArg::from_raw_parts(&from_lat_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&from_lon_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&heading_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&distance_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&to_lat_handle, jumps.len(), 1),
+            ArrayArg::from_raw_parts(&to_lon_handle, jumps.len(), 1),
+        )
+    };
+
+    let bytes = client.read(to_lat_handle.binding());
+    let to_lat = f32::from_bytes(&bytes);
+    let bytes = client.read(to_lon_handle.binding());
+    let to_lon = f32::from_bytes(&bytes);
+
+    for i in 0..jumps.len() {
+        let from = Coords { lat: from_lat[i] as f64, lon: from_lon[i] as f64 };
+        let expected = Spherical {}.destination(&from, heading[i] as f64, &Distance::from_m(distance[i] as f64));
+
+        // 1e-3° is about 100 m
+        assert!((to_lat[i] as f64 - expected.lat).abs() < 1e-3, "{:?}: lat {} instead of {}", jumps[i], to_lat[i], expected.lat);
+        assert!((to_lon[i] as f64 - expected.lon).abs() < 1e-3, "{:?}: lon {} instead of {}", jumps[i], to_lon[i], expected.lon);
+    }
+}
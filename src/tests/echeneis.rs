@@ -0,0 +1,722 @@
+use std::sync::Arc;
+
+use crate::algorithm::spherical::Spherical;
+use crate::error::PhtError;
+use crate::phtheirichthys::BoatOptions;
+use crate::position::Coords;
+use crate::router::echeneis::{Echeneis, EcheneisConfig};
+use crate::router::{CancellationToken, Router};
+use crate::tests::fixtures;
+use crate::utils::Speed;
+use crate::wind::Wind;
+
+#[tokio::test]
+async fn cancelled_before_first_step() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+
+    let e = fixtures::router(EcheneisConfig::default()).route(&race, BoatOptions::new(), fixtures::route_request(from), None, Some(cancellation)).await.err().expect("cancelled");
+
+    assert_eq!(e.downcast_ref::<PhtError>(), Some(&PhtError::Cancelled));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancelled_mid_route() {
+    use crate::router::LiveBest;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: 20.0 }, 1.0);
+
+    let cancellation = CancellationToken::new();
+    let live_best = LiveBest::new();
+    let router = fixtures::router(EcheneisConfig::default()).with_live_best(live_best.clone());
+
+    let handle = {
+        let cancellation = cancellation.clone();
+        tokio::spawn(async move {
+            router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, Some(cancellation)).await
+        })
+    };
+
+    // flipped once the first steps are done
+    while live_best.get().is_none() && !handle.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+    cancellation.cancel();
+
+    let e = handle.await.expect("join").err().expect("cancelled");
+
+    assert_eq!(e.downcast_ref::<PhtError>(), Some(&PhtError::Cancelled));
+}
+
+#[test]
+fn heading_exploration_near_land() {
+    use crate::position::Heading;
+    use crate::router::echeneis::{exploration_headings, ExplorationMode};
+
+    // coastline along the 46th parallel, land to the north
+    let lands = fixtures::Lands(|lat, _| lat > 46.0);
+
+    let near = exploration_headings(&lands, &Coords { lat: 45.999, lon: -5.0 }, ExplorationMode::Twa, 10).collect::<Vec<_>>();
+    assert_eq!(near.len(), 360);
+    assert_eq!(near.first(), Some(&Heading::HEADING(0.0)));
+    assert_eq!(near.last(), Some(&Heading::HEADING(359.0)));
+
+    let far = exploration_headings(&lands, &Coords { lat: 45.0, lon: -5.0 }, ExplorationMode::Twa, 10).collect::<Vec<_>>();
+    assert_eq!(far.len(), 360);
+    assert_eq!(far.first(), Some(&Heading::TWA(-180.0)));
+    assert_eq!(far.last(), Some(&Heading::TWA(179.0)));
+}
+
+#[test]
+fn door_crossed_under_both_algorithms() {
+    use crate::algorithm::Algorithm;
+    use crate::algorithm::great_circle::GreatCircle;
+    use crate::position::{BoatSettings, Heading};
+    use crate::router::echeneis::{get_buoys, Position};
+
+    let race = fixtures::race_through(Coords { lat: 46.0, lon: -1.0 }, serde_json::json!([{
+        "type": "Door", "name": "gate",
+        "port": {"lat": 46.1, "lon": 0.0}, "starboard": {"lat": 45.9, "lon": 0.0},
+        "departure": {"lat": 46.0, "lon": 0.0}, "destination": {"lat": 46.0, "lon": 0.0},
+        "to_avoid": [], "validated": false
+    }]));
+
+    let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -0.01 }));
+    let settings = BoatSettings { heading: Heading::HEADING(90.0), ..Default::default() };
+    let position = |lon: f64| Position {
+        point: Coords { lat: 46.0, lon },
+        settings: settings.clone(),
+        previous: Some(Arc::new(src.clone())),
+        ..src.clone()
+    };
+
+    let algorithms: [Arc<dyn Algorithm + Send + Sync>; 2] = [Arc::new(Spherical {}), Arc::new(GreatCircle {})];
+    for algorithm in algorithms {
+        let door = get_buoys(&race, race.start.clone(), algorithm).next().expect("door");
+
+        assert!(door.crossed(&position(0.01)));
+        assert!(!door.crossed(&position(-0.005)));
+    }
+}
+
+#[test]
+fn route_request_validation() {
+    use chrono::Duration;
+    use crate::position::Penalty;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    assert!(fixtures::route_request(from.clone()).validate().is_ok());
+
+    let mut request = fixtures::route_request(from.clone());
+    request.status.stamina = 150.0;
+    assert!(request.validate().is_err());
+
+    let mut request = fixtures::route_request(from);
+    request.status.penalties.tack = Some(Penalty { duration: Duration::seconds(-60), ratio: 0.5 });
+    assert!(request.validate().is_err());
+}
+
+#[test]
+fn current_shifts_destination() {
+    use chrono::Duration;
+    use crate::algorithm::Algorithm;
+    use crate::polar::PolarCache;
+    use crate::position::Heading;
+    use crate::router::echeneis::Position;
+
+    let algorithm = Arc::new(Spherical {});
+    let mut polar = PolarCache::new(Arc::new(fixtures::polar()));
+    let boat_options = Arc::new(BoatOptions::new());
+    let from = Arc::new(Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -5.0 })));
+    let start = Arc::new(from.point.clone());
+    let wind = Wind { direction: 90.0, speed: Speed::from_kts(10.0), gust: None };
+    let eastward = (90.0, Speed::from_kts(2.0));
+
+    let mut jump = |current: Option<&(f64, Speed)>| {
+        Echeneis::jump2(&algorithm, None, None, &mut polar, &boat_options, &start, &from, &None, &Heading::HEADING(0.0), Duration::hours(1), &wind, current, 1.0, true)
+            .into_iter().map(|(_, pos)| pos.point).next().expect("a position")
+    };
+
+    let still = jump(None);
+    let drifted = jump(Some(&eastward));
+
+    let (drift, heading) = algorithm.distance_and_heading_to(&still, &drifted);
+    assert!((drift.nm() - 2.0).abs() < 0.05, "drift {}", drift.nm());
+    assert!((heading - 90.0).abs() < 1.0, "heading {heading}");
+}
+
+#[test]
+fn over_ground() {
+    use crate::current::over_ground;
+    use crate::utils::Distance;
+
+    let (heading, distance) = over_ground(0.0, &Distance::from_nm(10.0), 90.0, &Distance::from_nm(2.0));
+
+    assert!((heading - 11.31).abs() < 0.01);
+    assert!((distance.nm() - 104f64.sqrt()).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn routed_with_current() {
+    use crate::current::ConstantCurrent;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+
+    let arrival = |router: Echeneis<Spherical>| {
+        let (race, from) = (race.clone(), from.clone());
+        async move {
+            let result = router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+            result.way.last().expect("waypoint").duration
+        }
+    };
+
+    let still = arrival(fixtures::router(EcheneisConfig::default())).await;
+    let pushed = arrival(fixtures::router(EcheneisConfig::default()).with_currents(Arc::new(ConstantCurrent { direction: 90.0, speed: Speed::from_kts(2.0) }))).await;
+
+    assert!(pushed < still, "{} with the current, {} without", pushed, still);
+}
+
+#[tokio::test]
+async fn alternative_routes() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+    let config = EcheneisConfig { alternatives: 2, max_duration_hours: 12, ..Default::default() };
+
+    let router = fixtures::router(config);
+
+    let result = router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+
+    assert_eq!(result.alternatives.len(), 2);
+
+    let ending = |way: &Vec<crate::router::RouteWaypoint>| way.last().map(|waypoint| waypoint.from.clone()).expect("waypoint");
+    assert_ne!(ending(&result.alternatives[0]), ending(&result.alternatives[1]));
+    assert_ne!(ending(&result.way), ending(&result.alternatives[0]));
+}
+
+#[tokio::test]
+async fn straight_leg_in_constant_wind() {
+    use crate::algorithm::Algorithm;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let to = Coords { lat: 46.0, lon: -4.0 };
+    let race = fixtures::race(from.clone(), to.clone(), 1.0);
+
+    let result = fixtures::router(EcheneisConfig::default()).route(&race, BoatOptions::new(), fixtures::route_request(from.clone()), None, None).await.expect("route");
+
+    assert!(result.way.len() > 1);
+    for waypoint in result.way.iter() {
+        let off_track = Spherical {}.cross_track_distance((&from, &to), &waypoint.from);
+        assert!(off_track.nm().abs() < 1.0, "{} nm off track at {:?}", off_track.nm(), waypoint.from);
+    }
+}
+
+#[tokio::test]
+async fn heading_exploration_mode() {
+    use crate::router::echeneis::ExplorationMode;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+    let config = EcheneisConfig { exploration: ExplorationMode::Heading, max_duration_hours: 12, ..Default::default() };
+
+    let router = fixtures::router(config);
+
+    let result = router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+
+    // the last waypoint has no leg of its own
+    let legs = &result.way[..result.way.len() - 1];
+    assert!(!legs.is_empty());
+    assert!(legs.iter().all(|waypoint| !waypoint.boat_settings.heading.is_regulated()), "{:?}", legs);
+}
+
+#[cfg(not(feature = "wasm"))]
+#[test]
+fn blocking_route() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.5 }, 1.0);
+
+    let router = fixtures::router(EcheneisConfig::default());
+    let result = crate::utils::block_on(router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None)).expect("route");
+
+    assert!(result.infos.success);
+    assert!(result.way.len() > 1);
+}
+
+#[test]
+fn zone_entry_heading() {
+    use crate::router::echeneis::{get_buoys, Position};
+
+    let race = |entry_heading: serde_json::Value| fixtures::race_through(Coords { lat: 46.0, lon: -1.0 }, serde_json::json!([{
+        "type": "Zone", "name": "zone", "destination": {"lat": 46.0, "lon": 0.0}, "radius": 1.0,
+        "to_avoid": [], "validated": false, "entry_heading": entry_heading
+    }]));
+
+    // entering the zone from `from`, 1.25nm away, to `to`, 0.4nm away
+    let entering = |from: f64, to: f64| {
+        let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: from }));
+        Position { point: Coords { lat: 46.0, lon: to }, previous: Some(Arc::new(src.clone())), ..src }
+    };
+
+    let eastward = race(serde_json::json!([45.0, 135.0]));
+    let zone = get_buoys(&eastward, eastward.start.clone(), Arc::new(Spherical {})).next().expect("zone");
+    assert!(zone.crossed(&entering(-0.03, -0.01)));
+    assert!(!zone.crossed(&entering(0.03, 0.01)));
+
+    let anywhere = race(serde_json::Value::Null);
+    let zone = get_buoys(&anywhere, anywhere.start.clone(), Arc::new(Spherical {})).next().expect("zone");
+    assert!(zone.crossed(&entering(-0.03, -0.01)));
+    assert!(zone.crossed(&entering(0.03, 0.01)));
+}
+
+#[tokio::test]
+async fn isochrone_sections() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+
+    let result = fixtures::router(EcheneisConfig::default()).route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+
+    let section = result.sections().first().expect("section");
+    assert_eq!(section.door, "end");
+    assert!(!section.isochrones.is_empty());
+    assert!(section.isochrones.iter().flat_map(|isochrone| isochrone.paths.iter().flatten()).count() > 0);
+}
+
+#[tokio::test]
+async fn isochrone_interval() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -3.0 }, 1.0);
+    let config = EcheneisConfig { isochrone_interval_minutes: Some(180), ..Default::default() };
+
+    let router = fixtures::router(config);
+
+    let result = router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+
+    // hourly steps, one isochrone every 3 hours
+    let minutes = result.way.last().expect("way").duration.num_minutes();
+    assert_eq!(result.sections()[0].isochrones.len() as i64, minutes / 180, "route of {} minutes", minutes);
+}
+
+#[tokio::test]
+async fn max_duration_hours() {
+    use chrono::Duration;
+    use crate::algorithm::Algorithm;
+
+    // about 8 hours away
+    let (from, finish) = (Coords { lat: 46.0, lon: -5.0 }, Coords { lat: 46.0, lon: -3.0 });
+    let race = fixtures::race(from.clone(), finish.clone(), 1.0);
+
+    let route = |max_duration_hours: u64| {
+        let router = fixtures::router(EcheneisConfig { max_duration_hours, ..Default::default() });
+        let (race, request) = (race.clone(), fixtures::route_request(from.clone()));
+        async move { router.route(&race, BoatOptions::new(), request, None, None).await.expect("route") }
+    };
+
+    let cut = route(4).await;
+    let last = cut.way.last().expect("way");
+    assert!(last.duration <= Duration::hours(4), "{} minutes", last.duration.num_minutes());
+    assert!(Spherical {}.distance_to(&last.from, &finish).nm() > 30.0);
+
+    let raised = route(24).await;
+    let last = raised.way.last().expect("way");
+    assert!(last.duration > Duration::hours(4));
+    assert!(Spherical {}.distance_to(&last.from, &finish).nm() < 2.0);
+}
+
+#[tokio::test]
+async fn reroute_from_midpoint() {
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+    let router = fixtures::router(EcheneisConfig::default());
+
+    let request = fixtures::route_request(from);
+    let start = request.start_time;
+    let previous = router.route(&race, BoatOptions::new(), request, None, None).await.expect("route");
+    let arrival = start + previous.way.last().expect("way").duration;
+
+    let midpoint = &previous.way[previous.way.len() / 2];
+    let (new_from, new_time) = (midpoint.from.clone(), start + midpoint.duration);
+
+    let result = router.reroute(&race, BoatOptions::new(), &previous, new_from.clone(), new_time, None).await.expect("reroute");
+
+    assert!(result.infos.success);
+    assert_eq!(result.way[0].from, new_from);
+    // the stamina left at the midpoint carries on, no maneuver being needed on this beam reach
+    assert!(result.way[0].status.stamina >= midpoint.status.remaining_stamina, "stamina {} instead of {}", result.way[0].status.stamina, midpoint.status.remaining_stamina);
+    let rerouted_arrival = new_time + result.way.last().expect("way").duration;
+    assert!((rerouted_arrival - arrival).num_minutes().abs() <= 30, "arrival {} instead of {}", rerouted_arrival, arrival);
+}
+
+#[test]
+fn twa_step_resolution() {
+    use chrono::Duration;
+    use crate::polar::PolarCache;
+    use crate::router::echeneis::{exploration_headings, ExplorationMode, Position};
+
+    let algorithm = Arc::new(Spherical {});
+    let mut polar = PolarCache::new(Arc::new(fixtures::polar()));
+    let boat_options = Arc::new(BoatOptions::new());
+    let from = Arc::new(Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -5.0 })));
+    let start = Arc::new(from.point.clone());
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let lands = fixtures::Lands(|_, _| false);
+
+    let mut candidates = |step_tenths: u16| exploration_headings(&lands, &from.point, ExplorationMode::Twa, step_tenths)
+        .map(|heading| Echeneis::jump2(&algorithm, None, None, &mut polar, &boat_options, &start, &from, &None, &heading, Duration::hours(1), &wind, None, 1.0, false).len())
+        .sum::<usize>();
+
+    let (fine, default) = (candidates(5), candidates(10));
+    let ratio = fine as f64 / default as f64;
+    assert!(default > 0 && (1.9..=2.1).contains(&ratio), "{fine} candidates at 0.5°, {default} at 1°");
+}
+
+#[test]
+fn alternative_tie_break() {
+    use chrono::Duration;
+    use crate::position::{Penalties, Penalty, Sail};
+    use crate::router::echeneis::{Alternative, Position};
+    use crate::utils::Distance;
+
+    let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -5.0 }));
+    let position = |az: i32, sail: usize, penalty_secs: i64| {
+        let mut position = Position { az, from_dist: Distance::from_nm(10.0), ..src.clone() };
+        position.settings.sail = Sail::from_index(sail);
+        if penalty_secs > 0 {
+            position.remaining_penalties = Penalties { tack: Some(Penalty { duration: Duration::seconds(penalty_secs), ratio: 0.5 }), ..Penalties::new() };
+        }
+        position
+    };
+    let winner = |a: Position, b: Position| {
+        let mut merged = Alternative::empty();
+        merged.merge_fast(a);
+        let mut other = Alternative::empty();
+        other.merge_fast(b);
+        merged.merge_all(other);
+        let best = merged.best().expect("best");
+        (best.az, best.settings.sail.index)
+    };
+
+    // fewer remaining penalty seconds first
+    assert_eq!(winner(position(1, 0, 60), position(2, 0, 0)), (2, 0));
+    assert_eq!(winner(position(2, 0, 0), position(1, 0, 60)), (2, 0));
+    // then lower sail index
+    assert_eq!(winner(position(1, 3, 0), position(2, 1, 0)), (2, 1));
+    assert_eq!(winner(position(2, 1, 0), position(1, 3, 0)), (2, 1));
+    // then lower az
+    assert_eq!(winner(position(5, 1, 0), position(3, 1, 0)), (3, 1));
+    assert_eq!(winner(position(3, 1, 0), position(5, 1, 0)), (3, 1));
+}
+
+#[test]
+fn alternative_sail_slots() {
+    use crate::position::Sail;
+    use crate::router::echeneis::{Alternative, Position};
+    use crate::utils::Distance;
+
+    let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -5.0 }));
+    let position = |sail: usize, nm: f64| {
+        let mut position = Position { from_dist: Distance::from_nm(nm), ..src.clone() };
+        position.settings.sail = Sail::from_index(sail);
+        position
+    };
+
+    let mut alternative = Alternative::empty();
+    alternative.merge_fast(position(0, 12.0));
+    alternative.merge_fast(position(2, 10.0));
+
+    assert_eq!(alternative.get(0).map(|pos| pos.from_dist.nm()), Some(12.0));
+    assert_eq!(alternative.get(2).map(|pos| pos.from_dist.nm()), Some(10.0));
+    assert!(alternative.get(1).is_none());
+    assert_eq!(alternative.best().map(|pos| pos.settings.sail.index), Some(0));
+}
+
+#[test]
+fn max_points_per_azimuth() {
+    use crate::position::Sail;
+    use crate::router::echeneis::{Alternative, Position};
+    use crate::utils::Distance;
+
+    let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -5.0 }));
+    let mut alternative = Alternative::empty();
+    for (sail, nm) in [(0, 10.0), (1, 12.0), (2, 11.0), (3, 9.0)] {
+        let mut position = Position { from_dist: Distance::from_nm(nm), ..src.clone() };
+        position.settings.sail = Sail::from_index(sail);
+        alternative.merge_fast(position);
+    }
+
+    assert_eq!(alternative.keep_best(8), 0);
+    assert_eq!(alternative.keep_best(2), 2);
+    let kept = (0..8).filter_map(|s| alternative.get(s)).map(|pos| pos.settings.sail.index).collect::<Vec<_>>();
+    assert_eq!(kept, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn capped_points_per_azimuth() {
+    use std::collections::{BTreeMap, VecDeque};
+    use crate::polar::Polar;
+    use crate::race::Race;
+    use crate::router::echeneis::{get_buoys, Nav};
+    use crate::utils::Distance;
+
+    /// Most positions kept for a single azimuth after a first step from `from`.
+    async fn most_per_azimuth(polar: Arc<Polar>, race: &Race, from: &Coords, max_points_per_azimuth: usize) -> usize {
+        let router = fixtures::router_with(polar, EcheneisConfig { max_points_per_azimuth, ..Default::default() });
+
+        let request = fixtures::route_request(from.clone());
+        let mut buoy = get_buoys(race, from.clone(), Arc::new(Spherical {})).next().expect("buoy");
+        let factor = router.get_factor(from, &buoy);
+
+        let navs = router.navigate2(&Arc::new(BoatOptions::new()), &Arc::new(None), from, &request.start_time, Nav::start(&request), &mut buoy,
+            chrono::Duration::hours(1), factor, &mut BTreeMap::new(), &Distance::from_nm(1000.0), VecDeque::new()).await;
+
+        navs.front().expect("nav").points_per_azimuth()
+    }
+
+    // the same sail several times, each azimuth reached by all of them
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    let sail = polar["sail"][0].clone();
+    polar["sail"] = (1..=4).map(|id| {
+        let mut sail = sail.clone();
+        sail["id"] = id.into();
+        sail
+    }).collect();
+    let polar: Arc<Polar> = Arc::new(serde_json::from_value(polar).expect("polar ok"));
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+
+    assert_eq!(most_per_azimuth(polar.clone(), &race, &from, usize::MAX).await, 4);
+    assert_eq!(most_per_azimuth(polar.clone(), &race, &from, 2).await, 2);
+    assert_eq!(most_per_azimuth(polar, &race, &from, 1).await, 1);
+}
+
+#[test]
+fn accuracy_override() {
+    use crate::race::Race;
+    use crate::router::echeneis::get_buoys;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = |accuracy_override: serde_json::Value| -> Race {
+        fixtures::race_through(Coords { lat: 46.0, lon: -5.0 }, serde_json::json!([
+            {"type": "Waypoint", "name": "tricky", "destination": {"lat": 46.0, "lon": -4.9}, "to_avoid": [], "validated": false, "accuracy_override": accuracy_override},
+            {"type": "Zone", "name": "end", "destination": {"lat": 46.0, "lon": -2.0}, "radius": 1.0, "to_avoid": [], "validated": false}
+        ]))
+    };
+    let factors = |race: Race| {
+        let router = fixtures::router(EcheneisConfig::default());
+        let buoys = get_buoys(&race, from.clone(), Arc::new(Spherical {})).collect::<Vec<_>>();
+        (router.get_factor(&from, &buoys[0]), router.get_factor(&from, &buoys[1]))
+    };
+
+    let (tricky, end) = factors(race(serde_json::Value::Null));
+    let (tricky_override, end_override) = factors(race(serde_json::json!(5.0)));
+
+    assert_eq!(tricky_override, tricky + 4.0);
+    assert_eq!(end_override, end);
+}
+
+#[tokio::test]
+async fn unreachable_optional_buoy() {
+    use crate::algorithm::Algorithm;
+    use crate::race::Race;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let finish = Coords { lat: 46.0, lon: -4.0 };
+
+    // the gate is 200nm away, out of reach in 12 hours
+    let race = |optional: bool| -> Race {
+        fixtures::race_through(from.clone(), serde_json::json!([
+            {"type": "Waypoint", "name": "gate", "destination": {"lat": 46.0, "lon": 0.0}, "to_avoid": [], "validated": false, "optional": optional},
+            {"type": "Zone", "name": "end", "destination": finish, "radius": 1.0, "to_avoid": [], "validated": false}
+        ]))
+    };
+
+    let router = fixtures::router(EcheneisConfig { max_duration_hours: 12, ..Default::default() });
+    let arrival = |result: &crate::router::RouteResult| Spherical {}.distance_to(&result.way.last().expect("waypoint").from, &finish);
+
+    let result = router.route(&race(true), BoatOptions::new(), fixtures::route_request(from.clone()), None, None).await.expect("route");
+    assert!(result.infos.success);
+    assert_eq!(result.sections().iter().map(|section| section.door.as_str()).collect::<Vec<_>>(), vec!["gate", "end"]);
+    assert!(arrival(&result).nm() < 2.0, "{} nm from the finish", arrival(&result).nm());
+
+    let result = router.route(&race(false), BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route");
+    assert_eq!(result.sections().len(), 1);
+    assert!(arrival(&result).nm() > 2.0);
+}
+
+#[test]
+fn ice_speed_ratio() {
+    use chrono::Duration;
+    use crate::polar::PolarCache;
+    use crate::position::{BoatSettings, Heading, Sail};
+    use crate::race::Limits;
+    use crate::router::echeneis::Position;
+
+    let limits = Limits {
+        north: vec![],
+        south: vec![Coords { lat: -60.0, lon: -10.0 }, Coords { lat: -60.0, lon: 10.0 }],
+        max_lat: 90.0,
+        min_lat: -90.0,
+    };
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let mut polar = PolarCache::new(Arc::new(fixtures::polar()));
+
+    // eastward along the limit, 10 kts at twa 90, 30% of it inside the limits
+    let mut jump = |from: Coords| {
+        let mut request = fixtures::route_request(from.clone());
+        request.boat_settings = BoatSettings { heading: Heading::TWA(90.0), sail: Sail::from_index(0) };
+        let src = Arc::new(Position { is_in_ice_limits: limits.is_in(&from), ..Position::from(request) });
+
+        let (_, pos) = Echeneis::<Spherical>::jump2(&Arc::new(Spherical {}), None, Some(&limits), &mut polar, &Arc::new(BoatOptions::new()), &Arc::new(from), &src, &None,
+            &Heading::TWA(90.0), Duration::hours(1), &wind, None, 1.0, false).into_iter().next().expect("jump");
+        pos
+    };
+
+    let inside = jump(Coords { lat: -61.0, lon: 0.0 });
+    assert!((inside.status.boat_speed.kts() - 3.0).abs() < 1e-9, "{}", inside.status.boat_speed);
+    assert!(inside.is_in_ice_limits);
+
+    let outside = jump(Coords { lat: -50.0, lon: 0.0 });
+    assert!((outside.status.boat_speed.kts() - 10.0).abs() < 1e-9, "{}", outside.status.boat_speed);
+    assert!(!outside.is_in_ice_limits);
+}
+
+#[test]
+fn gybe_penalty_type() {
+    use chrono::Duration;
+    use crate::polar::PolarCache;
+    use crate::position::{BoatSettings, Heading, Sail};
+    use crate::router::PenaltyType;
+    use crate::router::echeneis::Position;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let mut request = fixtures::route_request(from.clone());
+    request.boat_settings = BoatSettings { heading: Heading::TWA(-135.0), sail: Sail::from_index(0) };
+    let src = Arc::new(Position::from(request));
+
+    // gybing from port to starboard
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let mut polar = PolarCache::new(Arc::new(fixtures::polar()));
+    let (_, gybed) = Echeneis::<Spherical>::jump2(&Arc::new(Spherical {}), None, None, &mut polar, &Arc::new(BoatOptions::new()), &Arc::new(from), &src, &None,
+        &Heading::TWA(135.0), Duration::hours(1), &wind, None, 1.0, false).into_iter().next().expect("jump");
+
+    let way = Echeneis::<Spherical>::way_to(&Spherical {}, Arc::new(gybed));
+    let penalties = way[0].status.penalties.iter().map(|penalty| penalty.typ).collect::<Vec<_>>();
+    assert_eq!(penalties, vec![PenaltyType::Gybe]);
+}
+
+#[test]
+fn max_reachers() {
+    use chrono::Duration;
+    use crate::router::echeneis::{get_buoys, Position};
+
+    // a 120nm wide door, reached every hour along its span
+    let race = fixtures::race_through(Coords { lat: 46.0, lon: -1.0 }, serde_json::json!([{
+        "type": "Door", "name": "gate",
+        "port": {"lat": 47.0, "lon": 0.0}, "starboard": {"lat": 45.0, "lon": 0.0},
+        "departure": {"lat": 46.0, "lon": 0.0}, "destination": {"lat": 46.0, "lon": 0.0},
+        "to_avoid": [], "validated": false
+    }]));
+    let mut door = get_buoys(&race, race.start.clone(), Arc::new(Spherical {})).next().expect("door");
+
+    let src = Position::from(fixtures::route_request(Coords { lat: 46.0, lon: -1.0 }));
+    for hours in (1..=6).rev() {
+        let reacher = Position {
+            point: Coords { lat: 45.2 + hours as f64 * 0.2, lon: 0.0 },
+            duration: src.duration.clone() + Duration::hours(hours),
+            previous: Some(Arc::new(src.clone())),
+            ..src.clone()
+        };
+        door.reach(&reacher, 1.0);
+    }
+
+    assert_eq!(door.reachers(usize::MAX).len(), 6);
+
+    let carried = door.reachers(2).iter().map(|reacher| reacher.absolute_duration.num_hours()).collect::<Vec<_>>();
+    assert_eq!(carried, vec![1, 2]);
+}
+
+#[test]
+fn corridor_policy() {
+    use crate::router::echeneis::CorridorPolicy;
+    use crate::utils::Distance;
+
+    let short = Distance::from_nm(10.0);
+    let long = Distance::from_nm(2000.0);
+
+    // short legs get more room to go around a headland
+    assert_eq!(CorridorPolicy::Auto.max_radius(&short), Distance::from_nm(20.0));
+    assert_eq!(CorridorPolicy::Auto.max_radius(&long), Distance::from_nm(3000.0));
+
+    assert_eq!(CorridorPolicy::Factor(1.2).max_radius(&short), Distance::from_nm(12.0));
+    assert_eq!(CorridorPolicy::Factor(1.2).max_radius(&long), Distance::from_nm(2400.0));
+
+    let fixed = CorridorPolicy::Fixed(Distance::from_nm(50.0));
+    assert_eq!(fixed.max_radius(&short), Distance::from_nm(60.0));
+    assert_eq!(fixed.max_radius(&long), Distance::from_nm(2050.0));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn best_so_far() {
+    use crate::router::LiveBest;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: 5.0 }, 1.0);
+
+    let live_best = LiveBest::new();
+    let router = fixtures::router(EcheneisConfig::default()).with_live_best(live_best.clone());
+    assert!(live_best.get().is_none());
+
+    let handle = tokio::spawn(async move {
+        router.route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await
+    });
+
+    // polled from outside the router, as a client would while the routing goes on
+    let mut snapshots = Vec::new();
+    while !handle.is_finished() {
+        if let Some(best) = live_best.get() {
+            snapshots.push(best);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+    let result = handle.await.expect("join").expect("route");
+
+    assert!(!snapshots.is_empty());
+    for pair in snapshots.windows(2) {
+        assert!(pair[1].from.lon >= pair[0].from.lon, "{:?} then {:?}", pair[0].from, pair[1].from);
+        assert!(pair[1].duration >= pair[0].duration);
+    }
+
+    let last = live_best.get().expect("best");
+    assert_eq!(last.from, result.way.last().expect("way").from);
+}
+
+#[tokio::test]
+async fn arrival_time_factor() {
+    use chrono::Duration;
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+
+    let route = |config: EcheneisConfig| {
+        let race = race.clone();
+        let from = from.clone();
+        async move {
+            fixtures::router(config).route(&race, BoatOptions::new(), fixtures::route_request(from), None, None).await.expect("route")
+        }
+    };
+
+    // the waypoint the buoy is sailed to straight from
+    let snapped = route(EcheneisConfig::default()).await;
+    let snapped = &snapped.way[snapped.way.len() - 2];
+    let tight = route(EcheneisConfig { arrival_time_factor: 0.5, ..Default::default() }).await;
+    let tight = &tight.way[tight.way.len() - 2];
+
+    // about 4 hours away at 10 knots, reached from the 3rd hourly step unless tighter
+    assert!(snapped.way_duration > Duration::minutes(30), "{}", snapped.way_duration);
+    assert!(tight.way_duration <= Duration::minutes(30), "{}", tight.way_duration);
+    assert!(tight.duration > snapped.duration, "{} then {}", snapped.duration, tight.duration);
+}
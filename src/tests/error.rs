@@ -0,0 +1,46 @@
+use crate::error::{ErrorPayload, PhtError};
+use crate::phtheirichthys::Phtheirichthys;
+
+#[test]
+fn missing_provider() {
+    let phtheirichthys = Phtheirichthys::new();
+
+    let e = phtheirichthys.get_wind_provider_status("missing".to_string()).err().expect("error");
+    let payload = ErrorPayload::from(&e);
+
+    assert_eq!(payload.code, "provider_not_found");
+    assert_eq!(payload.message, "Provider missing not found");
+}
+
+#[test]
+fn missing_race() {
+    let phtheirichthys = Phtheirichthys::new();
+
+    let e = phtheirichthys.get_race("missing".to_string()).err().expect("error");
+
+    assert_eq!(e.downcast_ref::<PhtError>(), Some(&PhtError::RaceNotFound("missing".to_string())));
+}
+
+#[test]
+fn untyped_error() {
+    let payload = ErrorPayload::from(&anyhow::anyhow!("boom"));
+
+    assert_eq!(payload.code, "internal");
+}
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+    use web_sys::js_sys::Reflect;
+
+    use crate::wasm_binding::get_wind_provider_status;
+
+    #[wasm_bindgen_test]
+    fn missing_provider() {
+        let e = get_wind_provider_status("missing".to_string()).err().expect("error");
+
+        let code = Reflect::get(&e, &JsValue::from_str("code")).unwrap();
+        assert_eq!(code.as_string().as_deref(), Some("provider_not_found"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::algorithm::spherical::Spherical;
+use crate::land::LandsProvider;
+use crate::polar::Polar;
+use crate::position::{BoatSettings, BoatStatus, Coords, Heading, Sail};
+use crate::race::Race;
+use crate::router::echeneis::{Echeneis, EcheneisConfig};
+use crate::router::{RouteRequest, RouteResult, RouteWaypoint, WaypointStatus};
+use crate::utils::Speed;
+use crate::wind::Wind;
+use crate::wind::providers::constant::ConstantWindProvider;
+
+/// Single sail polar, 10 kts of boat speed at 90° in 10 kts of wind.
+pub(crate) fn polar() -> Polar {
+    let polar = r#"
+    {
+        "_id":1,"label":"test/simple","globalSpeedRatio":1,"iceSpeedRatio":0.3,"autoSailChangeTolerance":1.014,"badSailTolerance":1.02,"maxSpeed":20,
+        "foil":{"speedRatio":1.04,"twaMin":80,"twaMax":160,"twaMerge":10,"twsMin":16,"twsMax":35,"twsMerge":5},
+        "hull":{"speedRatio":1},
+        "winch":{
+            "tack":{"stdTimerSec":300,"stdRatio":0.5,"proTimerSec":75,"proRatio":0.5},
+            "gybe":{"stdTimerSec":300,"stdRatio":0.5,"proTimerSec":75,"proRatio":0.5},
+            "sailChange":{"stdTimerSec":300,"stdRatio":0.5,"proTimerSec":75,"proRatio":0.5}
+        },
+        "tws":[0,10,20,40],
+        "twa":[0,45,90,135,180],
+        "sail":[{"id":1,"name":"Jib","speed":[[0,0,0,0],[0,7,12,14],[0,10,16,18],[0,9,15,17],[0,6,11,13]]}]
+    }"#;
+
+    serde_json::from_str(polar).expect("polar ok")
+}
+
+/// Race from `start` to a zone of `radius` nm around `end`.
+pub(crate) fn race(start: Coords, end: Coords, radius: f64) -> Race {
+    let race = serde_json::json!({
+        "id": "test",
+        "leg": 1,
+        "name": "Test",
+        "shortName": "Test",
+        "boat": "1",
+        "start_time": "2024-01-01T00:00:00Z",
+        "end_time": "2024-01-10T00:00:00Z",
+        "start": start,
+        "buoys": [{"type": "Zone", "name": "end", "radius": radius, "destination": end, "to_avoid": [], "validated": false}],
+        "ice_limits": {"north": [], "south": [], "maxLat": 90, "minLat": -90}
+    });
+
+    serde_json::from_value(race).expect("race ok")
+}
+
+/// Race from `start` through `buoys`, given as their json.
+pub(crate) fn race_through(start: Coords, buoys: serde_json::Value) -> Race {
+    let race = serde_json::json!({
+        "id": "test", "leg": 1, "name": "Test", "boat": "1",
+        "start": start,
+        "buoys": buoys
+    });
+
+    serde_json::from_value(race).expect("race ok")
+}
+
+/// Router sailing `polar()` in a steady 10 kts northerly, with no land around.
+pub(crate) fn router(config: EcheneisConfig) -> Echeneis<Spherical> {
+    router_with(Arc::new(polar()), config)
+}
+
+/// Same as `router`, sailing `polar` instead.
+pub(crate) fn router_with(polar: Arc<Polar>, config: EcheneisConfig) -> Echeneis<Spherical> {
+    Echeneis::new(
+        "test".to_string(),
+        polar,
+        Arc::new(ConstantWindProvider::new(0.0, Speed::from_kts(10.0))),
+        Arc::new(Box::new(Lands(|_, _| false))),
+        Arc::new(Spherical {}),
+        config,
+    )
+}
+
+pub(crate) fn start_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+pub(crate) fn route_request(from: Coords) -> RouteRequest {
+    RouteRequest {
+        from,
+        start_time: start_time(),
+        boat_settings: Default::default(),
+        status: BoatStatus {
+            aground: false,
+            boat_speed: Speed::from_kts(0.0),
+            wind: Wind { direction: 0.0, speed: Default::default(), gust: None },
+            foil: 0,
+            boost: 0,
+            best_ratio: 0.0,
+            ratio: 0,
+            vmgs: None,
+            penalties: Default::default(),
+            stamina: 100.0,
+        },
+        steps: vec![
+            (chrono::Duration::hours(9999), chrono::Duration::hours(1)),
+        ],
+    }
+}
+
+/// Waypoint leaving `from` for an hour on a 90° twa.
+pub(crate) fn waypoint(from: Coords, sail: Sail) -> RouteWaypoint {
+    RouteWaypoint {
+        from,
+        duration: chrono::Duration::zero(),
+        way_duration: chrono::Duration::hours(1),
+        bearing: None,
+        boat_settings: BoatSettings { heading: Heading::TWA(90.0), sail },
+        status: WaypointStatus {
+            boat_speed: Speed::from_kts(10.0),
+            wind: Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None },
+            foil: 0,
+            boost: 0,
+            best_ratio: 1.0,
+            ice: false,
+            change: false,
+            penalties: Vec::new(),
+            remaining_penalties: Vec::new(),
+            stamina: 100.0,
+            remaining_stamina: 100.0,
+        },
+    }
+}
+
+/// Successful route along `way`, started at `start_time()`.
+pub(crate) fn route_result(way: Vec<RouteWaypoint>) -> RouteResult {
+    let result = serde_json::json!({
+        "infos": { "start": start_time(), "duration": 0.0, "success": true, "sailsDuration": {}, "foilDuration": 0.0 },
+        "way": way,
+        "sections": [],
+        "debug": [],
+    });
+
+    serde_json::from_value(result).expect("route ok")
+}
+
+/// Land wherever the predicate says so.
+pub(crate) struct Lands(pub(crate) fn(f64, f64) -> bool);
+
+impl LandsProvider for Lands {
+    fn is_land(&self, lat: f64, lon: f64) -> bool {
+        (self.0)(lat, lon)
+    }
+}
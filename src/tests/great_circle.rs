@@ -0,0 +1,48 @@
+use crate::algorithm::Algorithm;
+use crate::algorithm::great_circle::GreatCircle;
+use crate::position::Coords;
+use crate::utils::Distance;
+
+const LONDON: Coords = Coords { lat: 51.5074, lon: -0.1278 };
+const PARIS: Coords = Coords { lat: 48.8566, lon: 2.3522 };
+const NEW_YORK: Coords = Coords { lat: 40.7128, lon: -74.0060 };
+const LOS_ANGELES: Coords = Coords { lat: 34.0522, lon: -118.2437 };
+
+fn assert_km(distance: Distance, expected: f64) {
+    let km = distance.m() / 1000.0;
+    assert!((km - expected).abs() < expected * 0.005, "{km}km, expected {expected}km");
+}
+
+#[test]
+fn distance_to() {
+    let algorithm = GreatCircle {};
+
+    assert_km(algorithm.distance_to(&LONDON, &PARIS), 343.5);
+    assert_km(algorithm.distance_to(&NEW_YORK, &LOS_ANGELES), 3935.7);
+}
+
+#[test]
+fn destination_is_consistent_with_distance_and_heading() {
+    let algorithm = GreatCircle {};
+
+    let (distance, heading) = algorithm.distance_and_heading_to(&NEW_YORK, &LOS_ANGELES);
+    let destination = algorithm.destination(&NEW_YORK, heading, &distance);
+
+    assert!((destination.lat - LOS_ANGELES.lat).abs() < 1e-6);
+    assert!((destination.lon - LOS_ANGELES.lon).abs() < 1e-6);
+}
+
+#[test]
+fn cross_and_along_track_distance() {
+    let algorithm = crate::algorithm::spherical::Spherical {};
+    let path = (&Coords { lat: 0.0, lon: 0.0 }, &Coords { lat: 0.0, lon: 10.0 });
+
+    // one degree north of an eastbound equatorial path : on the left
+    let point = Coords { lat: 1.0, lon: 5.0 };
+    assert_km(algorithm.cross_track_distance(path, &point) * -1.0, 111.2);
+    assert_km(algorithm.along_track_distance(path, &point), 556.0);
+
+    // behind the path origin
+    let point = Coords { lat: 0.0, lon: -2.0 };
+    assert_km(algorithm.along_track_distance(path, &point) * -1.0, 222.4);
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::land::LandsProvider;
+use crate::land::vr::VrLandProvider;
+
+/// `index` with the tiles `(ceil(lat), floor(lon))` set to `kind` : 1 mixed, 2 land.
+fn index(tiles: &[(i32, i32, u8)]) -> Vec<u8> {
+    let mut index = vec![0u8; 180 * 360 / 4];
+    for &(lat, lon, kind) in tiles {
+        let p = ((lat + 89) * 360 + lon + 180) as usize;
+        index[p / 4] |= kind << (6 - 2 * (p % 4));
+    }
+
+    index
+}
+
+#[test]
+fn lands_from_files() {
+    // Paris tile all land, the north half of the one off Brest
+    let mut north_half = vec![0u8; 730 * 730 / 8 + 1];
+    north_half[..365 * 730 / 8].fill(0xFF);
+
+    let files = HashMap::from([
+        ("index".to_string(), index(&[(49, 2, 2), (47, -5, 1)])),
+        ("carto/1_-5_47.deg".to_string(), north_half),
+    ]);
+    let lands = VrLandProvider::from_files(&files).expect("lands");
+
+    assert!(lands.is_land(48.85, 2.35));
+    assert!(lands.is_land(46.9, -4.5));
+    assert!(!lands.is_land(46.1, -4.5));
+    assert!(!lands.is_land(45.0, -10.0));
+}
+
+#[test]
+fn lands_missing_tile() {
+    let files = HashMap::from([("index".to_string(), index(&[(47, -5, 1)]))]);
+    assert!(VrLandProvider::from_files(&files).is_err());
+
+    assert!(VrLandProvider::from_files(&HashMap::new()).is_err());
+}
+
+#[test]
+fn single_tile_reader() {
+    // only the tile off Brest, its west half being land
+    let mut west_half = vec![0u8; 730 * 730 / 8 + 1];
+    for row in 0..730 {
+        for col in 0..365 {
+            let p = row * 730 + col;
+            west_half[p / 8] |= 0x80 >> (p % 8);
+        }
+    }
+
+    let lands = VrLandProvider::from_reader(&index(&[(47, -5, 1)]), |file_name| {
+        assert_eq!(file_name, "1_-5_47.deg");
+        Some(west_half.clone())
+    }).expect("lands");
+
+    assert!(lands.is_land(46.5, -4.9));
+    assert!(!lands.is_land(46.5, -4.1));
+    assert!(!lands.is_land(48.85, 2.35));
+}
@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod cubecl_spherical;
+#[cfg(test)]
+mod great_circle;
+#[cfg(test)]
+mod race;
+#[cfg(test)]
+mod error;
+#[cfg(test)]
+mod fixtures;
+#[cfg(test)]
+mod echeneis;
+#[cfg(test)]
+mod land;
+#[cfg(test)]
+mod position;
+#[cfg(test)]
+mod polar;
+#[cfg(test)]
+mod phtheirichthys;
+#[cfg(test)]
+mod router;
+#[cfg(test)]
+mod spherical;
+#[cfg(test)]
+mod utils;
+#[cfg(test)]
+mod wind;
@@ -0,0 +1,191 @@
+use crate::phtheirichthys::BoatOptions;
+
+#[test]
+fn boat_options_with() {
+    let options = BoatOptions::new().with_foil(true).with_hull(true);
+
+    assert!(options.foil);
+    assert!(options.hull);
+    assert!(!options.lt);
+    assert!(!options.gt);
+    assert!(!options.code0);
+    assert!(!options.winch);
+    assert!(!options.stamina);
+}
+
+#[test]
+fn eval_jump() {
+    use crate::algorithm::{spherical::Spherical, Algorithm};
+    use crate::phtheirichthys::Phtheirichthys;
+    use crate::position::{BoatSettings, Coords, Heading, Sail};
+    use crate::tests::fixtures;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let phtheirichthys = Phtheirichthys::new();
+    phtheirichthys.add_polar("test".to_string(), fixtures::polar()).expect("polar ok");
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let settings = BoatSettings { heading: Heading::TWA(-90.0), sail: Sail::from_index(0) };
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+
+    let jump = phtheirichthys.eval_jump("test".to_string(), BoatOptions::new(), from.clone(), settings, wind, 3600).expect("jump");
+
+    // 10 kts at twa 90 in 10 kts of wind, eastward
+    assert!((jump.boat_speed.kts() - 10.0).abs() < 1e-9, "{}", jump.boat_speed);
+    let (distance, heading) = Spherical {}.distance_and_heading_to(&from, &jump.destination);
+    assert!((distance.nm() - 10.0).abs() < 0.01, "{}", distance);
+    assert!((heading - 90.0).abs() < 0.1, "{}", heading);
+    assert_eq!(jump.foil, 0);
+    assert!(!jump.penalties.is_some());
+    assert_eq!(jump.stamina, 100.0);
+
+    assert!(phtheirichthys.eval_jump("unknown".to_string(), BoatOptions::new(), from, Default::default(), Default::default(), 3600).is_err());
+}
+
+#[test]
+fn eta_to() {
+    use crate::algorithm::{spherical::Spherical, Algorithm};
+    use crate::phtheirichthys::Phtheirichthys;
+    use crate::position::{BoatSettings, Coords, Heading, Sail};
+    use crate::tests::fixtures;
+    use crate::utils::{Distance, Speed};
+    use crate::wind::Wind;
+
+    let phtheirichthys = Phtheirichthys::new();
+    phtheirichthys.add_polar("test".to_string(), fixtures::polar()).expect("polar ok");
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let to = Spherical {}.destination(&from, 90.0, &Distance::from_nm(10.0));
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+
+    // 10 nm at 10 kts, already on starboard tack
+    let settings = BoatSettings { heading: Heading::TWA(-90.0), sail: Sail::from_index(0) };
+    let eta = phtheirichthys.eta_to("test".to_string(), BoatOptions::new(), from.clone(), settings, wind.clone(), to.clone()).expect("eta");
+    assert!((eta.num_seconds() - 3600).abs() <= 5, "{}", eta);
+
+    // tacking first slows the boat down
+    let settings = BoatSettings { heading: Heading::TWA(90.0), sail: Sail::from_index(0) };
+    let tacking = phtheirichthys.eta_to("test".to_string(), BoatOptions::new(), from, settings, wind, to).expect("eta");
+    assert!(tacking > eta, "{} <= {}", tacking, eta);
+}
+
+#[test]
+fn polar_sails() {
+    use crate::phtheirichthys::Phtheirichthys;
+    use crate::tests::fixtures;
+
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    let sail = polar["sail"][0].clone();
+    polar["sail"] = IntoIterator::into_iter([(1, "Jib"), (2, "Spi"), (5, "Code0")]).map(|(id, name)| {
+        let mut sail = sail.clone();
+        sail["id"] = id.into();
+        sail["name"] = name.into();
+        sail
+    }).collect();
+
+    let phtheirichthys = Phtheirichthys::new();
+    phtheirichthys.add_polar("test".to_string(), serde_json::from_value(polar).expect("polar ok")).expect("polar ok");
+
+    assert_eq!(phtheirichthys.polar_sails("test".to_string()).expect("sails"), vec![(1, "Jib".to_string()), (2, "Spi".to_string()), (5, "Code0".to_string())]);
+    assert!(phtheirichthys.polar_sails("unknown".to_string()).is_err());
+}
+
+#[tokio::test]
+async fn navigate_without_forecasts() {
+    use std::sync::Arc;
+    use crate::error::PhtError;
+    use crate::phtheirichthys::Phtheirichthys;
+    use crate::position::Coords;
+    use crate::tests::fixtures;
+    use crate::wind::providers::vr::{References, VrWindProvider};
+
+    let phtheirichthys = Phtheirichthys::new();
+    phtheirichthys.add_polar("test".to_string(), fixtures::polar()).expect("polar ok");
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+    let references = |references: serde_json::Value| -> References {
+        serde_json::from_value(serde_json::json!({
+            "export_ts": 1_700_000_000, "publish_ts": 1_700_000_000, "start_ts": 1_700_000_000,
+            "references": references,
+        })).expect("references ok")
+    };
+
+    // index not loaded yet
+    phtheirichthys.insert_wind_provider("vr".to_string(), Arc::new(VrWindProvider::from_references(references(serde_json::json!([])))));
+    let e = phtheirichthys.navigate("vr".to_string(), "test".to_string(), race.clone(), BoatOptions::new(), fixtures::route_request(from.clone()), None, None, None).await.err().expect("not ready");
+    assert_eq!(e.downcast_ref::<PhtError>(), Some(&PhtError::ProviderNotReady));
+
+    // forecasts from November 2023 only, the request starting in 2024
+    let reference = serde_json::json!({"reference": "a", "valid_ts": 1_700_000_000, "delta_ref": 0, "delta": 0, "avail_ts": 1_700_000_000, "rel_path": "a.wnd"});
+    phtheirichthys.insert_wind_provider("vr".to_string(), Arc::new(VrWindProvider::from_references(references(serde_json::json!([[reference]])))));
+    let e = phtheirichthys.navigate("vr".to_string(), "test".to_string(), race, BoatOptions::new(), fixtures::route_request(from), None, None, None).await.err().expect("out of coverage");
+    assert!(matches!(e.downcast_ref::<PhtError>(), Some(PhtError::OutOfCoverage(_))), "{e}");
+}
+
+#[test]
+fn cpu_fallback() {
+    use crate::phtheirichthys::{ComputeBackend, Phtheirichthys};
+
+    // as cubecl does when no adapter can be found
+    assert_eq!(Phtheirichthys::with_cpu_fallback(|| panic!("No possible adapter available")), ComputeBackend::Cpu);
+    assert_eq!(Phtheirichthys::with_cpu_fallback(|| {}), ComputeBackend::Gpu);
+}
+
+#[test]
+fn navigation_solution() {
+    use crate::phtheirichthys::Phtheirichthys;
+    use crate::position::Coords;
+
+    let from = Coords { lat: 45.0, lon: -60.0 };
+    let to = Coords { lat: 45.0, lon: 0.0 };
+
+    let solution = Phtheirichthys::new().navigation_solution(from.clone(), to.clone());
+
+    assert!(solution.rhumb_distance >= solution.gc_distance);
+    assert!((solution.rhumb_distance.nm() - from.rhumb_distance_to(&to).nm()).abs() < 1e-9);
+    assert!((solution.rhumb_bearing - 90.0).abs() < 1e-9);
+
+    // heading north of east first, south of east on arrival
+    assert!(solution.gc_initial_bearing < 90.0);
+    assert!((solution.gc_final_bearing - (180.0 - solution.gc_initial_bearing)).abs() < 1e-6);
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_test::*;
+    use web_sys::js_sys;
+
+    use crate::phtheirichthys::BoatOptions;
+    use crate::position::Coords;
+    use crate::tests::fixtures;
+    use crate::wasm_binding::{add_polar, add_wind_provider, navigate_streaming};
+
+    #[wasm_bindgen_test]
+    async fn streamed_isochrones() {
+        add_wind_provider().await;
+        add_polar("streaming".to_string(), fixtures::polar()).unwrap();
+
+        let from = Coords { lat: 46.0, lon: -5.0 };
+        let race = fixtures::race(from.clone(), Coords { lat: 46.0, lon: -4.0 }, 1.0);
+        let mut request = fixtures::route_request(from);
+        request.start_time = chrono::Utc::now();
+
+        let streamed = Rc::new(RefCell::new(Vec::new()));
+        let on_isochrone = {
+            let streamed = streamed.clone();
+            Closure::<dyn Fn(String, JsValue)>::new(move |door: String, _isochrone: JsValue| streamed.borrow_mut().push(door))
+        };
+
+        let result = navigate_streaming("vr".to_string(), "streaming".to_string(), race, BoatOptions::new(), request, Some(12.0), None, None, on_isochrone.as_ref().unchecked_ref::<js_sys::Function>().clone()).await.expect("route");
+
+        let streamed = streamed.borrow();
+        assert!(!streamed.is_empty());
+        assert_eq!(streamed.len(), result.sections().iter().map(|section| section.isochrones.len()).sum::<usize>());
+    }
+}
@@ -0,0 +1,426 @@
+use chrono::Duration;
+
+use crate::polar::{Polar, SailFilter};
+use crate::position::{BoatSettings, Heading, Sail};
+use crate::router::{RouteWaypoint, Telemetry, WaypointStatus};
+use crate::tests::fixtures;
+
+fn eight_sails_polar() -> Polar {
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    let sail = polar["sail"][0].clone();
+    polar["sail"] = (1..=8).map(|id| {
+        let mut sail = sail.clone();
+        sail["id"] = id.into();
+        sail["name"] = format!("Custom{id}").into();
+        sail
+    }).collect();
+
+    serde_json::from_value(polar).expect("polar ok")
+}
+
+#[test]
+fn sail_label() {
+    let polar = eight_sails_polar();
+
+    assert_eq!(polar.sail_label(0), "Custom1");
+    assert_eq!(polar.sail_label(7), "Custom8");
+    assert_eq!(polar.sail_label(8), "Unknown");
+
+    assert_eq!(Sail::from_index(0).to_string(), "Jib");
+    assert_eq!(Sail::from_index(7).to_string(), "Sail7");
+
+    let waypoint = RouteWaypoint {
+        from: Default::default(),
+        duration: Duration::zero(),
+        way_duration: Duration::zero(),
+        bearing: None,
+        boat_settings: BoatSettings { heading: Heading::HEADING(90.0), sail: Sail::from_index(7) },
+        status: WaypointStatus {
+            boat_speed: Default::default(),
+            wind: Default::default(),
+            foil: 0,
+            boost: 0,
+            best_ratio: 1.0,
+            ice: false,
+            change: false,
+            penalties: Vec::new(),
+            remaining_penalties: Vec::new(),
+            stamina: 100.0,
+            remaining_stamina: 100.0,
+        },
+    };
+
+    assert_eq!(Telemetry::from((&waypoint, &polar)).sail, "Custom8");
+    assert!(waypoint.to_string().ends_with("using Sail7"));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_vmg_sweep_matches_serial() {
+    use crate::utils::Speed;
+
+    let polar = fixtures::polar();
+
+    for tws in [0.0, 4.0, 10.0, 15.0, 25.0, 40.0] {
+        let wind_speed = Speed::from_kts(tws);
+
+        let (upwind, downwind) = polar.vmg_sweep(&wind_speed, None, false);
+        let (par_upwind, par_downwind) = polar.par_vmg_sweep(&wind_speed, None, false);
+
+        assert_eq!((upwind.twa, upwind.sail.index, upwind.vmg.kts()), (par_upwind.twa, par_upwind.sail.index, par_upwind.vmg.kts()), "upwind at {tws} kts");
+        assert_eq!((downwind.twa, downwind.sail.index, downwind.vmg.kts()), (par_downwind.twa, par_downwind.sail.index, par_downwind.vmg.kts()), "downwind at {tws} kts");
+    }
+}
+
+#[test]
+fn custom_stamina_model() {
+    use crate::utils::Speed;
+
+    let sail = Sail::from_index(0);
+    let wind_speed = Speed::from_kts(10.0);
+
+    let polar = fixtures::polar();
+    assert_eq!(polar.tired(100.0, 45.0, -45.0, &sail, &sail, &wind_speed), 87.5);
+
+    let mut custom = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    custom["stamina"] = serde_json::json!({"tack": 4.0, "tiredness": [[0.0, 2.0]]});
+    let custom: Polar = serde_json::from_value(custom).expect("polar ok");
+    assert_eq!(custom.tired(100.0, 45.0, -45.0, &sail, &sail, &wind_speed), 92.0);
+    // unchanged values keep their default
+    assert_eq!(custom.tired(100.0, 45.0, 45.0, &sail, &Sail::from_index(1), &wind_speed), 60.0);
+}
+
+#[test]
+fn bad_sail_tolerance() {
+    use std::sync::Arc;
+    use crate::polar::PolarCache;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    // a second sail at 60% of the first one
+    let polar = |tolerance: f64| {
+        let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+        let mut worse = polar["sail"][0].clone();
+        worse["id"] = 2.into();
+        worse["name"] = "Worse".into();
+        worse["speed"] = serde_json::to_value(serde_json::from_value::<Vec<Vec<f64>>>(worse["speed"].clone()).unwrap()
+            .into_iter().map(|speeds| speeds.into_iter().map(|speed| speed * 0.6).collect::<Vec<_>>()).collect::<Vec<_>>()).unwrap();
+        polar["sail"].as_array_mut().unwrap().push(worse);
+        polar["badSailTolerance"] = tolerance.into();
+        PolarCache::new(Arc::new(serde_json::from_value::<Polar>(polar).expect("polar ok")))
+    };
+
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let sails = |tolerance: f64| {
+        let mut polar = polar(tolerance);
+        let filter = polar.sail_filter();
+        polar.get_boat_speeds(&Heading::TWA(90.0), &wind, &Sail::from_index(0), false, filter, true)
+            .into_iter().map(|result| result.sail.index).collect::<Vec<_>>()
+    };
+
+    assert_eq!(sails(0.0), vec![0, 1]);
+    assert_eq!(sails(0.7), vec![0]);
+    assert_eq!(sails(0.55), vec![0, 1]);
+
+    let sails = |filter: SailFilter| polar(0.0).get_boat_speeds(&Heading::TWA(90.0), &wind, &Sail::from_index(1), false, filter, true)
+        .into_iter().map(|result| result.sail.index).collect::<Vec<_>>();
+
+    assert_eq!(sails(SailFilter::All), vec![0, 1]);
+    assert_eq!(sails(SailFilter::BestOnly), vec![0]);
+    assert_eq!(sails(SailFilter::Threshold(0.5)), vec![0, 1]);
+    assert_eq!(sails(SailFilter::Threshold(0.8)), vec![0]);
+}
+
+#[test]
+fn speed_at() {
+    use crate::phtheirichthys::BoatOptions;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let polar = fixtures::polar();
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+
+    let expected = polar.get_boat_speed(&Heading::HEADING(90.0), &wind, None, &Sail::from_index(0), false);
+    let result = polar.speed_at(90.0, Speed::from_kts(10.0), None, &BoatOptions::new(), false);
+
+    assert_eq!(result.speed.kts(), expected.speed.kts());
+    assert_eq!(result.sail, expected.sail);
+    assert_eq!((result.foil, result.best), (expected.foil, expected.best));
+    assert_eq!(polar.speed_at(-90.0, Speed::from_kts(10.0), Some(0), &BoatOptions::new(), false).speed.kts(), expected.speed.kts());
+}
+
+#[test]
+fn asymmetric_polar() {
+    use std::sync::Arc;
+    use crate::polar::PolarCache;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    // starboard (positive twa) as the fixture, port 20% slower
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    polar["twa"] = serde_json::json!([-180, -135, -90, -45, 0, 45, 90, 135, 180]);
+    polar["sail"][0]["speed"] = serde_json::json!([
+        [0, 4.8, 8.8, 10.4], [0, 7.2, 12, 13.6], [0, 8, 12.8, 14.4], [0, 5.6, 9.6, 11.2],
+        [0, 0, 0, 0],
+        [0, 7, 12, 14], [0, 10, 16, 18], [0, 9, 15, 17], [0, 6, 11, 13]
+    ]);
+    let polar: Polar = serde_json::from_value(polar).expect("polar ok");
+    assert!(polar.is_asymmetric());
+    assert!(!fixtures::polar().is_asymmetric());
+
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let sail = Sail::from_index(0);
+    let starboard = polar.get_boat_speed(&Heading::TWA(90.0), &wind, None, &sail, false);
+    let port = polar.get_boat_speed(&Heading::TWA(-90.0), &wind, None, &sail, false);
+
+    assert_eq!(starboard.speed.kts(), 10.0);
+    assert_eq!(port.speed.kts(), 8.0);
+
+    // close to the wind on both tacks, through the cached twa indices
+    let mut cache = PolarCache::new(Arc::new(polar));
+    let speed = |cache: &mut PolarCache, twa: f64| cache.get_boat_speeds(&Heading::TWA(twa), &wind, &sail, false, SailFilter::All, false)[0].speed.kts();
+    assert!((speed(&mut cache, 0.9) - 0.14).abs() < 1e-9);
+    assert!((speed(&mut cache, -0.9) - 0.112).abs() < 1e-9);
+}
+
+#[test]
+fn min_boat_speed() {
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    polar["minBoatSpeed"] = 1.5.into();
+    let polar: Polar = serde_json::from_value(polar).expect("polar ok");
+
+    let sail = Sail::from_index(0);
+    for (twa, tws) in [(0.0, 0.0), (45.0, 0.1), (90.0, 0.5), (180.0, 1.0), (90.0, 10.0)] {
+        let wind = Wind { direction: 0.0, speed: Speed::from_kts(tws), gust: None };
+        let speed = polar.get_boat_speed(&Heading::TWA(twa), &wind, None, &sail, false).speed.kts();
+        assert!(speed >= 1.5, "{speed} kts at twa {twa} in {tws} kts");
+    }
+
+    let (upwind, downwind) = polar.vmg_sweep(&Speed::from_kts(0.1), None, false);
+    assert!(upwind.vmg.kts() > 0.0 && downwind.vmg.kts() < 0.0);
+
+    // no floor by default
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(0.0), gust: None };
+    assert_eq!(fixtures::polar().get_boat_speed(&Heading::TWA(90.0), &wind, None, &sail, false).speed.kts(), 0.0);
+}
+
+#[test]
+fn wind_thresholds() {
+    use crate::utils::Speed;
+
+    let sail = Sail::from_index(0);
+    let wind_speed = Speed::from_kts(20.0);
+
+    // 1.5 at 20 kts
+    let polar = fixtures::polar();
+    assert_eq!(polar.tired(100.0, 45.0, -45.0, &sail, &sail, &wind_speed), 85.0);
+
+    // 1.25 once the stops are twice as high
+    let mut shifted = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    shifted["windThresholds"] = serde_json::json!({"tiredness": [0.0, 20.0, 40.0, 60.0], "recoveryHws": 60.0});
+    let shifted: Polar = serde_json::from_value(shifted).expect("polar ok");
+    assert_eq!(shifted.tired(100.0, 45.0, -45.0, &sail, &sail, &wind_speed), 87.5);
+
+    // recovering in 20 kts : a point every 12.4 minutes, every 7.6 minutes once shifted
+    let duration = Duration::minutes(35);
+    assert_eq!(polar.recovers(50.0, &duration, &wind_speed).floor(), 52.0);
+    assert_eq!(shifted.recovers(50.0, &duration, &wind_speed).floor(), 54.0);
+
+    // still read from the stamina model, where they used to be
+    let mut former = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    former["stamina"] = serde_json::json!({"recoveryHws": 60.0});
+    let former: Polar = serde_json::from_value(former).expect("polar ok");
+    assert_eq!(former.recovers(50.0, &duration, &wind_speed).floor(), 54.0);
+}
+
+#[test]
+fn tiredness_coefs() {
+    use crate::utils::Speed;
+
+    let sail = Sail::from_index(0);
+    let wind_speed = Speed::from_kts(10.0);
+
+    let mut custom = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    custom["stamina"] = serde_json::json!({"tack": 4.0, "tiredness": [2.0, 2.0, 2.0, 2.0]});
+    let custom: Polar = serde_json::from_value(custom).expect("polar ok");
+    assert!(custom.validate().is_ok());
+    assert_eq!(custom.tired(100.0, 45.0, -45.0, &sail, &sail, &wind_speed), 92.0);
+
+    // a coefficient for each of the 4 default thresholds
+    let mut short = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    short["stamina"] = serde_json::json!({"tiredness": [2.0]});
+    let short: Polar = serde_json::from_value(short).expect("polar ok");
+    assert!(short.validate().is_err());
+}
+
+#[test]
+fn best_heading_upwind() {
+    use crate::phtheirichthys::BoatOptions;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let polar = fixtures::polar();
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let options = BoatOptions::new();
+
+    // dead upwind : tacking on either side, never straight
+    let (heading, vmg) = polar.best_heading_to(&wind, 0.0, None, &options, false);
+    let twa = heading.twa(wind.direction);
+    assert!(twa.abs() > 30.0 && twa.abs() < 90.0, "{twa}");
+    assert!(vmg.kts() > 0.0);
+
+    let mirror = Heading::TWA(-twa).heading(wind.direction);
+    let mirror_vmg = polar.speed_at(-twa, wind.speed.clone(), None, &options, false).speed.kts() * mirror.to_radians().cos();
+    assert!((mirror_vmg - vmg.kts()).abs() < 1e-9);
+
+    // beam reach : straight to it
+    let (heading, vmg) = polar.best_heading_to(&wind, 90.0, None, &options, false);
+    assert!((heading.heading(wind.direction) - 90.0).abs() < 1.0);
+    assert!((vmg.kts() - 10.0).abs() < 0.1);
+}
+
+#[test]
+fn speed_grid() {
+    use crate::phtheirichthys::BoatOptions;
+    use crate::utils::Speed;
+
+    let polar = eight_sails_polar();
+    let grid = polar.speed_grid(Speed::from_kts(10.0), &BoatOptions::new(), false);
+
+    assert_eq!(grid.len(), polar.twa.len());
+    for (row, twa) in grid.iter().zip(polar.twa.iter()) {
+        assert_eq!(row.0, *twa);
+        assert_eq!(row.1.iter().map(|(sail, _)| *sail).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+    }
+
+    let (twa, speeds) = &grid[2];
+    assert_eq!(*twa, 90.0);
+    assert!(speeds.iter().all(|(_, speed)| speed.kts() == 10.0));
+}
+
+#[test]
+fn manual_sails() {
+    use std::sync::Arc;
+    use crate::phtheirichthys::BoatOptions;
+    use crate::polar::PolarCache;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    // a second sail 1% slower than the first one, within the 1.4% tolerance
+    let mut polar = serde_json::to_value(fixtures::polar()).expect("polar ok");
+    let mut close = polar["sail"][0].clone();
+    close["id"] = 2.into();
+    close["name"] = "Close".into();
+    close["speed"] = serde_json::to_value(serde_json::from_value::<Vec<Vec<f64>>>(close["speed"].clone()).unwrap()
+        .into_iter().map(|speeds| speeds.into_iter().map(|speed| speed * 0.99).collect::<Vec<_>>()).collect::<Vec<_>>()).unwrap();
+    polar["sail"].as_array_mut().unwrap().push(close);
+    let polar = Arc::new(serde_json::from_value::<Polar>(polar).expect("polar ok"));
+
+    let wind = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None };
+    let mut cache = PolarCache::new(polar.clone());
+    let mut close = |auto_sail_change: bool| cache.get_boat_speeds(&Heading::TWA(90.0), &wind, &Sail::from_index(1), false, SailFilter::All, auto_sail_change)
+        .into_iter().find(|result| result.sail.index == 1).expect("close sail");
+
+    let boosted = close(true);
+    assert_eq!(boosted.speed.kts(), 10.0);
+    assert!(boosted.boost > 0);
+
+    let raw = close(false);
+    assert!((raw.speed.kts() - 9.9).abs() < 1e-9);
+    assert_eq!(raw.boost, 0);
+
+    let manual = BoatOptions::new().with_manual_sails(true);
+    assert!((polar.speed_at(90.0, Speed::from_kts(10.0), Some(1), &manual, false).speed.kts() - 9.9).abs() < 1e-9);
+    assert_eq!(polar.speed_at(90.0, Speed::from_kts(10.0), Some(1), &BoatOptions::new(), false).speed.kts(), 10.0);
+
+    // the grid at 90° twa
+    let close = |boat_options: &BoatOptions| polar.speed_grid(Speed::from_kts(10.0), boat_options, false)[2].1[1].1.kts();
+    assert!((close(&manual) - 9.9).abs() < 1e-9);
+    assert_eq!(close(&BoatOptions::new()), 10.0);
+}
+
+#[test]
+fn motor() {
+    use std::sync::Arc;
+    use crate::polar::PolarCache;
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let mut cache = PolarCache::new(Arc::new(Polar::motor(Speed::from_kts(7.5))));
+
+    for tws in [0.0, 5.0, 25.0, 60.0, 150.0] {
+        let wind = Wind { direction: 45.0, speed: Speed::from_kts(tws), gust: None };
+        for twa in (-180..=180).step_by(15) {
+            let speeds = cache.get_boat_speeds(&Heading::TWA(twa as f64), &wind, &Sail::default(), false, SailFilter::All, true);
+            assert_eq!(speeds.len(), 1);
+            assert_eq!(speeds[0].speed.kts(), 7.5, "twa {twa} tws {tws}");
+        }
+    }
+}
+
+#[test]
+fn interpolation_index() {
+    let at = |values: &Vec<f64>, value: f64| {
+        let (i0, i1, f) = Polar::interpolation_index(values, value);
+        values[i0] * f + values[i1] * (1.0 - f)
+    };
+
+    let grid = vec![0.0, 10.0, 20.0, 40.0];
+    assert_eq!(at(&grid, 15.0), 15.0);
+    assert_eq!(at(&grid, 40.0), 40.0);
+    // clamped outside of the grid
+    assert_eq!(Polar::interpolation_index(&grid, -5.0), (0, 0, 0.0));
+    assert_eq!(at(&grid, -5.0), 0.0);
+    assert_eq!(at(&grid, 70.0), 40.0);
+
+    let singleton = vec![12.0];
+    for value in [0.0, 12.0, 30.0] {
+        assert_eq!(Polar::interpolation_index(&singleton, value), (0, 0, 0.0));
+    }
+    assert_eq!(Polar::interpolation_index(&Vec::new(), 5.0), (0, 0, 0.0));
+}
+
+#[test]
+fn invalid_polar() {
+    use crate::phtheirichthys::Phtheirichthys;
+
+    assert!(fixtures::polar().validate().is_ok());
+
+    let mut polar = fixtures::polar();
+    polar.tws.clear();
+    assert!(polar.validate().is_err());
+
+    let mut polar = fixtures::polar();
+    polar.sail[0].speed.pop();
+    assert!(polar.validate().is_err());
+
+    let phtheirichthys = Phtheirichthys::new();
+    let mut polar = fixtures::polar();
+    polar.twa.clear();
+    assert!(phtheirichthys.add_polar("empty".to_string(), polar).is_err());
+    assert!(phtheirichthys.list_polars().is_empty());
+}
+
+#[test]
+fn list_and_remove_polars() {
+    use crate::error::PhtError;
+    use crate::phtheirichthys::Phtheirichthys;
+
+    let phtheirichthys = Phtheirichthys::new();
+    phtheirichthys.add_polar("imoca".to_string(), fixtures::polar()).unwrap();
+    phtheirichthys.add_polar("class40".to_string(), fixtures::polar()).unwrap();
+
+    let mut polars = phtheirichthys.list_polars();
+    polars.sort();
+    assert_eq!(polars, vec!["class40".to_string(), "imoca".to_string()]);
+
+    phtheirichthys.remove_polar("imoca".to_string()).unwrap();
+    assert_eq!(phtheirichthys.list_polars(), vec!["class40".to_string()]);
+
+    let e = phtheirichthys.remove_polar("imoca".to_string()).unwrap_err();
+    assert_eq!(e.downcast_ref::<PhtError>(), Some(&PhtError::PolarNotFound("imoca".to_string())));
+    assert_eq!(phtheirichthys.list_polars(), vec!["class40".to_string()]);
+}
@@ -0,0 +1,102 @@
+use crate::position::Coords;
+
+#[test]
+fn coords_normalize_lon() {
+    assert_eq!(Coords::new(10.0, 181.0).expect("valid").lon, -179.0);
+    assert_eq!(Coords::new(10.0, -181.0).expect("valid").lon, 179.0);
+    assert_eq!(Coords::new(10.0, 360.0).expect("valid").lon, 0.0);
+    assert_eq!(Coords::new(10.0, 180.0).expect("valid").lon, -180.0);
+    assert_eq!(Coords::new(10.0, -180.0).expect("valid").lon, -180.0);
+
+    assert_eq!(Coords::from((10.0, 540.0)), Coords { lat: 10.0, lon: -180.0 });
+    assert_eq!(Coords::from([10.0, -190.0]), Coords { lat: 10.0, lon: 170.0 });
+}
+
+#[test]
+fn coords_reject_lat() {
+    assert!(Coords::new(120.0, 0.0).is_err());
+    assert!(Coords::new(-90.5, 0.0).is_err());
+    assert!(Coords::new(90.0, 0.0).is_ok());
+}
+
+fn penalties() -> crate::position::Penalties {
+    use chrono::Duration;
+    use crate::position::{Penalties, Penalty};
+
+    Penalties {
+        gybe: Some(Penalty { duration: Duration::seconds(300), ratio: 0.5 }),
+        sail_change: None,
+        tack: Some(Penalty { duration: Duration::seconds(120), ratio: 0.8 }),
+    }
+}
+
+#[test]
+fn penalties_navigate() {
+    use chrono::Duration;
+
+    let (remaining, ratio) = penalties().navigate(Duration::seconds(180));
+
+    assert_eq!(ratio, 0.5 * 0.8);
+    assert_eq!(remaining.gybe.as_ref().map(|p| (p.duration, p.ratio)), Some((Duration::seconds(120), 0.5)));
+    assert!(remaining.sail_change.is_none());
+    assert!(remaining.tack.is_none());
+
+    assert_eq!(penalties().duration(), Duration::seconds(300));
+    assert_eq!(penalties().min_penalty_duration(), Some(Duration::seconds(120)));
+    assert!(penalties().is_some());
+    assert!(!(penalties() - Duration::seconds(300)).is_some());
+}
+
+#[test]
+fn penalties_to_vec() {
+    use chrono::Duration;
+    use crate::router;
+
+    let penalties = penalties();
+
+    let merged = penalties.to_vec().iter().map(|p| (p.duration, p.ratio)).collect::<Vec<_>>();
+    assert_eq!(merged, vec![(Duration::seconds(300), 0.5), (Duration::seconds(120), 0.8)]);
+
+    let exposed: Vec<router::Penalty> = penalties.into();
+    assert_eq!(exposed.iter().map(|p| p.typ).collect::<Vec<_>>(), vec![router::PenaltyType::Gybe, router::PenaltyType::Tack]);
+}
+
+#[test]
+fn heading_twa_dead_downwind() {
+    use crate::position::Heading;
+
+    // heading 180° off the wind, on both sides of north
+    assert_eq!(Heading::HEADING(180.0).twa(0.0), 180.0);
+    assert_eq!(Heading::HEADING(0.0).twa(180.0), 180.0);
+    assert_eq!(Heading::HEADING(270.0).twa(90.0), 180.0);
+    assert_eq!(Heading::HEADING(90.0).twa(270.0), 180.0);
+
+    assert_eq!(Heading::TWA(-180.0).twa(0.0), 180.0);
+    assert_eq!(Heading::TWA(180.0).twa(0.0), 180.0);
+    assert_eq!(Heading::TWA(-180.0).heading(0.0), Heading::TWA(180.0).heading(0.0));
+
+    assert_eq!(Heading::HEADING(10.0).twa(0.0), -10.0);
+    assert_eq!(Heading::HEADING(350.0).twa(0.0), 10.0);
+}
+
+#[test]
+fn coords_navigation() {
+    use crate::algorithm::Algorithm;
+    use crate::algorithm::great_circle::GreatCircle;
+    use crate::algorithm::spherical::Spherical;
+    use crate::utils::Distance;
+
+    let (from, to) = (Coords { lat: 46.0, lon: -5.0 }, Coords { lat: 16.2, lon: -61.5 });
+    let distance = Distance::from_nm(1200.0);
+
+    assert_eq!(from.rhumb_destination(235.0, distance.clone()), Spherical {}.destination(&from, 235.0, &distance));
+    assert_eq!(from.rhumb_bearing_to(&to), Spherical {}.heading_to(&from, &to));
+    assert_eq!(from.rhumb_distance_to(&to).m(), Spherical {}.distance_to(&from, &to).m());
+
+    assert_eq!(from.great_circle_destination(235.0, distance.clone()), GreatCircle {}.destination(&from, 235.0, &distance));
+    assert_eq!(from.great_circle_bearing_to(&to), GreatCircle {}.heading_to(&from, &to));
+    assert_eq!(from.great_circle_distance_to(&to).m(), GreatCircle {}.distance_to(&from, &to).m());
+
+    // the great circle is the shortest
+    assert!(from.great_circle_distance_to(&to) < from.rhumb_distance_to(&to));
+}
@@ -0,0 +1,301 @@
+use crate::race::{Buoy, Race};
+use crate::tests::fixtures;
+
+#[test]
+fn from_vr_json() {
+    let leg = r#"
+    {
+        "_id": {"race_id": 635, "num": 1},
+        "name": "Transat Test",
+        "boat": {"polar_id": 19},
+        "start": {"lat": 47.5, "lon": -3.2, "date": 1700000000000},
+        "end": {"lat": 16.2, "lon": -61.5, "name": "Pointe-a-Pitre", "radius": 5, "date": 1701000000000},
+        "checkpoints": [
+            {"_id": 2, "group": 2, "name": "Madeira", "start": {"lat": 32.7, "lon": -17.0}, "display": "buoy"},
+            {"_id": 1, "group": 1, "name": "Exit", "start": {"lat": 47.0, "lon": -4.0}, "end": {"lat": 46.0, "lon": -4.0}, "display": "gate"}
+        ],
+        "ice_limits": {"north": [], "south": [{"lat": -60, "lon": -180}, {"lat": -60, "lon": 180}], "maxLat": 90, "minLat": -90}
+    }"#;
+
+    let race = Race::from_vr_json(leg.as_bytes()).expect("race ok");
+
+    assert_eq!(race.id, "635");
+    assert_eq!(race.boat, "19");
+    assert_eq!(race.buoys.len(), 3);
+
+    match &race.buoys[0] {
+        Buoy::Door(door) => {
+            assert_eq!(door.name, "Exit");
+            assert_eq!(door.destination.lat, 46.5);
+        },
+        _ => panic!("first buoy should be a door"),
+    }
+    match &race.buoys[1] {
+        Buoy::Waypoint(waypoint) => assert_eq!(waypoint.name, "Madeira"),
+        _ => panic!("second buoy should be a waypoint"),
+    }
+    match &race.buoys[2] {
+        Buoy::Zone(zone) => {
+            assert_eq!(zone.name, "Pointe-a-Pitre");
+            assert_eq!(zone.radius.nm(), 5.0);
+        },
+        _ => panic!("last buoy should be a zone"),
+    }
+}
+
+#[test]
+fn from_gpx() {
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <gpx version="1.1" creator="test">
+        <metadata><name>Fastnet</name></metadata>
+        <wpt lat="50.77" lon="-1.30"><name>Cowes</name></wpt>
+        <wpt lat='51.38' lon='-9.60'><name>Fastnet Rock</name></wpt>
+        <wpt lat="49.65" lon="-1.62"><name>Cherbourg</name></wpt>
+    </gpx>"#;
+
+    let race = Race::from_gpx(gpx.as_bytes()).expect("race ok");
+
+    assert_eq!(race.name, "Fastnet");
+    assert_eq!(race.start.lat, 50.77);
+    assert_eq!(race.start.lon, -1.30);
+
+    let waypoints = race.buoys.iter().map(|buoy| match buoy {
+        Buoy::Waypoint(waypoint) => (waypoint.name.clone(), waypoint.destination.lat, waypoint.destination.lon),
+        _ => panic!("gpx buoys should be waypoints"),
+    }).collect::<Vec<_>>();
+
+    assert_eq!(waypoints, vec![("Fastnet Rock".to_string(), 51.38, -9.60), ("Cherbourg".to_string(), 49.65, -1.62)]);
+
+    // attributes on their own lines, spaced around `=`
+    let gpx = r#"<gpx>
+        <wpt
+            lat = "50.77"
+            lon=
+                '-1.30'>
+            <name>Cowes</name>
+        </wpt>
+        <wpt lat="51.38"   lon="-9.60"/>
+    </gpx>"#;
+
+    let race = Race::from_gpx(gpx.as_bytes()).expect("race ok");
+
+    assert_eq!(race.start.lat, 50.77);
+    assert_eq!(race.start.lon, -1.30);
+    assert_eq!(race.buoys[0].destination().lat, 51.38);
+    assert_eq!(race.buoys[0].destination().lon, -9.60);
+}
+
+#[test]
+fn validate_next_waypoint() {
+    let gpx = r#"<gpx>
+        <wpt lat="50.77" lon="-1.30"><name>Start</name></wpt>
+        <wpt lat="51.38" lon="-9.60"><name>First</name></wpt>
+        <wpt lat="49.65" lon="-1.62"><name>Second</name></wpt>
+    </gpx>"#;
+
+    let mut race = Race::from_gpx(gpx.as_bytes()).expect("race ok");
+
+    assert!(!race.is_finished());
+    assert!(matches!(race.validate_next_waypoint(), Some(Buoy::Waypoint(waypoint)) if waypoint.name == "First" && waypoint.validated));
+    assert!(!race.is_finished());
+    assert!(matches!(race.validate_next_waypoint(), Some(Buoy::Waypoint(waypoint)) if waypoint.name == "Second"));
+    assert!(race.is_finished());
+    assert!(race.validate_next_waypoint().is_none());
+}
+
+#[test]
+fn course_distance() {
+    use crate::algorithm::Algorithm;
+    use crate::algorithm::spherical::Spherical;
+
+    let gpx = r#"<gpx>
+        <wpt lat="50.77" lon="-1.30"><name>Start</name></wpt>
+        <wpt lat="51.38" lon="-9.60"><name>First</name></wpt>
+        <wpt lat="49.65" lon="-1.62"><name>Second</name></wpt>
+    </gpx>"#;
+
+    let race = Race::from_gpx(gpx.as_bytes()).expect("race ok");
+    let algorithm = Spherical {};
+
+    let expected = algorithm.distance_to(&race.start, race.buoys[0].destination()).m()
+        + algorithm.distance_to(race.buoys[0].destination(), race.buoys[1].destination()).m();
+
+    assert!((race.course_distance(&algorithm).m() - expected).abs() < 1e-6);
+}
+
+#[test]
+fn remove_race() {
+    use crate::race::{Races, RacesSpec};
+
+    let gpx = r#"<gpx><wpt lat="50.77" lon="-1.30"/><wpt lat="51.38" lon="-9.60"/></gpx>"#;
+
+    let races = <Races as RacesSpec>::new();
+    races.set("a".to_string(), Race::from_gpx(gpx.as_bytes()).expect("race ok"));
+    races.set("b".to_string(), Race::from_gpx(gpx.as_bytes()).expect("race ok"));
+    assert_eq!(races.list().len(), 2);
+
+    races.remove(&"a".to_string()).expect("race removed");
+    assert_eq!(races.list().len(), 1);
+    assert!(races.get(&"a".to_string()).is_err());
+    assert!(races.remove(&"a".to_string()).is_err());
+
+    races.clear();
+    assert!(races.list().is_empty());
+}
+
+#[test]
+fn next_buoy() {
+    use crate::position::Coords;
+
+    let mut race = fixtures::race_through(Coords { lat: 46.0, lon: -5.0 }, serde_json::json!([
+        {"type": "Waypoint", "name": "first", "destination": {"lat": 46.0, "lon": -4.0}, "to_avoid": [], "validated": false},
+        {"type": "Zone", "name": "second", "destination": {"lat": 45.0, "lon": -3.0}, "radius": 1, "to_avoid": [], "validated": false}
+    ]));
+
+    assert_eq!(race.next_destination(), Some(Coords { lat: 46.0, lon: -4.0 }));
+    assert_eq!(race.next_buoy_name().as_deref(), Some("first"));
+
+    race.validate_next_waypoint();
+    assert_eq!(race.next_destination(), Some(Coords { lat: 45.0, lon: -3.0 }));
+    assert_eq!(race.next_buoy_name().as_deref(), Some("second"));
+
+    race.validate_next_waypoint();
+    assert_eq!(race.next_destination(), None);
+    assert_eq!(race.next_buoy_name(), None);
+}
+
+#[test]
+fn to_avoid_triangles() {
+    use std::sync::Arc;
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::Coords;
+    use crate::router::echeneis::get_buoys;
+
+    let race = |to_avoid: serde_json::Value| serde_json::from_value::<Race>(serde_json::json!({
+        "id": "test", "leg": 1, "name": "Test", "boat": "1",
+        "start": {"lat": 46.0, "lon": -5.0},
+        "buoys": [{"type": "Waypoint", "name": "end", "destination": {"lat": 46.0, "lon": 0.0}, "to_avoid": to_avoid, "validated": false}]
+    }));
+
+    // collinear points
+    let degenerate = race(serde_json::json!([[{"lat": 0.0, "lon": 0.0}, {"lat": 1.0, "lon": 1.0}, {"lat": 2.0, "lon": 2.0}]]));
+    assert!(degenerate.is_err());
+
+    let race = race(serde_json::json!([[{"lat": 0.0, "lon": 0.0}, {"lat": 0.0, "lon": 2.0}, {"lat": 2.0, "lon": 0.0}]])).expect("race ok");
+    let buoy = get_buoys(&race, race.start.clone(), Arc::new(Spherical {})).next().expect("buoy");
+
+    assert!(buoy.is_to_avoid(&Coords { lat: 0.5, lon: 0.5 }));
+    // edges and vertices are inclusive
+    assert!(buoy.is_to_avoid(&Coords { lat: 0.0, lon: 1.0 }));
+    assert!(buoy.is_to_avoid(&Coords { lat: 1.0, lon: 1.0 }));
+    assert!(buoy.is_to_avoid(&Coords { lat: 2.0, lon: 0.0 }));
+    assert!(!buoy.is_to_avoid(&Coords { lat: 1.01, lon: 1.01 }));
+    assert!(!buoy.is_to_avoid(&Coords { lat: -0.01, lon: 1.0 }));
+}
+
+#[test]
+fn ice_limits_crossing() {
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::Coords;
+    use crate::race::Limits;
+
+    let limits = Limits {
+        north: vec![],
+        south: vec![Coords { lat: -60.0, lon: -10.0 }, Coords { lat: -60.0, lon: 10.0 }],
+        max_lat: 90.0,
+        min_lat: -90.0,
+    };
+    let algorithm = Spherical {};
+
+    let from = Coords { lat: -59.0, lon: 0.0 };
+    assert!(limits.crosses(&from, &Coords { lat: -62.0, lon: 0.0 }, &algorithm));
+    assert!(limits.crosses(&Coords { lat: -62.0, lon: 1.0 }, &from, &algorithm));
+    assert!(!limits.crosses(&from, &Coords { lat: -59.5, lon: 1.0 }, &algorithm));
+    // beyond the end of the line
+    assert!(!limits.crosses(&Coords { lat: -59.0, lon: 20.0 }, &Coords { lat: -62.0, lon: 20.0 }, &algorithm));
+}
+
+#[test]
+fn in_ice_limits() {
+    use crate::position::Coords;
+
+    let mut race = fixtures::race_through(Coords { lat: -50.0, lon: 0.0 }, serde_json::json!([]));
+    assert!(!race.in_ice_limits(&Coords { lat: -80.0, lon: 0.0 }));
+
+    race.ice_limits = serde_json::from_value(serde_json::json!({
+        "north": [], "south": [{"lat": -60.0, "lon": -10.0}, {"lat": -55.0, "lon": 10.0}], "maxLat": 70.0, "minLat": -70.0
+    })).expect("limits ok");
+
+    assert!(!race.in_ice_limits(&Coords { lat: -50.0, lon: 0.0 }));
+    assert!(race.in_ice_limits(&Coords { lat: -58.0, lon: 0.0 }));
+    // the line rises eastward
+    assert!(race.in_ice_limits(&Coords { lat: -58.0, lon: 9.0 }));
+    assert!(!race.in_ice_limits(&Coords { lat: -58.0, lon: -9.0 }));
+    // beyond the lat bounds, whatever the lines
+    assert!(race.in_ice_limits(&Coords { lat: 75.0, lon: 0.0 }));
+    assert!(race.in_ice_limits(&Coords { lat: -72.0, lon: 50.0 }));
+    assert!(!race.in_ice_limits(&Coords { lat: -65.0, lon: 50.0 }));
+}
+
+#[test]
+fn validate_passed_buoys() {
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::Coords;
+
+    let mut race = fixtures::race_through(Coords { lat: 46.0, lon: -5.0 }, serde_json::json!([
+        {"type": "Door", "name": "gate", "port": {"lat": 46.1, "lon": -4.5}, "starboard": {"lat": 45.9, "lon": -4.5},
+         "departure": {"lat": 46.0, "lon": -4.5}, "destination": {"lat": 46.0, "lon": -4.5}, "to_avoid": [], "validated": false},
+        {"type": "Zone", "name": "end", "destination": {"lat": 46.0, "lon": -3.0}, "radius": 1, "to_avoid": [], "validated": false}
+    ]));
+
+    let track = [Coords { lat: 46.0, lon: -5.0 }, Coords { lat: 46.0, lon: -4.6 }, Coords { lat: 46.02, lon: -4.2 }];
+    race.validate_passed(&track, &Spherical {});
+
+    assert_eq!(race.next_buoy_name().as_deref(), Some("end"));
+}
+
+#[test]
+fn buoys_round_trip() {
+    use crate::position::Coords;
+
+    let race = fixtures::race_through(Coords { lat: 47.0, lon: -3.0 }, serde_json::json!([
+        {"type": "Door", "name": "gate", "port": {"lat": 47.0, "lon": -4.0}, "starboard": {"lat": 46.0, "lon": -4.0},
+            "departure": {"lat": 46.5, "lon": -3.9}, "destination": {"lat": 46.5, "lon": -4.0},
+            "to_avoid": [[{"lat": 45.0, "lon": -5.0}, {"lat": 45.5, "lon": -5.0}, {"lat": 45.0, "lon": -5.5}]], "validated": false},
+        {"type": "Waypoint", "name": "mark", "destination": {"lat": 40.0, "lon": -10.0}, "to_avoid": [], "validated": true},
+        {"type": "Zone", "name": "end", "destination": {"lat": 16.2, "lon": -61.5}, "radius": 5.0, "to_avoid": [], "validated": false, "entry_heading": [180.0, 270.0]}
+    ]));
+
+    let json = serde_json::to_value(&race).expect("serialized");
+    let types = json["buoys"].as_array().unwrap().iter().map(|buoy| buoy["type"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(types, vec!["Door", "Waypoint", "Zone"]);
+
+    let back: Race = serde_json::from_value(json).expect("deserialized");
+    assert_eq!(back, race);
+}
+
+#[test]
+fn start_beyond_door() {
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::Coords;
+
+    // crossed from west to east, then a zone further east
+    let race = |start: Coords| -> Race {
+        fixtures::race_through(start, serde_json::json!([
+            {"type": "Door", "name": "gate", "port": {"lat": 47.0, "lon": -4.0}, "starboard": {"lat": 46.0, "lon": -4.0},
+                "departure": {"lat": 46.5, "lon": -4.0}, "destination": {"lat": 46.5, "lon": -4.0}, "to_avoid": [], "validated": false},
+            {"type": "Zone", "name": "end", "destination": {"lat": 46.5, "lon": -2.0}, "radius": 5.0, "to_avoid": [], "validated": false}
+        ]))
+    };
+
+    let validated = |start: Coords| {
+        let mut race = race(start.clone());
+        race.validate_doors_behind(&start, &Spherical {});
+        race.buoys.iter().map(|buoy| buoy.is_validated()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(validated(Coords { lat: 46.5, lon: -3.5 }), vec![true, false]);
+    assert_eq!(validated(Coords { lat: 46.5, lon: -4.5 }), vec![false, false]);
+    // beyond the line, but not facing the door
+    assert_eq!(validated(Coords { lat: 48.0, lon: -3.5 }), vec![false, false]);
+}
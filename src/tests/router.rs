@@ -0,0 +1,248 @@
+use chrono::Duration;
+
+use crate::position::{Coords, Sail};
+use crate::router::{simplify_way, RouteWaypoint};
+use crate::tests::fixtures;
+use crate::utils::Distance;
+
+#[test]
+fn simplify_straight_leg() {
+    use crate::algorithm::spherical::Spherical;
+
+    // along the equator, with a tiny wobble and a sail change half way
+    let mut way = (0..=10).map(|i| {
+        let lat = if i % 2 == 0 { 0.0 } else { 0.0001 };
+        let sail = if i < 5 { Sail::from_index(0) } else { Sail::from_index(2) };
+        let bearing = if i < 10 { Some(0.0) } else { None };
+        RouteWaypoint { bearing, ..fixtures::waypoint(Coords { lat, lon: i as f64 * 0.1 }, sail) }
+    }).collect::<Vec<_>>();
+
+    simplify_way(&mut way, &Distance::from_m(100.0), &Spherical {});
+
+    let lons = way.iter().map(|waypoint| (waypoint.from.lon * 10.0).round() as i32).collect::<Vec<_>>();
+    assert_eq!(lons, vec![0, 5, 10]);
+    assert_eq!(way[0].way_duration, Duration::hours(5));
+    assert_eq!(way[1].way_duration, Duration::hours(5));
+
+    // bearings to the next kept waypoint
+    assert_eq!(way[0].bearing, Some(way[0].bearing_to(&way[1].from, &Spherical {})));
+    assert_eq!(way[1].bearing, Some(way[1].bearing_to(&way[2].from, &Spherical {})));
+    assert_eq!(way[2].bearing, None);
+}
+
+#[test]
+fn simplify_keeps_corners() {
+    use crate::algorithm::spherical::Spherical;
+
+    let sail = Sail::from_index(0);
+    let mut way = vec![
+        fixtures::waypoint(Coords { lat: 0.0, lon: 0.0 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 0.0, lon: 0.5 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 0.5, lon: 1.0 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 0.0, lon: 1.5 }, sail),
+    ];
+
+    simplify_way(&mut way, &Distance::from_m(100.0), &Spherical {});
+
+    assert_eq!(way.len(), 4);
+}
+
+#[test]
+fn bearings_to_next_waypoint() {
+    use std::sync::Arc;
+    use crate::algorithm::{great_circle::GreatCircle, spherical::Spherical, Algorithm};
+    use crate::router::echeneis::{Echeneis, Position};
+
+    let from = Coords { lat: 46.0, lon: -5.0 };
+    let to = Coords { lat: 46.5, lon: -4.0 };
+
+    let start = Position::from(fixtures::route_request(from.clone()));
+    let end = Position { point: to.clone(), duration: start.duration.clone() + Duration::hours(1), previous: Some(Arc::new(start.clone())), ..start.clone() };
+
+    let expected = Spherical {}.heading_to(&from, &to);
+    assert_eq!(start.heading_to_next(&Spherical {}), None);
+    assert_eq!(end.heading_to_next(&Spherical {}), Some(expected));
+    assert_eq!(end.heading_to_next(&GreatCircle {}), Some(GreatCircle {}.heading_to(&from, &to)));
+
+    let way = Echeneis::<Spherical>::way_to(&Spherical {}, Arc::new(end));
+    assert_eq!(way.len(), 2);
+    assert_eq!(way[0].bearing, Some(expected));
+    assert_eq!(way[0].bearing_to(&to, &Spherical {}), expected);
+    assert_eq!(way[1].bearing, None);
+}
+
+#[test]
+fn iso8601_durations() {
+    use serde::{Deserialize, Serialize};
+    use crate::router::duration_iso8601;
+
+    #[derive(Serialize, Deserialize)]
+    struct Leg {
+        #[serde(with = "duration_iso8601")]
+        duration: Duration,
+    }
+
+    for (duration, iso) in [(Duration::minutes(70), "PT1H10M"), (Duration::zero(), "PT0S"), (Duration::seconds(-3_601), "-PT1H1S")] {
+        let json = serde_json::to_value(Leg { duration }).unwrap();
+        assert_eq!(json, serde_json::json!({"duration": iso}));
+
+        let leg: Leg = serde_json::from_value(json).unwrap();
+        assert_eq!(leg.duration, duration);
+    }
+
+    assert_eq!(duration_iso8601::parse("P1DT30S"), Some(Duration::seconds(86_430)));
+    assert_eq!(duration_iso8601::parse("PT10"), None);
+    assert_eq!(duration_iso8601::parse("1H"), None);
+}
+
+#[test]
+fn wind_profile() {
+    use std::sync::Arc;
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::BoatStatus;
+    use crate::router::echeneis::{Echeneis, Position};
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let request = fixtures::route_request(Coords { lat: 46.0, lon: -5.0 });
+    let start_time = request.start_time;
+
+    let mut position = Arc::new(Position::from(request));
+    for i in 1..=3 {
+        let wind = Wind { direction: i as f64 * 10.0, speed: Speed::from_kts(10.0), gust: None };
+        position = Arc::new(Position {
+            point: Coords { lat: 46.0, lon: -5.0 + i as f64 * 0.1 },
+            duration: position.duration.clone() + Duration::hours(1),
+            status: Arc::new(BoatStatus { wind, ..(*position.status).clone() }),
+            previous: Some(position.clone()),
+            ..(*position).clone()
+        });
+    }
+
+    let way = Echeneis::<Spherical>::way_to(&Spherical {}, position);
+    let result = fixtures::route_result(way);
+
+    let profile = result.wind_profile();
+    assert_eq!(profile.len(), result.way.len());
+    assert_eq!(profile[0].0, start_time);
+    assert_eq!(profile[3].0, start_time + Duration::hours(3));
+    let directions = profile.iter().map(|(_, wind)| wind.direction).collect::<Vec<_>>();
+    assert_eq!(directions, vec![10.0, 20.0, 30.0, 30.0]);
+}
+
+#[test]
+fn sail_change_flags() {
+    use std::sync::Arc;
+    use crate::algorithm::spherical::Spherical;
+    use crate::position::BoatSettings;
+    use crate::router::echeneis::{Echeneis, Position};
+
+    let mut request = fixtures::route_request(Coords { lat: 46.0, lon: -5.0 });
+    request.boat_settings.sail = Sail::from_index(0);
+
+    let mut position = Arc::new(Position::from(request));
+    for (i, sail) in [(1, 0), (2, 1), (3, 1)] {
+        position = Arc::new(Position {
+            point: Coords { lat: 46.0, lon: -5.0 + i as f64 * 0.1 },
+            duration: position.duration.clone() + Duration::hours(1),
+            settings: BoatSettings { sail: Sail::from_index(sail), ..position.settings.clone() },
+            is_in_ice_limits: i == 2,
+            previous: Some(position.clone()),
+            ..(*position).clone()
+        });
+    }
+
+    // each waypoint carries the leg it starts, the sail changing on the second one
+    let way = Echeneis::<Spherical>::way_to(&Spherical {}, position);
+    assert_eq!(way.iter().map(|waypoint| waypoint.status.change).collect::<Vec<_>>(), vec![false, true, false, false]);
+    assert_eq!(way.iter().map(|waypoint| waypoint.status.ice).collect::<Vec<_>>(), vec![false, true, false, false]);
+}
+
+#[test]
+fn route_crossing_land() {
+    use crate::phtheirichthys::Phtheirichthys;
+
+    let sail = Sail::from_index(0);
+    let way = vec![
+        fixtures::waypoint(Coords { lat: 46.0, lon: -5.0 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 46.0, lon: -4.8 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 46.0, lon: -4.0 }, sail),
+    ];
+    let result = fixtures::route_result(way);
+
+    // an island between the second and the last waypoints
+    let island = fixtures::Lands(|lat, lon| (lat - 46.0).abs() < 0.1 && (lon + 4.5).abs() < 0.1);
+    assert_eq!(result.crosses_land(&island), Some(1));
+
+    let open_sea = fixtures::Lands(|_, _| false);
+    assert_eq!(result.crosses_land(&open_sea), None);
+
+    // flagged in the infos of the navigations
+    let flagged = Phtheirichthys::check_lands(result.clone(), &island);
+    assert_eq!(flagged.infos.crosses_land, Some(1));
+    assert_eq!(serde_json::to_value(&flagged.infos).unwrap()["crossesLand"], 1);
+
+    let clear = Phtheirichthys::check_lands(result, &open_sea);
+    assert_eq!(clear.infos.crosses_land, None);
+    assert!(serde_json::to_value(&clear.infos).unwrap().get("crossesLand").is_none());
+}
+
+#[test]
+fn telemetry() {
+    use crate::position::{Penalty, PenaltyKind};
+
+    let sail = Sail::from_index(0);
+    let mut way = vec![
+        fixtures::waypoint(Coords { lat: 46.0, lon: -5.0 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 46.0, lon: -4.8 }, sail.clone()),
+        fixtures::waypoint(Coords { lat: 46.0, lon: -4.6 }, sail),
+    ];
+    way[1].duration = Duration::hours(1);
+    way[1].status.penalties = vec![
+        (PenaltyKind::Gybe, Penalty { duration: Duration::minutes(5), ratio: 0.5 }).into(),
+        (PenaltyKind::SailChange, Penalty { duration: Duration::seconds(30), ratio: 0.5 }).into(),
+    ];
+    let result = fixtures::route_result(way);
+
+    let telemetry = result.telemetry(&fixtures::polar());
+    assert_eq!(telemetry.len(), result.way.len());
+
+    let row = &telemetry[1];
+    assert_eq!((row.time, row.lat, row.lon), (3600, 46.0, -4.8));
+    assert_eq!((row.heading, row.twa), (270.0, 90.0));
+    assert_eq!((row.sail.as_str(), row.boat_speed, row.wind_speed), ("Jib", 10.0, 10.0));
+    assert_eq!(row.penalties, 330);
+    assert_eq!(telemetry[0].penalties, 0);
+}
+
+#[test]
+fn remaining_distances() {
+    use crate::algorithm::{great_circle::GreatCircle, Algorithm};
+    use crate::phtheirichthys::Phtheirichthys;
+
+    // through a door at -4.5 to a zone at -4.0
+    let race = fixtures::race_through(Coords { lat: 46.0, lon: -5.0 }, serde_json::json!([
+        {"type": "Door", "name": "gate",
+         "port": {"lat": 46.1, "lon": -4.5}, "starboard": {"lat": 45.9, "lon": -4.5},
+         "departure": {"lat": 46.0, "lon": -4.5}, "destination": {"lat": 46.0, "lon": -4.5},
+         "to_avoid": [], "validated": false},
+        {"type": "Zone", "name": "end", "destination": {"lat": 46.0, "lon": -4.0}, "radius": 0.5, "to_avoid": [], "validated": false}
+    ]));
+
+    let sail = Sail::from_index(0);
+    let lats = [46.0, 46.02, 46.0, 46.02, 46.0, 46.0];
+    let way = lats.iter().enumerate().map(|(i, lat)| fixtures::waypoint(Coords { lat: *lat, lon: -5.0 + i as f64 * 0.2 }, sail.clone())).collect::<Vec<_>>();
+    let result = fixtures::route_result(way);
+
+    let remaining = result.remaining_distances(&race);
+    assert_eq!(remaining.len(), result.way.len());
+    assert!(remaining.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", remaining.iter().map(Distance::nm).collect::<Vec<_>>());
+
+    let course = GreatCircle {}.distance_to(&Coords { lat: 46.0, lon: -5.0 }, &Coords { lat: 46.0, lon: -4.0 });
+    assert!((remaining[0].nm() - course.nm()).abs() < 0.01);
+    assert_eq!(remaining[5], Distance::zero());
+
+    // in nm for the bindings
+    let nm = Phtheirichthys::new().route_remaining_distances(&result, &race);
+    assert_eq!(nm, remaining.iter().map(Distance::nm).collect::<Vec<_>>());
+}
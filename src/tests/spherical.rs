@@ -0,0 +1,60 @@
+use crate::algorithm::Algorithm;
+use crate::algorithm::great_circle::GreatCircle;
+use crate::algorithm::spherical::Spherical;
+use crate::position::Coords;
+
+#[test]
+fn intersection_across_antimeridian() {
+    // door along the equator, from 179°E to 179°W
+    let (port, starboard) = (Coords { lat: 0.0, lon: 179.0 }, Coords { lat: 0.0, lon: -179.0 });
+
+    let algorithms: [Box<dyn Algorithm>; 2] = [Box::new(Spherical {}), Box::new(GreatCircle {})];
+    for algorithm in algorithms.iter() {
+        for (lon, expected) in [(180.0, 180.0), (179.5, 179.5), (-179.5, -179.5)] {
+            let crossing = algorithm.intersection((&port, &starboard), &Coords { lat: 1.0, lon }, 180.0).expect("crossing");
+
+            assert!(crossing.lat.abs() < 1e-6, "{:?}", crossing);
+            let gap = (crossing.lon - expected).rem_euclid(360.0);
+            assert!(gap.min(360.0 - gap) < 1e-6, "{:?}, expected lon {}", crossing, expected);
+            assert!((-180.0..180.0).contains(&crossing.lon));
+        }
+    }
+}
+
+#[test]
+fn intersection() {
+    let line = (&Coords { lat: -60.0, lon: -10.0 }, &Coords { lat: -60.0, lon: 10.0 });
+
+    let crossing = Spherical {}.intersection(line, &Coords { lat: -59.0, lon: 0.0 }, 180.0).expect("crossing");
+
+    // the great circle bulges towards the pole
+    assert!(crossing.lon.abs() < 1e-6, "{:?}", crossing);
+    assert!(crossing.lat < -60.0 && crossing.lat > -61.0, "{:?}", crossing);
+}
+
+#[test]
+fn intersection_on_same_meridian() {
+    // the angular distance from the line origin to a point due north of it is not zero
+    let line = (&Coords { lat: 0.0, lon: 0.0 }, &Coords { lat: 0.0, lon: 10.0 });
+
+    let crossing = Spherical {}.intersection(line, &Coords { lat: 10.0, lon: 0.0 }, 135.0).expect("crossing");
+
+    assert!(crossing.lat.abs() < 1e-6, "{:?}", crossing);
+    assert!((crossing.lon - 9.851).abs() < 1e-3, "{:?}", crossing);
+}
+
+#[test]
+fn distance_to_many() {
+    let pairs = (0..500).map(|i| {
+        let i = i as f64;
+        (Coords { lat: (i * 0.37) % 170.0 - 85.0, lon: (i * 1.3) % 360.0 - 180.0 }, Coords { lat: (i * 0.91) % 170.0 - 85.0, lon: (i * 2.7) % 360.0 - 180.0 })
+    }).collect::<Vec<_>>();
+
+    let algorithm = Spherical {};
+    let distances = algorithm.distance_to_many(&pairs);
+
+    assert_eq!(distances.len(), pairs.len());
+    for ((from, to), distance) in pairs.iter().zip(distances.iter()) {
+        assert_eq!(distance.m(), algorithm.distance_to(from, to).m());
+    }
+}
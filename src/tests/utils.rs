@@ -0,0 +1,68 @@
+use crate::utils::Speed;
+
+#[test]
+fn miles_per_hour() {
+    let speed = Speed::from_mph(10.0);
+
+    assert!((speed.kts() - 8.68976).abs() < 1e-9);
+    assert!((speed.mph() - 10.0).abs() < 1e-9);
+    assert!((speed.km_h() - 16.0934).abs() < 1e-3);
+    assert!((speed.m_s() - 4.4704).abs() < 1e-3);
+    assert!(speed.to_string().ends_with("(mph)"));
+
+    let back = Speed::from_kts(speed.kts());
+    assert!((back.mph() - 10.0).abs() < 1e-9);
+    assert!((Speed::from_m_s(speed.m_s()).mph() - 10.0).abs() < 1e-9);
+    assert!((Speed::from_km_h(speed.km_h()).mph() - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn negative_distances() {
+    use crate::utils::Distance;
+
+    // from inside a 2nm zone, 500m away from its center
+    let inside = Distance::from_m(500.0) - &Distance::from_nm(2.0);
+
+    assert!((inside.signed_m() - (500.0 - 3704.0)).abs() < 1e-9);
+    assert!(inside < Distance::zero());
+    assert!(Distance::zero() > inside);
+    assert!(inside < Distance::from_m(-1.0));
+    assert!(Distance::from_nm(-1.0) < inside);
+
+    let negative_zero = Distance::from_m(0.0) * -1.0;
+    assert_eq!(negative_zero.signed_m(), 0.0);
+    assert_eq!(negative_zero, Distance::zero());
+    assert_eq!(negative_zero.cmp(&Distance::zero()), std::cmp::Ordering::Equal);
+    assert!(!negative_zero.is_negative());
+}
+
+#[test]
+fn distance_abs() {
+    use crate::utils::Distance;
+
+    let inside = Distance::from_m(500.0) - &Distance::from_nm(2.0);
+    assert!(inside.is_negative());
+    assert!(!inside.clone().abs().is_negative());
+    assert!((inside.abs().m() - 3204.0).abs() < 1e-9);
+
+    let outside = Distance::from_nm(3.0);
+    assert!(!outside.is_negative());
+    assert_eq!(outside.clone().abs(), outside);
+}
+
+#[test]
+fn deserialize_speed() {
+    let kts: Speed = serde_json::from_str("12.5").expect("speed ok");
+    assert_eq!(kts.kts(), 12.5);
+    assert_eq!(serde_json::from_str::<Speed>("12").expect("speed ok").kts(), 12.0);
+
+    let m_s: Speed = serde_json::from_str(r#"{"value":6,"unit":"m/s"}"#).expect("speed ok");
+    assert!((m_s.m_s() - 6.0).abs() < 1e-9);
+    assert!((m_s.kts() - 6.0 * 3.6 / 1.852).abs() < 1e-9);
+
+    let default: Speed = serde_json::from_str(r#"{"value":6}"#).expect("speed ok");
+    assert_eq!(default.kts(), 6.0);
+
+    assert!(serde_json::from_str::<Speed>(r#"{"value":6,"unit":"furlong"}"#).is_err());
+    assert!(serde_json::from_str::<Speed>(r#"{"unit":"m/s"}"#).is_err());
+}
@@ -0,0 +1,340 @@
+use chrono::{TimeZone, Utc};
+
+use crate::tests::fixtures;
+use crate::wind::providers::vr::{References, VrWindProvider};
+use crate::wind::Provider;
+
+#[test]
+fn vr_available_range() {
+    let reference = |name: &str, valid: i64, delta_ref: u8| serde_json::json!({
+        "reference": name, "valid_ts": valid, "delta_ref": delta_ref, "delta": 0,
+        "avail_ts": valid, "rel_path": format!("{name}.wnd"),
+    });
+
+    let references: References = serde_json::from_value(serde_json::json!({
+        "export_ts": 1_700_000_000, "publish_ts": 1_700_000_000, "start_ts": 1_700_000_000,
+        "references": [
+            [reference("a", 1_700_000_000, 0)],
+            [reference("b", 1_700_010_800, 3), reference("b0", 1_700_010_800, 9)],
+            [reference("c", 1_700_021_600, 6)],
+        ],
+    })).expect("references ok");
+
+    let vr = VrWindProvider::from_references(references);
+
+    let expected = (Utc.timestamp_opt(1_700_000_000, 0).unwrap(), Utc.timestamp_opt(1_700_021_600, 0).unwrap());
+    assert_eq!(vr.available_range(), Some(expected));
+
+    let status = vr.status();
+    assert_eq!(status.available_range, Some(expected));
+    assert_eq!(status.coverage(), Some(expected));
+}
+
+#[tokio::test]
+async fn constant_provider() {
+    use crate::position::Coords;
+    use crate::utils::Speed;
+    use crate::wind::providers::config::ProviderConfig;
+    use crate::wind::providers::Providers;
+
+    let providers = Providers::new();
+    providers.init_provider(&ProviderConfig::Constant { direction: 270.0, speed: Speed::from_kts(12.0) }).await.expect("init");
+
+    let provider = providers.get("constant".to_string()).expect("provider");
+    for (m, point) in [(Utc::now(), Coords { lat: 0.0, lon: 0.0 }), (Utc.timestamp_opt(0, 0).unwrap(), Coords { lat: -60.0, lon: 170.0 })] {
+        let wind = provider.find(&m).interpolate(&point);
+        assert_eq!(wind.direction, 270.0);
+        assert_eq!(wind.speed.kts(), 12.0);
+    }
+
+    let (first, last) = provider.available_range().expect("range");
+    assert!(first <= fixtures::start_time() && fixtures::start_time() <= last);
+}
+
+#[tokio::test]
+async fn linear_shift_provider() {
+    use chrono::Duration;
+    use crate::position::Coords;
+    use crate::utils::Speed;
+    use crate::wind::providers::config::ProviderConfig;
+    use crate::wind::providers::Providers;
+
+    let providers = Providers::new();
+    let start = fixtures::start_time();
+    providers.init_provider(&ProviderConfig::LinearShift { base_dir: 350.0, deg_per_hour: 7.5, speed: Speed::from_kts(15.0), start }).await.expect("init");
+
+    let provider = providers.get("linear_shift".to_string()).expect("provider");
+    let point = Coords { lat: 46.0, lon: -5.0 };
+
+    let at_start = provider.find(&start).interpolate(&point);
+    let after_an_hour = provider.find(&(start + Duration::hours(1))).interpolate(&point);
+    let after_two_hours = provider.find(&(start + Duration::hours(2))).interpolate(&point);
+
+    assert_eq!(at_start.direction, 350.0);
+    assert_eq!(after_an_hour.direction, 357.5);
+    assert_eq!(after_two_hours.direction, 5.0);
+    assert_eq!(after_an_hour.speed.kts(), 15.0);
+}
+
+/// Serves `statuses` in turn, one connection each, answering `ok` on 200.
+fn mock_server(statuses: Vec<u16>) -> reqwest::Url {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = reqwest::Url::parse(&format!("http://{}/references.json", listener.local_addr().unwrap())).unwrap();
+
+    std::thread::spawn(move || {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().expect("accept");
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+
+            let body = if status == 200 { "ok" } else { "unavailable" };
+            write!(stream, "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len()).unwrap();
+        }
+    });
+
+    url
+}
+
+#[tokio::test]
+async fn retry_until_success() {
+    use std::time::Duration;
+    use crate::wind::providers::retry::Retry;
+
+    let retry = Retry { attempts: 3, base_delay: Duration::from_millis(10) };
+
+    let url = mock_server(vec![503, 503, 200]);
+    let response = retry.get(&reqwest::Client::new(), url).await.expect("third attempt succeeds");
+    assert_eq!(response.text().await.unwrap(), "ok");
+
+    let url = mock_server(vec![503, 503, 503]);
+    assert!(retry.get(&reqwest::Client::new(), url).await.is_err());
+}
+
+#[test]
+fn gust_skipped_when_unknown() {
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    let wind = Wind { direction: 90.0, speed: Speed::from_kts(10.0), gust: None };
+    assert_eq!(serde_json::to_value(&wind).unwrap(), serde_json::json!({"direction": 90.0, "speed": 10.0}));
+
+    let wind: Wind = serde_json::from_value(serde_json::json!({"direction": 90.0, "speed": 10.0, "gust": 13.0})).unwrap();
+    assert_eq!(wind.gust.map(|gust| gust.kts()), Some(13.0));
+}
+
+/// Wind speed growing with latitude and longitude, to tell pixels apart.
+struct Gradient;
+
+impl crate::wind::InstantWind for Gradient {
+    fn interpolate(&self, point: &crate::position::Coords) -> crate::wind::Wind {
+        crate::wind::Wind {
+            direction: 0.0,
+            speed: crate::utils::Speed::from_kts((point.lat + 90.0) / 4.0 + (point.lon + 180.0) / 8.0),
+            gust: None,
+        }
+    }
+}
+
+#[test]
+fn draw_region_matches_draw() {
+    use std::sync::{Arc, Mutex};
+    use crate::wind::InstantWind;
+
+    let z = 4;
+    let (width, height) = (32, 32);
+
+    let region = Arc::new(Mutex::new(Vec::new()));
+    let drawn = region.clone();
+    Gradient.draw_region(3, 5, 5, 6, z, width, height, Box::new(move |tiles| {
+        *drawn.lock().unwrap() = tiles.clone();
+        Ok(())
+    })).unwrap();
+
+    let region = region.lock().unwrap();
+    assert_eq!(region.len(), 6);
+
+    let mut tiles = region.iter();
+    for y in 5..=6 {
+        for x in 3..=5 {
+            let tile = Arc::new(Mutex::new(Vec::new()));
+            let drawn = tile.clone();
+            Gradient.draw(x, y, z, width, height, Box::new(move |data| {
+                *drawn.lock().unwrap() = data.clone();
+                Ok(())
+            })).unwrap();
+
+            assert_eq!(tiles.next(), Some(&*tile.lock().unwrap()), "tile ({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn uv_round_trip() {
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    for direction in [0.0, 0.001, 45.0, 90.0, 180.0, 270.0, 359.999, 360.0] {
+        let wind = Wind { direction, speed: Speed::from_kts(12.0), gust: None };
+        let (u, v) = wind.to_uv();
+        let back = Wind::from_uv(u, v);
+
+        let diff = (back.direction - direction).rem_euclid(360.0);
+        assert!(diff.min(360.0 - diff) < 1e-9, "{} became {}", direction, back.direction);
+        assert!((back.speed.kts() - 12.0).abs() < 1e-9);
+    }
+
+    // a northerly wind blows southward
+    let (u, v) = Wind { direction: 0.0, speed: Speed::from_m_s(5.0), gust: None }.to_uv();
+    assert!(u.abs() < 1e-9 && (v + 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn apparent_wind() {
+    use crate::utils::Speed;
+    use crate::wind::Wind;
+
+    // sailing straight into the wind
+    let (angle, speed) = Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None }.apparent(0.0, Speed::from_kts(5.0));
+    assert!(angle.abs() < 1e-9, "{angle}");
+    assert!((speed.kts() - 15.0).abs() < 1e-9);
+
+    // wind on the beam draws forward
+    let (angle, speed) = Wind { direction: 90.0, speed: Speed::from_kts(10.0), gust: None }.apparent(0.0, Speed::from_kts(10.0));
+    assert!((angle - 45.0).abs() < 1e-9, "{angle}");
+    assert!((speed.kts() - 200f64.sqrt()).abs() < 1e-9);
+
+    // running as fast as the wind
+    let (_, speed) = Wind { direction: 180.0, speed: Speed::from_kts(8.0), gust: None }.apparent(0.0, Speed::from_kts(8.0));
+    assert!(speed.kts().abs() < 1e-9);
+}
+
+#[test]
+fn averaged_in_uniform_wind() {
+    use crate::position::Coords;
+    use crate::utils::{Distance, Speed};
+    use crate::wind::providers::constant::ConstantWindProvider;
+    use crate::wind::InstantWind;
+
+    let winds = ConstantWindProvider::new(359.5, Speed::from_kts(15.0));
+    let point = Coords { lat: 46.0, lon: -5.0 };
+
+    let wind = winds.interpolate_averaged(&point, Distance::from_nm(10.0), 8);
+    let diff = (wind.direction - 359.5).rem_euclid(360.0);
+    assert!(diff.min(360.0 - diff) < 1e-9, "{}", wind);
+    assert!((wind.speed.kts() - winds.interpolate(&point).speed.kts()).abs() < 1e-9);
+}
+
+#[test]
+fn wind_colors() {
+    use crate::utils::Speed;
+    use crate::wind::wind_color;
+
+    assert_eq!(wind_color(&Speed::from_kts(0.0)), [98, 113, 184]);
+    assert_eq!(wind_color(&Speed::from_kts(2.5)), [61, 110, 163]);
+    assert_eq!(wind_color(&Speed::from_kts(75.0)), [91, 136, 161]);
+    assert_eq!(wind_color(&Speed::from_kts(60.0)), [91, 136, 161]);
+    // a fifth of the way between 10 and 15 kts
+    assert_eq!(wind_color(&Speed::from_kts(11.0)), [77, 146, 114]);
+}
+
+#[tokio::test]
+async fn blend_providers() {
+    use std::sync::Arc;
+    use crate::position::Coords;
+    use crate::utils::Speed;
+    use crate::wind::providers::blend::BlendWindProvider;
+    use crate::wind::providers::config::ProviderConfig;
+    use crate::wind::providers::constant::ConstantWindProvider;
+    use crate::wind::providers::Providers;
+    use crate::wind::Wind;
+
+    let north = Arc::new(ConstantWindProvider::new(0.0, Speed::from_kts(10.0)));
+    let east = Arc::new(ConstantWindProvider::new(90.0, Speed::from_kts(20.0)));
+
+    let blend = BlendWindProvider::new(north.clone(), east.clone(), 0.5);
+    let wind = blend.find(&fixtures::start_time()).interpolate(&Coords { lat: 46.0, lon: -5.0 });
+
+    let ((un, vn), (ue, ve)) = (Wind { direction: 0.0, speed: Speed::from_kts(10.0), gust: None }.to_uv(), Wind { direction: 90.0, speed: Speed::from_kts(20.0), gust: None }.to_uv());
+    let (u, v) = wind.to_uv();
+    assert!((u - (un + ue) / 2.0).abs() < 1e-9 && (v - (vn + ve) / 2.0).abs() < 1e-9, "{}", wind);
+
+    let wind = BlendWindProvider::new(north, east, 0.0).find(&fixtures::start_time()).interpolate(&Coords { lat: 46.0, lon: -5.0 });
+    assert!(wind.direction.rem_euclid(360.0) < 1e-9 || wind.direction.rem_euclid(360.0) > 360.0 - 1e-9);
+    assert!((wind.speed.kts() - 10.0).abs() < 1e-9);
+
+    // registered from providers already known
+    let providers = Providers::new();
+    providers.init_provider(&ProviderConfig::Constant { direction: 270.0, speed: Speed::from_kts(12.0) }).await.expect("init");
+    assert!(providers.init_provider(&ProviderConfig::Blend { a: "constant".to_string(), b: "linear_shift".to_string(), weight: 0.0 }).await.is_err());
+
+    providers.init_provider(&ProviderConfig::LinearShift { base_dir: 0.0, deg_per_hour: 1.0, speed: Speed::from_kts(15.0), start: fixtures::start_time() }).await.expect("init");
+    providers.init_provider(&ProviderConfig::Blend { a: "constant".to_string(), b: "linear_shift".to_string(), weight: 0.0 }).await.expect("init");
+    let wind = providers.get_wind("blend".to_string(), fixtures::start_time(), Coords { lat: 46.0, lon: -5.0 }).expect("wind");
+    assert!((wind.direction.rem_euclid(360.0) - 270.0).abs() < 1e-9, "{}", wind);
+    assert!((wind.speed.kts() - 12.0).abs() < 1e-9, "{}", wind);
+}
+
+/// `wind` everywhere, with forecasts from `first` to `last` only.
+struct Window {
+    wind: crate::wind::providers::constant::ConstantWindProvider,
+    first: chrono::DateTime<Utc>,
+    last: chrono::DateTime<Utc>,
+}
+
+impl Provider for Window {
+    fn start(&self) {}
+
+    fn status(&self) -> crate::wind::ProviderStatus {
+        self.wind.status()
+    }
+
+    fn available_range(&self) -> Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
+        Some((self.first, self.last))
+    }
+
+    fn find(&self, m: &chrono::DateTime<Utc>) -> Box<dyn crate::wind::InstantWind + Send + Sync> {
+        self.wind.find(m)
+    }
+}
+
+#[tokio::test]
+async fn fallback_provider() {
+    use std::sync::Arc;
+    use chrono::Duration;
+    use crate::position::Coords;
+    use crate::utils::Speed;
+    use crate::wind::providers::config::ProviderConfig;
+    use crate::wind::providers::constant::ConstantWindProvider;
+    use crate::wind::providers::fallback::FallbackWindProvider;
+    use crate::wind::providers::Providers;
+
+    let t0 = fixtures::start_time();
+    let point = Coords { lat: 46.0, lon: -5.0 };
+
+    let primary = Arc::new(Window { wind: ConstantWindProvider::new(0.0, Speed::from_kts(10.0)), first: t0, last: t0 + Duration::hours(6) });
+    let secondary = Arc::new(ConstantWindProvider::new(90.0, Speed::from_kts(20.0)));
+    let fallback = FallbackWindProvider::new(primary, secondary);
+
+    for hours in [0, 3, 6] {
+        assert_eq!(fallback.find(&(t0 + Duration::hours(hours))).interpolate(&point).direction, 0.0, "t{hours}");
+    }
+    let wind = fallback.find(&(t0 + Duration::hours(12))).interpolate(&point);
+    assert_eq!(wind.direction, 90.0);
+    assert_eq!(wind.speed.kts(), 20.0);
+
+    // registered from providers already known
+    let providers = Providers::new();
+    providers.init_provider(&ProviderConfig::Constant { direction: 270.0, speed: Speed::from_kts(12.0) }).await.expect("init");
+    assert!(providers.init_provider(&ProviderConfig::Fallback { primary: "constant".to_string(), secondary: "linear_shift".to_string() }).await.is_err());
+
+    providers.init_provider(&ProviderConfig::LinearShift { base_dir: 0.0, deg_per_hour: 1.0, speed: Speed::from_kts(15.0), start: t0 }).await.expect("init");
+    providers.init_provider(&ProviderConfig::Fallback { primary: "constant".to_string(), secondary: "linear_shift".to_string() }).await.expect("init");
+    assert_eq!(providers.get_wind("fallback".to_string(), t0, point).expect("wind").direction, 270.0);
+}
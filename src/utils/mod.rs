@@ -20,6 +20,8 @@ pub(crate) enum SpeedUnit {
     Knot,
     MeterPerSecond,
     KiloMeterPerHour,
+    /// 1 mph = 0.868976 kt
+    MilesPerHour,
 }
 
 impl Speed {
@@ -50,11 +52,19 @@ impl Speed {
         }
     }
 
+    pub(crate) fn from_mph(value: f64) -> Self {
+        Self {
+            value,
+            unit: SpeedUnit::MilesPerHour
+        }
+    }
+
     pub(crate) fn kts(&self) -> f64 {
         match &self.unit {
             SpeedUnit::Knot => self.value,
             SpeedUnit::MeterPerSecond => self.value * 3.6 / 1.852,
             SpeedUnit::KiloMeterPerHour => self.value / 1.852,
+            SpeedUnit::MilesPerHour => self.value * 0.868976,
         }
     }
 
@@ -63,6 +73,7 @@ impl Speed {
             SpeedUnit::Knot => self.value * 1.852 / 3.6,
             SpeedUnit::MeterPerSecond => self.value,
             SpeedUnit::KiloMeterPerHour => self.value / 3.6,
+            SpeedUnit::MilesPerHour => self.value * 0.868976 * 1.852 / 3.6,
         }
     }
 
@@ -71,6 +82,16 @@ impl Speed {
             SpeedUnit::Knot => self.value * 1.852,
             SpeedUnit::MeterPerSecond => self.value * 3.6,
             SpeedUnit::KiloMeterPerHour => self.value,
+            SpeedUnit::MilesPerHour => self.value * 0.868976 * 1.852,
+        }
+    }
+
+    pub(crate) fn mph(&self) -> f64 {
+        match &self.unit {
+            SpeedUnit::Knot => self.value / 0.868976,
+            SpeedUnit::MeterPerSecond => self.value * 3.6 / 1.852 / 0.868976,
+            SpeedUnit::KiloMeterPerHour => self.value / 1.852 / 0.868976,
+            SpeedUnit::MilesPerHour => self.value,
         }
     }
 
@@ -85,6 +106,7 @@ impl Display for Speed {
       SpeedUnit::Knot => write!(f, "{}kt (kt)", self.kts()),
       SpeedUnit::MeterPerSecond => write!(f, "{}kt (m/s)", self.kts()),
       SpeedUnit::KiloMeterPerHour => write!(f, "{}kt (km/h)", self.kts()),
+      SpeedUnit::MilesPerHour => write!(f, "{}kt (mph)", self.kts()),
     }
   }
 }
@@ -141,7 +163,7 @@ impl<'de> Visitor<'de> for SpeedVisitor {
     type Value = Speed;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("an integer between -2^31 and 2^31")
+        formatter.write_str("a number of knots or a {value, unit} object")
     }
 
     fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
@@ -151,6 +173,13 @@ impl<'de> Visitor<'de> for SpeedVisitor {
         Ok(Speed::from_kts(value))
     }
 
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Speed::from_kts(value as f64))
+    }
+
     fn visit_i8<E>(self, value: i8) -> Result<Self::Value, E>
     where
         E: de::Error,
@@ -179,6 +208,29 @@ impl<'de> Visitor<'de> for SpeedVisitor {
         Ok(Speed::from_kts(value as f64))
     }
 
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: de::MapAccess<'de>,
+    {
+        let mut value = None;
+        let mut unit = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "value" => value = Some(map.next_value::<f64>()?),
+                "unit" => unit = Some(map.next_value::<String>()?),
+                _ => { map.next_value::<de::IgnoredAny>()?; },
+            }
+        }
+
+        let value = value.ok_or_else(|| de::Error::missing_field("value"))?;
+        match unit.as_deref() {
+            None | Some("kt") | Some("kts") | Some("knot") => Ok(Speed::from_kts(value)),
+            Some("m/s") => Ok(Speed::from_m_s(value)),
+            Some("km/h") => Ok(Speed::from_km_h(value)),
+            Some("mph") => Ok(Speed::from_mph(value)),
+            Some(unit) => Err(de::Error::unknown_variant(unit, &["kt", "m/s", "km/h", "mph"])),
+        }
+    }
 }
 
 impl<'de> Deserialize<'de> for Speed {
@@ -186,7 +238,7 @@ impl<'de> Deserialize<'de> for Speed {
         where
             D: Deserializer<'de>,
     {
-        deserializer.deserialize_f64(SpeedVisitor)
+        deserializer.deserialize_any(SpeedVisitor)
     }
 }
 
@@ -208,7 +260,7 @@ impl Distance {
     pub(crate) fn zero() -> Self {
         Distance {
             value: 0.0,
-            unit: DistanceUnit::Meters
+            unit: DistanceUnit::default()
         }
     }
 
@@ -244,6 +296,24 @@ impl Distance {
         }
     }
 
+    /// Signed length in meters whatever the unit, e.g. negative from inside a `Zone`.
+    pub(crate) fn signed_m(&self) -> f64 {
+        // + 0.0 turns -0.0 into 0.0
+        self.m() + 0.0
+    }
+
+    /// Length whatever the sign, e.g. how far from its edge from inside a `Zone`.
+    pub(crate) fn abs(self) -> Distance {
+        Distance {
+            value: self.value.abs(),
+            unit: self.unit,
+        }
+    }
+
+    pub(crate) fn is_negative(&self) -> bool {
+        self.signed_m() < 0.0
+    }
+
     fn val(&self, unit: &DistanceUnit) -> f64 {
         match unit {
             DistanceUnit::Meters => self.m(),
@@ -263,7 +333,7 @@ impl Display for Distance {
 
 impl PartialEq<Self> for Distance {
     fn eq(&self, other: &Self) -> bool {
-        self.m().eq(&other.m())
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -277,13 +347,13 @@ impl PartialOrd<Self> for Distance {
 
 impl Ord for Distance {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.m().total_cmp(&other.m())
+        self.signed_m().total_cmp(&other.signed_m())
     }
 }
 
 impl PartialEq<&Distance> for Distance {
     fn eq(&self, other: &&Distance) -> bool {
-        self.m().eq(&other.m())
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -486,3 +556,54 @@ pub(crate) fn to_lat_lon(x: f64, y: f64, z: f64) -> (f64, f64) {
 
     (lat, lon)
 }
+
+#[cfg(not(feature = "wasm"))]
+struct ThreadWaker(std::thread::Thread);
+
+#[cfg(not(feature = "wasm"))]
+impl std::task::Wake for ThreadWaker {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls `future` on the current thread, parking it while pending.
+#[cfg(not(feature = "wasm"))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = std::task::Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())));
+    let mut context = std::task::Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            std::task::Poll::Ready(output) => return output,
+            std::task::Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Hands control back to the executor, and on wasm to the js event loop, so that `cancel_navigation` can run during a route.
+pub(crate) async fn yield_now() {
+    #[cfg(feature = "wasm")]
+    JsYield(gloo::timers::future::TimeoutFuture::new(0)).await;
+
+    #[cfg(all(not(feature = "wasm"), feature = "tokio"))]
+    tokio::task::yield_now().await;
+}
+
+/// Zero delay timeout, a macrotask letting the pending js events be handled before resuming.
+#[cfg(feature = "wasm")]
+struct JsYield(gloo::timers::future::TimeoutFuture);
+
+// SAFETY: wasm32 is single-threaded, the timeout is never polled nor dropped from another thread.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+unsafe impl Send for JsYield {}
+
+#[cfg(feature = "wasm")]
+impl std::future::Future for JsYield {
+    type Output = ();
+
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<()> {
+        std::pin::Pin::new(&mut self.get_mut().0).poll(cx)
+    }
+}
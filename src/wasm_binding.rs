@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+#[cfg(target_arch = "wasm32")]
 use std::sync::Arc;
 
 use chrono::{TimeZone, Utc};
@@ -8,17 +11,54 @@ use tsify_next::{declare, Tsify};
 use wasm_bindgen::Clamped;
 use wasm_bindgen::prelude::*;
 use web_sys::{js_sys, ImageData, OffscreenCanvas};
-use crate::phtheirichthys::{BoatOptions, Phtheirichthys, SnakeParams, SnakeResult};
+use crate::algorithm::AlgorithmKind;
+use crate::error::ErrorPayload;
+use crate::phtheirichthys::{ApparentWind, BestHeading, BoatOptions, ComputeBackend, JumpResult, NavSolution, Phtheirichthys, PolarSpeed, SnakeParams, SnakeResult};
 use crate::polar::Polar;
-use crate::position::{Coords, Heading};
+use crate::position::{BoatSettings, Coords, Heading};
 use crate::race::Race;
-use crate::router::{RouteRequest, RouteResult};
+use crate::router::{CancellationToken, LiveBest, RouteRequest, RouteResult};
+#[cfg(target_arch = "wasm32")]
+use crate::router::Isochrone;
+use crate::utils::{Distance, Speed};
 use crate::wind::{providers::{config::ProviderConfig, Providers}, ProviderStatus, Wind};
 
 static PHTHEIRICHTHYS: Lazy<std::sync::RwLock<Phtheirichthys>> = Lazy::new(|| {
     std::sync::RwLock::new(Phtheirichthys::new())
 });
 
+/// A running navigation, which can be cancelled or polled for its best waypoint so far.
+struct Navigation {
+    cancellation: CancellationToken,
+    best: LiveBest,
+}
+
+static NAVIGATIONS: Lazy<Mutex<HashMap<String, Navigation>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// Registers the navigation `navigation_id`, returning its cancellation token and best waypoint so far.
+fn start_navigation(navigation_id: &Option<String>) -> (Option<CancellationToken>, Option<LiveBest>) {
+    match navigation_id {
+        Some(id) => {
+            let navigation = Navigation { cancellation: CancellationToken::new(), best: LiveBest::new() };
+            let started = (Some(navigation.cancellation.clone()), Some(navigation.best.clone()));
+            NAVIGATIONS.lock().unwrap().insert(id.clone(), navigation);
+            started
+        },
+        None => (None, None),
+    }
+}
+
+/// Converts an error into a `{ code, message }` JS object.
+fn to_js_error(e: &anyhow::Error) -> JsValue {
+    let payload = ErrorPayload::from(e);
+    match serde_wasm_bindgen::to_value(&payload) {
+        Ok(value) => value,
+        Err(_) => js_sys::Error::new(&payload.message).into(),
+    }
+}
+
 #[wasm_bindgen(start)]
 fn run() {
     let _ = console_log::init_with_level(Level::Debug);
@@ -29,11 +69,17 @@ pub async fn add_wind_provider() {
     PHTHEIRICHTHYS.read().unwrap().add_wind_provider().await;
 }
 
+/// Sets the current flowing to `direction` everywhere during the next navigations, none with a zero speed.
+#[wasm_bindgen]
+pub fn set_current(direction: f64, speed_kts: f64) {
+    PHTHEIRICHTHYS.read().unwrap().set_current(direction, Speed::from_kts(speed_kts));
+}
+
 #[wasm_bindgen]
 pub fn get_wind_provider_status(provider: String) -> Result<JsValue, JsValue> {
     match PHTHEIRICHTHYS.read().unwrap().get_wind_provider_status(provider) {
         Ok(status) => Ok(serde_wasm_bindgen::to_value(&status)?),
-        Err(e) => Err(js_sys::Error::new(&e.to_string()))?,
+        Err(e) => Err(to_js_error(&e)),
     }
 }
 
@@ -44,7 +90,7 @@ pub fn get_wind(provider: String, m: js_sys::Date, point: JsValue) -> Result<JsV
 
     match PHTHEIRICHTHYS.read().unwrap().get_wind(provider, m, point) {
         Ok(status) => Ok(serde_wasm_bindgen::to_value(&status)?),
-        Err(e) => Err(js_sys::Error::new(&e.to_string()))?,
+        Err(e) => Err(to_js_error(&e)),
     }
 }
 
@@ -53,6 +99,19 @@ pub async fn add_land_provider() {
     PHTHEIRICHTHYS.read().unwrap().add_land_provider().await;
 }
 
+/// `files` maps the paths in the `land` folder (`index`, `carto/1_<lon>_<lat>.deg`) to their content.
+#[wasm_bindgen]
+pub async fn add_land_provider_from_files(files: js_sys::Map) {
+    let mut content = HashMap::new();
+    files.for_each(&mut |data, name| {
+        if let Some(name) = name.as_string() {
+            content.insert(name, js_sys::Uint8Array::new(&data).to_vec());
+        }
+    });
+
+    PHTHEIRICHTHYS.read().unwrap().add_land_provider_from_files(content).await;
+}
+
 #[wasm_bindgen]
 pub fn draw_land(provider: String, canvas: OffscreenCanvas, x: f64, y: f64, z: f64, width: usize, height: usize) -> Result<(), JsValue> {
 
@@ -68,7 +127,7 @@ pub fn draw_land(provider: String, canvas: OffscreenCanvas, x: f64, y: f64, z: f
         Ok(_) => Ok(()),
         Err(e) => {
             error!("Error drawing land : {:?}", e);
-            Err(js_sys::Error::new(&e.to_string()))?
+            Err(to_js_error(&e))
         },
     }
 }
@@ -89,7 +148,7 @@ pub fn draw_wind(provider: String, canvas: OffscreenCanvas, m: js_sys::Date, x:
         Ok(_) => Ok(()),
         Err(e) => {
             error!("Error drawing land : {:?}", e);
-            Err(js_sys::Error::new(&e.to_string()))?
+            Err(to_js_error(&e))
         },
     }
 }
@@ -100,36 +159,222 @@ pub fn eval_snake(route_request: RouteRequest, params: SnakeParams, heading: Hea
         Ok(res) => Ok(res),
         Err(e) => {
             error!("Error evaluating snake : {:?}", e);
-            Err(js_sys::Error::new(&e.to_string()))?
+            Err(to_js_error(&e))
+        },
+    }
+}
+
+#[wasm_bindgen]
+pub fn eval_jump(polar: String, boat_options: BoatOptions, from: Coords, settings: BoatSettings, wind: Wind, duration_secs: i64) -> Result<JumpResult, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().eval_jump(polar, boat_options, from, settings, wind, duration_secs) {
+        Ok(res) => Ok(res),
+        Err(e) => {
+            error!("Error evaluating jump : {:?}", e);
+            Err(to_js_error(&e))
+        },
+    }
+}
+
+#[wasm_bindgen]
+pub fn eta_to(polar: String, boat_options: BoatOptions, from: Coords, settings: BoatSettings, wind: Wind, to: Coords) -> Result<i64, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().eta_to(polar, boat_options, from, settings, wind, to) {
+        Ok(duration) => Ok(duration.num_seconds()),
+        Err(e) => {
+            error!("Error computing eta : {:?}", e);
+            Err(to_js_error(&e))
+        },
+    }
+}
+
+#[wasm_bindgen]
+pub fn best_heading_to(polar: String, wind: Wind, bearing_to_dest: f64, sail: Option<usize>, boat_options: BoatOptions, ice: bool) -> Result<BestHeading, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().best_heading_to(polar, wind, bearing_to_dest, sail, boat_options, ice) {
+        Ok(res) => Ok(res),
+        Err(e) => {
+            error!("Error computing best heading : {:?}", e);
+            Err(to_js_error(&e))
         },
     }
 }
 
+#[wasm_bindgen]
+pub fn polar_speed_at(polar: String, twa: f64, tws_kts: f64, sail: Option<usize>, boat_options: BoatOptions, ice: bool) -> Result<PolarSpeed, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().polar_speed_at(polar, twa, Speed::from_kts(tws_kts), sail, boat_options, ice) {
+        Ok(res) => Ok(res),
+        Err(e) => {
+            error!("Error computing polar speed : {:?}", e);
+            Err(to_js_error(&e))
+        },
+    }
+}
+
+#[wasm_bindgen]
+pub fn polar_speed_grid(polar: String, tws_kts: f64, boat_options: BoatOptions, ice: bool) -> Result<JsValue, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().polar_speed_grid(polar, Speed::from_kts(tws_kts), boat_options, ice) {
+        Ok(grid) => Ok(serde_wasm_bindgen::to_value(&grid)?),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
 #[wasm_bindgen]
 pub fn add_polar(name: String, polar: Polar) -> Result<(), JsValue> {
-    PHTHEIRICHTHYS.read().unwrap().add_polar(name, polar);
+    match PHTHEIRICHTHYS.read().unwrap().add_polar(name, polar) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_motor_polar(name: String, speed_kts: f64) -> Result<(), JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().add_motor_polar(name, Speed::from_kts(speed_kts)) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
 
-    Ok(())
+#[wasm_bindgen]
+pub fn list_polars() -> Vec<String> {
+    PHTHEIRICHTHYS.read().unwrap().list_polars()
+}
+
+#[wasm_bindgen]
+pub fn polar_sails(name: String) -> Result<JsValue, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().polar_sails(name) {
+        Ok(sails) => Ok(serde_wasm_bindgen::to_value(&sails)?),
+        Err(e) => Err(to_js_error(&e)),
+    }
 }
 
 #[wasm_bindgen]
-pub async fn navigate(wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest) -> Result<RouteResult, JsValue> {
+pub fn route_telemetry(route: RouteResult, polar: String) -> Result<JsValue, JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().route_telemetry(&route, polar) {
+        Ok(telemetry) => Ok(serde_wasm_bindgen::to_value(&telemetry)?),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+/// Distance left to sail in nm at each waypoint of `route`, through the buoys of `race` not passed yet.
+#[wasm_bindgen]
+pub fn route_remaining_distances(route: RouteResult, race: Race) -> Vec<f64> {
+    PHTHEIRICHTHYS.read().unwrap().route_remaining_distances(&route, &race)
+}
+
+/// `[time, wind]` at each waypoint of `route`, for charting.
+#[wasm_bindgen]
+pub fn route_wind_profile(route: RouteResult) -> Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(&route.wind_profile())?)
+}
+
+#[wasm_bindgen]
+pub fn remove_polar(name: String) -> Result<(), JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().remove_polar(name) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+#[wasm_bindgen]
+pub async fn navigate(wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<f64>, algorithm: Option<AlgorithmKind>, navigation_id: Option<String>) -> Result<RouteResult, JsValue> {
     debug!("navigate");
-    match PHTHEIRICHTHYS.read().unwrap().navigate(wind_provider, polar_id, race, boat_options, request).await {
+    let (cancellation, live_best) = start_navigation(&navigation_id);
+
+    let result = PHTHEIRICHTHYS.read().unwrap().navigate_live(wind_provider, polar_id, race, boat_options, request, max_duration_hours.map(|h| h as u64), algorithm, cancellation, live_best).await;
+
+    if let Some(id) = navigation_id {
+        NAVIGATIONS.lock().unwrap().remove(&id);
+    }
+
+    match result {
+        Ok(result) => Ok(result),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+/// JS callback, only ever called from the wasm thread.
+#[cfg(target_arch = "wasm32")]
+struct JsIsochroneListener(js_sys::Function);
+
+// SAFETY: wasm32 is single-threaded, so the function is never called, sent nor dropped from another thread.
+// The isochrones reach js as the route yields at each step, see `utils::yield_now`.
+#[cfg(target_arch = "wasm32")]
+unsafe impl Send for JsIsochroneListener {}
+#[cfg(target_arch = "wasm32")]
+unsafe impl Sync for JsIsochroneListener {}
+
+/// Same as `navigate`, calling `on_isochrone(door, isochrone)` with each isochrone as soon as it is computed.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub async fn navigate_streaming(wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, request: RouteRequest, max_duration_hours: Option<f64>, algorithm: Option<AlgorithmKind>, navigation_id: Option<String>, on_isochrone: js_sys::Function) -> Result<RouteResult, JsValue> {
+    debug!("navigate_streaming");
+    let (cancellation, live_best) = start_navigation(&navigation_id);
+
+    let listener = JsIsochroneListener(on_isochrone);
+    let on_isochrone = Arc::new(move |door: &str, isochrone: &Isochrone| {
+        match serde_wasm_bindgen::to_value(isochrone) {
+            Ok(isochrone) => {
+                if let Err(e) = listener.0.call2(&JsValue::NULL, &JsValue::from_str(door), &isochrone) {
+                    error!("Error calling isochrone callback : {:?}", e);
+                }
+            },
+            Err(e) => error!("Error serializing isochrone : {:?}", e),
+        }
+    });
+
+    let result = PHTHEIRICHTHYS.read().unwrap().navigate_streaming(wind_provider, polar_id, race, boat_options, request, max_duration_hours.map(|h| h as u64), algorithm, cancellation, live_best, on_isochrone).await;
+
+    if let Some(id) = navigation_id {
+        NAVIGATIONS.lock().unwrap().remove(&id);
+    }
+
+    match result {
+        Ok(result) => Ok(result),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+/// Routes again from `new_from` at `new_time`, the buoys `previous` passed before its nearest waypoint being validated.
+#[wasm_bindgen]
+pub async fn reroute(wind_provider: String, polar_id: String, race: Race, boat_options: BoatOptions, previous: RouteResult, new_from: Coords, new_time: js_sys::Date, max_duration_hours: Option<f64>, algorithm: Option<AlgorithmKind>) -> Result<RouteResult, JsValue> {
+    debug!("reroute");
+    let new_time = Utc.timestamp_millis_opt(new_time.get_time() as i64).unwrap();
+
+    let result = PHTHEIRICHTHYS.read().unwrap().reroute(wind_provider, polar_id, race, boat_options, previous, new_from, new_time, max_duration_hours.map(|h| h as u64), algorithm).await;
+
+    match result {
         Ok(result) => Ok(result),
-        Err(e) => Err(js_sys::Error::new(&e.to_string()))?,
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+/// Cancels the navigation started with `navigation_id`, returns false if it is not running.
+#[wasm_bindgen]
+pub fn cancel_navigation(navigation_id: String) -> bool {
+    match NAVIGATIONS.lock().unwrap().get(&navigation_id) {
+        Some(navigation) => {
+            navigation.cancellation.cancel();
+            true
+        },
+        None => false,
     }
 }
 
+/// Best waypoint reached so far by the navigation started with `navigation_id`, undefined if none yet or not running.
+#[wasm_bindgen]
+pub fn route_best_so_far(navigation_id: String) -> Result<JsValue, JsValue> {
+    let best = NAVIGATIONS.lock().unwrap().get(&navigation_id).and_then(|navigation| navigation.best.get());
+
+    Ok(serde_wasm_bindgen::to_value(&best)?)
+}
+
 #[wasm_bindgen]
-pub fn test_webgpu() -> Result<(), JsValue> {
+pub async fn test_webgpu() -> Result<ComputeBackend, JsValue> {
     debug!("> test_webgpu");
-    match PHTHEIRICHTHYS.read().unwrap().test_webgpu() {
+    match PHTHEIRICHTHYS.read().unwrap().test_webgpu().await {
         Ok(result) => {
             debug!("< test_webgpu");
             Ok(result)
         },
-        Err(e) => Err(js_sys::Error::new(&e.to_string()))?,
+        Err(e) => Err(to_js_error(&e)),
     }
 }
 
@@ -151,11 +396,100 @@ pub fn list_races() -> VecRaces {
 pub fn get_race(name: String) -> Result<Race, JsValue> {
     match PHTHEIRICHTHYS.read().unwrap().get_race(name) {
         Ok(race) => Ok(race),
-        Err(e) => Err(js_sys::Error::new(&e.to_string()))?,
+        Err(e) => Err(to_js_error(&e)),
     }
 }
 
 #[wasm_bindgen]
 pub fn set_race(name: String, race: Race) {
     PHTHEIRICHTHYS.read().unwrap().set_race(name, race)
-}
\ No newline at end of file
+}
+
+#[wasm_bindgen]
+pub fn remove_race(name: String) -> Result<(), JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().remove_race(name) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn clear_races() {
+    PHTHEIRICHTHYS.read().unwrap().clear_races()
+}
+
+#[wasm_bindgen]
+pub fn set_race_from_vr_json(name: String, bytes: Vec<u8>) -> Result<(), JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().set_race_from_vr_json(name, &bytes) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_race_from_gpx(name: String, bytes: Vec<u8>) -> Result<(), JsValue> {
+    match PHTHEIRICHTHYS.read().unwrap().set_race_from_gpx(name, &bytes) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_js_error(&e)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn course_distance(race: Race, algorithm: Option<AlgorithmKind>) -> f64 {
+    race.course_distance(algorithm.unwrap_or_default().algorithm().as_ref()).nm()
+}
+
+#[wasm_bindgen]
+pub fn next_destination(race: Race) -> Option<Coords> {
+    race.next_destination()
+}
+
+#[wasm_bindgen]
+pub fn next_buoy_name(race: Race) -> Option<String> {
+    race.next_buoy_name()
+}
+
+#[wasm_bindgen]
+pub fn in_ice_limits(race: Race, point: Coords) -> bool {
+    race.in_ice_limits(&point)
+}
+
+#[wasm_bindgen]
+pub fn rhumb_destination(from: Coords, heading: f64, distance_nm: f64) -> Coords {
+    from.rhumb_destination(heading, Distance::from_nm(distance_nm))
+}
+
+#[wasm_bindgen]
+pub fn rhumb_bearing_to(from: Coords, to: Coords) -> f64 {
+    from.rhumb_bearing_to(&to)
+}
+
+#[wasm_bindgen]
+pub fn rhumb_distance_to(from: Coords, to: Coords) -> f64 {
+    from.rhumb_distance_to(&to).nm()
+}
+
+#[wasm_bindgen]
+pub fn great_circle_destination(from: Coords, heading: f64, distance_nm: f64) -> Coords {
+    from.great_circle_destination(heading, Distance::from_nm(distance_nm))
+}
+
+#[wasm_bindgen]
+pub fn great_circle_bearing_to(from: Coords, to: Coords) -> f64 {
+    from.great_circle_bearing_to(&to)
+}
+
+#[wasm_bindgen]
+pub fn great_circle_distance_to(from: Coords, to: Coords) -> f64 {
+    from.great_circle_distance_to(&to).nm()
+}
+
+#[wasm_bindgen]
+pub fn apparent_wind(wind: Wind, boat_heading: f64, boat_speed_kts: f64) -> ApparentWind {
+    PHTHEIRICHTHYS.read().unwrap().apparent_wind(wind, boat_heading, Speed::from_kts(boat_speed_kts))
+}
+
+#[wasm_bindgen]
+pub fn navigation_solution(from: Coords, to: Coords) -> NavSolution {
+    PHTHEIRICHTHYS.read().unwrap().navigation_solution(from, to)
+}
@@ -1,11 +1,12 @@
 use std::{collections::BTreeMap, collections::HashMap, fmt::{Display, Formatter}};
 
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Serialize, Deserialize};
 use tsify_next::Tsify;
 
-use crate::{position::Coords, utils::{self, Speed}};
+use crate::algorithm::{spherical::Spherical, Algorithm};
+use crate::{position::{Coords, Heading}, utils::{self, Distance, Speed}};
 
 pub mod providers;
 mod stamp;
@@ -14,11 +15,36 @@ mod stamp;
 mod tests;
 
 pub(crate) trait Provider {
+    /// Starts the periodic refresh of the forecasts, which natively needs the `tokio` feature and runtime.
     fn start(&self);
 
+    /// Reloads the forecasts once, outside of the periodic refresh started by `start`.
+    fn refresh_now(&self) {}
+
     fn status(&self) -> ProviderStatus;
 
+    /// Earliest and latest forecast times whose data is loaded.
+    fn available_range(&self) -> Option<(ForecastTime, ForecastTime)>;
+
     fn find(&self, m: &DateTime<Utc>) -> Box<dyn InstantWind + Send + Sync>;
+
+    /// Time elapsed from the availability of the newest loaded forecast run to `now`, zero for providers without runs.
+    ///
+    /// Measured from when the run was published rather than from the `valid` time of its forecasts: those lie days
+    /// ahead of `now`, so a provider stuck on an old run would only show an age once its whole horizon had elapsed.
+    fn data_age(&self, _now: &DateTime<Utc>) -> Duration {
+        Duration::zero()
+    }
+
+    /// Whether the newest loaded run is more than `threshold` old, the forecasts having stopped updating.
+    fn is_stale(&self, now: &DateTime<Utc>, threshold: &Duration) -> bool {
+        self.data_age(now) > *threshold
+    }
+
+    /// Ratio of gusts to the mean wind speed, providers only knowing the mean.
+    fn gust_factor(&self) -> f64 {
+        1.3
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -27,8 +53,26 @@ pub struct ProviderStatus {
     pub last: Option<ForecastTime>,
     pub progress: u8,
     pub forecasts: BTreeMap<ForecastTime, Vec<RefTime>>,
+    #[serde(default)]
+    pub available_range: Option<(ForecastTime, ForecastTime)>,
+    /// Newest forecast run published more than `STALE_AFTER_HOURS` ago, whatever the `valid` time of its forecasts,
+    /// see `Provider::data_age`.
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl ProviderStatus {
+    pub fn coverage(&self) -> Option<(ForecastTime, ForecastTime)> {
+        let first = self.forecasts.keys().next()?;
+        let last = self.forecasts.keys().next_back()?;
+
+        Some((first.clone(), last.clone()))
+    }
 }
 
+/// Age of the newest forecast run beyond which a provider is reported stale, forecasts being run every 6 hours.
+pub(crate) const STALE_AFTER_HOURS: i64 = 12;
+
 type RefTime = DateTime<Utc>;
 
 type ForecastTime = DateTime<Utc>;
@@ -36,25 +80,45 @@ type ForecastTime = DateTime<Utc>;
 pub(crate) trait InstantWind {
     fn interpolate(&self, point: &Coords) -> Wind;
 
+    /// Vector mean of the winds at `point` and at `samples` points evenly spread on a circle of `radius` around it.
+    fn interpolate_averaged(&self, point: &Coords, radius: Distance, samples: usize) -> Wind {
+        let algorithm = Spherical {};
+
+        let winds = std::iter::once(self.interpolate(point))
+            .chain((0..samples).map(|i| self.interpolate(&algorithm.destination(point, i as f64 * 360.0 / samples as f64, &radius))))
+            .collect::<Vec<_>>();
+
+        let (u, v) = winds.iter().map(Wind::to_uv).fold((0.0, 0.0), |(u, v), (wu, wv)| (u + wu, v + wv));
+        let mut wind = Wind::from_uv(u / winds.len() as f64, v / winds.len() as f64);
+
+        wind.gust = winds.iter()
+            .map(|wind| wind.gust.as_ref().map(Speed::kts))
+            .sum::<Option<f64>>()
+            .map(|kts| Speed::from_kts(kts / winds.len() as f64));
+
+        wind
+    }
+
     fn draw(&self, x: i64, y: i64, z: u32, width: usize, height: usize, f: Box<dyn FnOnce(&Vec<u8>) -> Result<()> + 'static>) -> Result<()> {
-        let colors = vec![
-            ( 98f64, 113f64, 184f64),
-            ( 61f64, 110f64, 163f64),
-            ( 74f64, 148f64, 170f64),
-            ( 74f64, 146f64, 148f64),
-            ( 77f64, 142f64, 124f64),
-            ( 76f64, 164f64,  76f64),
-            (103f64, 164f64,  54f64),
-            (162f64, 135f64,  64f64),
-            (162f64, 109f64,  92f64),
-            (141f64,  63f64,  92f64),
-            (151f64,  75f64, 145f64),
-            ( 95f64, 100f64, 160f64),
-            ( 91f64, 136f64, 161f64),
-        ];
-
-        let speeds = vec!(Speed::from_kts(0.0), Speed::from_kts(2.5), Speed::from_kts(5.0), Speed::from_kts(7.5), Speed::from_kts(10.0), Speed::from_kts(15.0), Speed::from_kts(20.0), Speed::from_kts(25.0), Speed::from_kts(30.0), Speed::from_kts(35.0), Speed::from_kts(40.0), Speed::from_kts(50.0), Speed::from_kts(60.0));
+        f(&self.render(x, y, z, width, height, &mut HashMap::new()))
+    }
 
+    /// Draws the tiles from `(x0, y0)` to `(x1, y1)` included, row by row, sharing wind lookups between them.
+    fn draw_region(&self, x0: i64, y0: i64, x1: i64, y1: i64, z: u32, width: usize, height: usize, f: Box<dyn FnOnce(&Vec<Vec<u8>>) -> Result<()> + 'static>) -> Result<()> {
+        let mut cache = HashMap::new();
+
+        let mut tiles = Vec::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                tiles.push(self.render(x, y, z, width, height, &mut cache));
+            }
+        }
+
+        f(&tiles)
+    }
+
+    /// RGBA pixels of a tile, winds being looked up at the nearest hundredth of degree through `cache`.
+    fn render(&self, x: i64, y: i64, z: u32, width: usize, height: usize, cache: &mut HashMap<(i64, i64), Wind>) -> Vec<u8> {
         let mut data = vec![0u8; width * height * 4];
 
         for i in 0..width {
@@ -62,35 +126,57 @@ pub(crate) trait InstantWind {
 
                 let (lat, lon) = utils::to_lat_lon((x * width as i64 + i as i64) as f64, (y * height as i64 + j as i64) as f64, z as f64);
 
-                let wind = self.interpolate(&Coords { lat, lon });
-
-                let mut s = 0;
-                for k in 0..speeds.len() {
-                    s = k;
-                    if speeds[k] >= wind.speed {
-                        break
-                    }
-                }
-
-                let mut h = 0f64;
-                let mut s_1 = s;
-                let s_2 = s;
-                if speeds[s_2] > wind.speed && s > 0 {
-                    s_1 = s - 1;
-                    h = (wind.speed.kts() - speeds[s_1].kts()) / (speeds[s_2].kts() - speeds[s_1].kts());
-                }
-
-                data[(j * width + i) * 4] = (colors[s_1].0*(1.0-h) + colors[s_2].0*h) as u8;
-                data[(j * width + i) * 4 + 1] = (colors[s_1].1*(1.0-h) + colors[s_2].1*h) as u8;
-                data[(j * width + i) * 4 + 2] = (colors[s_1].2*(1.0-h) + colors[s_2].2*h) as u8;
+                let key = ((lat * 100.0).round() as i64, (lon * 100.0).round() as i64);
+                let wind = cache.entry(key)
+                    .or_insert_with(|| self.interpolate(&Coords { lat: key.0 as f64 / 100.0, lon: key.1 as f64 / 100.0 }));
+
+                let color = wind_color(&wind.speed);
+
+                data[(j * width + i) * 4] = color[0];
+                data[(j * width + i) * 4 + 1] = color[1];
+                data[(j * width + i) * 4 + 2] = color[2];
                 data[(j * width + i) * 4 + 3] = 255;
             }
         }
 
-        f(&data)
+        data
     }
 }
 
+/// Color stops of the wind speeds, in knots.
+const COLORS: [(f64, [f64; 3]); 13] = [
+    ( 0.0, [ 98.0, 113.0, 184.0]),
+    ( 2.5, [ 61.0, 110.0, 163.0]),
+    ( 5.0, [ 74.0, 148.0, 170.0]),
+    ( 7.5, [ 74.0, 146.0, 148.0]),
+    (10.0, [ 77.0, 142.0, 124.0]),
+    (15.0, [ 76.0, 164.0,  76.0]),
+    (20.0, [103.0, 164.0,  54.0]),
+    (25.0, [162.0, 135.0,  64.0]),
+    (30.0, [162.0, 109.0,  92.0]),
+    (35.0, [141.0,  63.0,  92.0]),
+    (40.0, [151.0,  75.0, 145.0]),
+    (50.0, [ 95.0, 100.0, 160.0]),
+    (60.0, [ 91.0, 136.0, 161.0]),
+];
+
+/// RGB color of `speed`, blending the surrounding stops, clamped to the first and last ones.
+pub(crate) fn wind_color(speed: &Speed) -> [u8; 3] {
+    let kts = speed.kts();
+
+    let upper = match COLORS.iter().position(|(stop, _)| *stop >= kts) {
+        Some(0) => return COLORS[0].1.map(|c| c as u8),
+        Some(upper) => upper,
+        None => return COLORS[COLORS.len() - 1].1.map(|c| c as u8),
+    };
+
+    let (low, low_color) = COLORS[upper - 1];
+    let (high, high_color) = COLORS[upper];
+    let h = (kts - low) / (high - low);
+
+    [0, 1, 2].map(|c| (low_color[c] * (1.0 - h) + high_color[c] * h).round() as u8)
+}
+
 pub(crate) fn vector_to_degrees(u: f64, v: f64) -> f64 {
     let velocity_dir = libm::atan2(u, v);
     let velocity_dir_to_degrees = velocity_dir.to_degrees() + 180.0;
@@ -104,6 +190,9 @@ pub struct Wind {
     pub direction: f64,
     #[tsify(type = "number")]
     pub speed: Speed,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(type = "number", optional)]
+    pub gust: Option<Speed>,
 }
 
 impl Display for Wind {
@@ -113,6 +202,32 @@ impl Display for Wind {
 }
 
 impl Wind {
+    /// Wind blowing with the eastward `u` and northward `v` components, in m/s.
+    pub(crate) fn from_uv(u: f64, v: f64) -> Self {
+        Self {
+            direction: vector_to_degrees(u, v),
+            speed: Speed::from_m_s((u*u + v*v).sqrt()),
+            gust: None,
+        }
+    }
+
+    /// Eastward and northward components, in m/s, the wind blowing towards the opposite of its direction.
+    pub(crate) fn to_uv(&self) -> (f64, f64) {
+        let (sin, cos) = self.direction.to_radians().sin_cos();
+
+        (-self.speed.m_s() * sin, -self.speed.m_s() * cos)
+    }
+
+    /// Apparent wind angle, from the bow as a twa, and speed on a boat sailing `boat_heading` at `boat_speed`.
+    pub(crate) fn apparent(&self, boat_heading: f64, boat_speed: Speed) -> (f64, Speed) {
+        let (u, v) = self.to_uv();
+        let (sin, cos) = boat_heading.to_radians().sin_cos();
+
+        let apparent = Wind::from_uv(u - boat_speed.m_s() * sin, v - boat_speed.m_s() * cos);
+
+        (Heading::HEADING(boat_heading).twa(apparent.direction), apparent.speed)
+    }
+
     pub(crate) fn gap(&self, other: &Self) -> u8 {
         let mut diff = (self.direction - other.direction).abs();
         if diff > 180.0 {
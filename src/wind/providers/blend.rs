@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::position::Coords;
+use crate::utils::Speed;
+use crate::wind::{InstantWind, Provider, ProviderStatus, Wind, STALE_AFTER_HOURS};
+
+/// Cross-fade between two providers, `weight` being the share of `b`.
+pub(crate) struct BlendWindProvider {
+    pub(crate) a: Arc<dyn Provider + Send + Sync>,
+    pub(crate) b: Arc<dyn Provider + Send + Sync>,
+    pub(crate) weight: f64,
+}
+
+impl BlendWindProvider {
+    pub(crate) fn new(a: Arc<dyn Provider + Send + Sync>, b: Arc<dyn Provider + Send + Sync>, weight: f64) -> Self {
+        Self { a, b, weight: weight.clamp(0.0, 1.0) }
+    }
+}
+
+impl Provider for BlendWindProvider {
+    fn start(&self) {
+        self.a.start();
+        self.b.start();
+    }
+
+    fn status(&self) -> ProviderStatus {
+        let (a, b) = (self.a.status(), self.b.status());
+
+        let mut status = if b.progress > a.progress { b } else { a };
+        status.available_range = self.available_range();
+        status.stale = self.is_stale(&Utc::now(), &Duration::hours(STALE_AFTER_HOURS));
+        status
+    }
+
+    /// Times covered by both providers.
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let (a_first, a_last) = self.a.available_range()?;
+        let (b_first, b_last) = self.b.available_range()?;
+
+        let (first, last) = (a_first.max(b_first), a_last.min(b_last));
+        if first <= last {
+            Some((first, last))
+        } else {
+            None
+        }
+    }
+
+    fn find(&self, m: &DateTime<Utc>) -> Box<dyn InstantWind + Send + Sync> {
+        Box::new(BlendInstantWind {
+            a: self.a.find(m),
+            b: self.b.find(m),
+            weight: self.weight,
+        })
+    }
+
+    /// Age of the older of both providers, each feeding the blended wind.
+    fn data_age(&self, now: &DateTime<Utc>) -> Duration {
+        self.a.data_age(now).max(self.b.data_age(now))
+    }
+}
+
+struct BlendInstantWind {
+    a: Box<dyn InstantWind + Send + Sync>,
+    b: Box<dyn InstantWind + Send + Sync>,
+    weight: f64,
+}
+
+impl InstantWind for BlendInstantWind {
+    fn interpolate(&self, point: &Coords) -> Wind {
+        let (a, b) = (self.a.interpolate(point), self.b.interpolate(point));
+        let ((ua, va), (ub, vb)) = (a.to_uv(), b.to_uv());
+
+        let mut wind = Wind::from_uv(ua + (ub - ua) * self.weight, va + (vb - va) * self.weight);
+        wind.gust = match (a.gust, b.gust) {
+            (Some(a), Some(b)) => Some(Speed::from_kts(a.kts() + (b.kts() - a.kts()) * self.weight)),
+            _ => None,
+        };
+
+        wind
+    }
+}
@@ -0,0 +1,203 @@
+//! IndexedDB cache of the VR winds : the last references index and the decoded reference grids.
+//!
+//! The cache is best effort : any failure is logged and seen as a miss. Without the `wasm` feature
+//! nothing is ever cached.
+
+#[cfg(feature = "wasm")]
+use anyhow::{bail, Result};
+#[cfg(feature = "wasm")]
+use log::error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+#[cfg(feature = "wasm")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "wasm")]
+use web_sys::{js_sys::{Array, Float64Array, Function, Promise}, IdbDatabase, IdbRequest, IdbTransactionMode};
+
+#[cfg(feature = "wasm")]
+const DATABASE: &str = "phtheirichthys";
+#[cfg(feature = "wasm")]
+const INDEX: &str = "vr_index";
+#[cfg(feature = "wasm")]
+const REFERENCES: &str = "vr_references";
+
+/// Last references index seen, as downloaded.
+pub(crate) async fn get_index() -> Option<String> {
+    #[cfg(feature = "wasm")]
+    {
+        match get(INDEX, "references").await {
+            Ok(index) => index.and_then(|index| index.as_string()),
+            Err(e) => {
+                error!("Error reading cached winds references : {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    None
+}
+
+pub(crate) async fn put_index(index: &str) {
+    #[cfg(feature = "wasm")]
+    if let Err(e) = put(INDEX, "references", &JsValue::from_str(index)).await {
+        error!("Error caching winds references : {}", e);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    let _ = index;
+}
+
+/// Decoded `(u, v)` grid of a reference, flattened.
+pub(crate) async fn get_reference(reference: &str) -> Option<Vec<f64>> {
+    #[cfg(feature = "wasm")]
+    {
+        match get(REFERENCES, reference).await {
+            Ok(data) => data.map(|data| Float64Array::new(&data).to_vec()),
+            Err(e) => {
+                error!("Error reading cached reference {} : {}", reference, e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    {
+        let _ = reference;
+        None
+    }
+}
+
+pub(crate) async fn put_reference(reference: &str, data: &[f64]) {
+    #[cfg(feature = "wasm")]
+    if let Err(e) = put(REFERENCES, reference, &Float64Array::from(data).into()).await {
+        error!("Error caching reference {} : {}", reference, e);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    let _ = (reference, data);
+}
+
+/// Drops the cached grids of the references not in `kept`, past runs never being listed again.
+pub(crate) async fn retain_references(kept: &[String]) {
+    #[cfg(feature = "wasm")]
+    if let Err(e) = retain(REFERENCES, kept).await {
+        error!("Error pruning cached references : {}", e);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    let _ = kept;
+}
+
+/// Resolves with the request's result once it succeeds.
+#[cfg(feature = "wasm")]
+fn request_future(request: &IdbRequest) -> JsFuture {
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let succeeded = request.clone();
+        let onsuccess = Closure::once_into_js(move || {
+            let _ = resolve.call1(&JsValue::UNDEFINED, &succeeded.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let failed = request.clone();
+        let onerror = Closure::once_into_js(move || {
+            let error = failed.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
+            let _ = reject.call1(&JsValue::UNDEFINED, &error);
+        });
+
+        request.set_onsuccess(Some(onsuccess.unchecked_ref()));
+        request.set_onerror(Some(onerror.unchecked_ref()));
+    });
+
+    JsFuture::from(promise)
+}
+
+#[cfg(feature = "wasm")]
+async fn open() -> Result<IdbDatabase> {
+    let factory = match web_sys::window().map(|window| window.indexed_db()) {
+        Some(Ok(Some(factory))) => factory,
+        _ => bail!("IndexedDB not available"),
+    };
+
+    let request = match factory.open_with_u32(DATABASE, 1) {
+        Ok(request) => request,
+        Err(e) => bail!("Fail opening database : {:?}", e),
+    };
+
+    let upgraded = request.clone();
+    let onupgradeneeded = Closure::once_into_js(move || {
+        if let Ok(db) = upgraded.result() {
+            let db: IdbDatabase = db.unchecked_into();
+            for store in [INDEX, REFERENCES] {
+                if !db.object_store_names().contains(store) {
+                    let _ = db.create_object_store(store);
+                }
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(onupgradeneeded.unchecked_ref()));
+
+    match request_future(&request).await {
+        Ok(db) => Ok(db.unchecked_into()),
+        Err(e) => bail!("Fail opening database : {:?}", e),
+    }
+}
+
+#[cfg(feature = "wasm")]
+async fn get(store: &str, key: &str) -> Result<Option<JsValue>> {
+    let db = open().await?;
+
+    let request = match db.transaction_with_str(store).and_then(|transaction| transaction.object_store(store)).and_then(|store| store.get(&JsValue::from_str(key))) {
+        Ok(request) => request,
+        Err(e) => bail!("Fail reading {} from {} : {:?}", key, store, e),
+    };
+
+    match request_future(&request).await {
+        Ok(value) if value.is_undefined() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(e) => bail!("Fail reading {} from {} : {:?}", key, store, e),
+    }
+}
+
+#[cfg(feature = "wasm")]
+async fn put(store: &str, key: &str, value: &JsValue) -> Result<()> {
+    let db = open().await?;
+
+    let request = match db.transaction_with_str_and_mode(store, IdbTransactionMode::Readwrite).and_then(|transaction| transaction.object_store(store)).and_then(|store| store.put_with_key(value, &JsValue::from_str(key))) {
+        Ok(request) => request,
+        Err(e) => bail!("Fail writing {} to {} : {:?}", key, store, e),
+    };
+
+    match request_future(&request).await {
+        Ok(_) => Ok(()),
+        Err(e) => bail!("Fail writing {} to {} : {:?}", key, store, e),
+    }
+}
+
+#[cfg(feature = "wasm")]
+async fn retain(store: &str, kept: &[String]) -> Result<()> {
+    let db = open().await?;
+
+    let request = match db.transaction_with_str(store).and_then(|transaction| transaction.object_store(store)).and_then(|store| store.get_all_keys()) {
+        Ok(request) => request,
+        Err(e) => bail!("Fail listing {} : {:?}", store, e),
+    };
+
+    let keys = match request_future(&request).await {
+        Ok(keys) => Array::from(&keys),
+        Err(e) => bail!("Fail listing {} : {:?}", store, e),
+    };
+
+    let object_store = match db.transaction_with_str_and_mode(store, IdbTransactionMode::Readwrite).and_then(|transaction| transaction.object_store(store)) {
+        Ok(object_store) => object_store,
+        Err(e) => bail!("Fail pruning {} : {:?}", store, e),
+    };
+
+    for key in keys.iter() {
+        if key.as_string().is_some_and(|key| !kept.contains(&key)) {
+            if let Err(e) = object_store.delete(&key) {
+                bail!("Fail deleting {:?} from {} : {:?}", key, store, e);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::Speed;
+
 use super::storage::StorageConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -9,6 +12,12 @@ pub enum ProviderConfig {
 //   Meteofrance(MeteofranceProviderConfig),
 //   Zezo(ZezoProviderConfig),
   Vr,
+  Constant { direction: f64, speed: Speed },
+  LinearShift { base_dir: f64, deg_per_hour: f64, speed: Speed, start: DateTime<Utc> },
+  /// Registered providers by name, `secondary` used where `primary` has no forecast.
+  Fallback { primary: String, secondary: String },
+  /// Registered providers by name, cross-faded with `weight` the share of `b`.
+  Blend { a: String, b: String, weight: f64 },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
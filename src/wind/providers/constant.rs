@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+
+use crate::position::Coords;
+use crate::utils::Speed;
+use crate::wind::{InstantWind, Provider, ProviderStatus, Wind};
+
+/// Same wind everywhere, at any time. Deterministic, meant for tests and demos.
+#[derive(Clone, Debug)]
+pub(crate) struct ConstantWindProvider {
+    pub(crate) direction: f64,
+    pub(crate) speed: Speed,
+}
+
+impl ConstantWindProvider {
+    pub(crate) fn new(direction: f64, speed: Speed) -> Self {
+        Self { direction, speed }
+    }
+}
+
+impl Provider for ConstantWindProvider {
+    fn start(&self) {}
+
+    fn status(&self) -> ProviderStatus {
+        ProviderStatus {
+            current_ref_time: Utc::now(),
+            last: None,
+            progress: 100,
+            forecasts: Default::default(),
+            available_range: self.available_range(),
+            stale: false,
+        }
+    }
+
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC))
+    }
+
+    fn find(&self, _m: &DateTime<Utc>) -> Box<dyn InstantWind + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl InstantWind for ConstantWindProvider {
+    fn interpolate(&self, _point: &Coords) -> Wind {
+        Wind {
+            direction: self.direction,
+            speed: self.speed.clone(),
+            gust: None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::wind::{InstantWind, Provider, ProviderStatus, STALE_AFTER_HOURS};
+
+/// `primary` wherever its forecasts reach, `secondary` beyond.
+pub(crate) struct FallbackWindProvider {
+    pub(crate) primary: Arc<dyn Provider + Send + Sync>,
+    pub(crate) secondary: Arc<dyn Provider + Send + Sync>,
+}
+
+impl FallbackWindProvider {
+    pub(crate) fn new(primary: Arc<dyn Provider + Send + Sync>, secondary: Arc<dyn Provider + Send + Sync>) -> Self {
+        Self { primary, secondary }
+    }
+
+    /// Whether `primary` has forecasts covering `m`.
+    pub(crate) fn covers(&self, m: &DateTime<Utc>) -> bool {
+        self.primary.available_range().is_some_and(|(first, last)| first <= *m && *m <= last)
+    }
+}
+
+impl Provider for FallbackWindProvider {
+    fn start(&self) {
+        self.primary.start();
+        self.secondary.start();
+    }
+
+    fn status(&self) -> ProviderStatus {
+        let mut status = self.primary.status();
+        status.available_range = self.available_range();
+        status.stale = self.is_stale(&Utc::now(), &Duration::hours(STALE_AFTER_HOURS));
+        status
+    }
+
+    /// Times covered by either provider, gaps between them included.
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        match (self.primary.available_range(), self.secondary.available_range()) {
+            (Some((a_first, a_last)), Some((b_first, b_last))) => Some((a_first.min(b_first), a_last.max(b_last))),
+            (range, None) | (None, range) => range,
+        }
+    }
+
+    fn find(&self, m: &DateTime<Utc>) -> Box<dyn InstantWind + Send + Sync> {
+        if self.covers(m) {
+            self.primary.find(m)
+        } else {
+            self.secondary.find(m)
+        }
+    }
+
+    fn data_age(&self, now: &DateTime<Utc>) -> Duration {
+        self.primary.data_age(now)
+    }
+
+    fn gust_factor(&self) -> f64 {
+        self.primary.gust_factor()
+    }
+}
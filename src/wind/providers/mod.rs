@@ -4,14 +4,21 @@ use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
 use log::{debug, error, info};
 
+use crate::error::PhtError;
 use crate::position::Coords;
 
 use self::config::ProviderConfig;
 
 use super::{Provider, ProviderStatus, Wind};
 
+pub(crate) mod blend;
+pub(crate) mod cache;
 pub(crate) mod config;
-mod storage;
+pub(crate) mod constant;
+pub(crate) mod fallback;
+pub(crate) mod retry;
+pub(crate) mod shift;
+pub mod storage;
 pub(crate) mod vr;
 
 pub(crate) struct Providers {
@@ -42,7 +49,7 @@ impl Providers {
             ProviderConfig::Vr => {
                 let providers = self.providers.clone();
                 //wasm_bindgen_futures::spawn_local(async move {
-                    match vr::VrWindProvider::new().await {
+                    match vr::VrWindProvider::new(retry::Retry::default()).await {
                         Ok(vr) => {
                             vr.start();
 
@@ -55,11 +62,37 @@ impl Providers {
                     }
                 //});
             }
+            ProviderConfig::Constant { direction, speed } => {
+                let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<dyn Provider + Sync + Send>>> = self.providers.write().unwrap();
+                providers.insert("constant".into(), Arc::new(constant::ConstantWindProvider::new(*direction, speed.clone())));
+            }
+            ProviderConfig::LinearShift { base_dir, deg_per_hour, speed, start } => {
+                let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<dyn Provider + Sync + Send>>> = self.providers.write().unwrap();
+                providers.insert("linear_shift".into(), Arc::new(shift::LinearShiftWindProvider::new(*base_dir, *deg_per_hour, speed.clone(), *start)));
+            }
+            ProviderConfig::Fallback { primary, secondary } => {
+                let fallback = fallback::FallbackWindProvider::new(self.get(primary.clone())?, self.get(secondary.clone())?);
+
+                let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<dyn Provider + Sync + Send>>> = self.providers.write().unwrap();
+                providers.insert("fallback".into(), Arc::new(fallback));
+            }
+            ProviderConfig::Blend { a, b, weight } => {
+                let blend = blend::BlendWindProvider::new(self.get(a.clone())?, self.get(b.clone())?, *weight);
+
+                let mut providers: std::sync::RwLockWriteGuard<HashMap<String, Arc<dyn Provider + Sync + Send>>> = self.providers.write().unwrap();
+                providers.insert("blend".into(), Arc::new(blend));
+            }
         }
 
         Ok(())
     }
 
+    /// Registers `provider` under `name`, replacing any provider already there.
+    #[cfg(test)]
+    pub(crate) fn insert(&self, name: String, provider: Arc<dyn Provider + Sync + Send>) {
+        self.providers.write().unwrap().insert(name, provider);
+    }
+
     pub(crate) fn get(&self, provider: String) -> ProviderResult {
         let providers: std::sync::RwLockReadGuard<HashMap<String, Arc<dyn Provider + Sync + Send>>> = self.providers.read().unwrap();
 
@@ -69,7 +102,7 @@ impl Providers {
                 Ok(p)
             },
             None => {
-                bail!("Provider not found")
+                bail!(PhtError::ProviderNotFound(provider))
             },
         }
     }
@@ -82,7 +115,7 @@ impl Providers {
                 Ok(provider.find(&m).interpolate(&point))
             },
             None => {
-                bail!("Provider not found")
+                bail!(PhtError::ProviderNotFound(provider))
             },
         }
     }
@@ -97,7 +130,7 @@ impl Providers {
                 Ok(provider.status())
             },
             None => {
-                bail!("Provider not found")
+                bail!(PhtError::ProviderNotFound(provider))
             },
         }
     }
@@ -115,7 +148,7 @@ impl Providers {
                 provider.find(&m).draw(x, y, z, width, height, f)
             },
             None => {
-                bail!("Provider not found")
+                bail!(PhtError::ProviderNotFound(provider))
             },
         }
     }
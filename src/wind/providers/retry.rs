@@ -0,0 +1,58 @@
+use anyhow::{bail, Result};
+use log::warn;
+use reqwest::{Client, Response, Url};
+
+/// Bounded retry of downloads, waiting `base_delay`, then twice as long, and so on between attempts.
+#[derive(Clone, Debug)]
+pub(crate) struct Retry {
+    pub(crate) attempts: u32,
+    pub(crate) base_delay: std::time::Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl Retry {
+    /// GET `url`, retrying on transport errors and on 5xx / 429 responses.
+    pub(crate) async fn get(&self, client: &Client, url: Url) -> Result<Response> {
+        let mut delay = self.base_delay;
+
+        for attempt in 1..=self.attempts.max(1) {
+            let error = match client.get(url.clone()).send().await {
+                Ok(response) if response.status().is_server_error() || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    format!("status {}", response.status())
+                }
+                Ok(response) => return Ok(response),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt == self.attempts.max(1) {
+                bail!("Error downloading {} after {} attempts : {}", url, attempt, error);
+            }
+
+            warn!("Attempt {}/{} downloading {} failed ({}), retrying in {:?}", attempt, self.attempts, url, error, delay);
+            sleep(delay).await;
+            delay *= 2;
+        }
+
+        unreachable!()
+    }
+}
+
+/// Waits on the runtime timer, retrying at once when built without one rather than blocking the executor.
+async fn sleep(delay: std::time::Duration) {
+    #[cfg(feature = "wasm")]
+    gloo::timers::future::sleep(delay).await;
+
+    #[cfg(all(not(feature = "wasm"), feature = "tokio"))]
+    tokio::time::sleep(delay).await;
+
+    #[cfg(all(not(feature = "wasm"), not(feature = "tokio")))]
+    let _ = delay;
+}
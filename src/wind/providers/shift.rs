@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+
+use crate::utils::Speed;
+use crate::wind::{InstantWind, Provider, ProviderStatus};
+
+use super::constant::ConstantWindProvider;
+
+/// Same wind everywhere, its direction rotating by `deg_per_hour` from `base_dir` at `start`.
+#[derive(Clone, Debug)]
+pub(crate) struct LinearShiftWindProvider {
+    pub(crate) base_dir: f64,
+    pub(crate) deg_per_hour: f64,
+    pub(crate) speed: Speed,
+    pub(crate) start: DateTime<Utc>,
+}
+
+impl LinearShiftWindProvider {
+    pub(crate) fn new(base_dir: f64, deg_per_hour: f64, speed: Speed, start: DateTime<Utc>) -> Self {
+        Self { base_dir, deg_per_hour, speed, start }
+    }
+
+    pub(crate) fn direction(&self, m: &DateTime<Utc>) -> f64 {
+        let hours = (*m - self.start).num_seconds() as f64 / 3600.0;
+
+        (self.base_dir + self.deg_per_hour * hours).rem_euclid(360.0)
+    }
+}
+
+impl Provider for LinearShiftWindProvider {
+    fn start(&self) {}
+
+    fn status(&self) -> ProviderStatus {
+        ProviderStatus {
+            current_ref_time: self.start,
+            last: None,
+            progress: 100,
+            forecasts: Default::default(),
+            available_range: self.available_range(),
+            stale: false,
+        }
+    }
+
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC))
+    }
+
+    fn find(&self, m: &DateTime<Utc>) -> Box<dyn InstantWind + Send + Sync> {
+        Box::new(ConstantWindProvider::new(self.direction(m), self.speed.clone()))
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-// pub(crate) mod web_sys;
+#[cfg(feature = "wasm")]
+pub mod web_sys;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -23,7 +24,9 @@ pub enum StorageConfig {
 
 pub trait Storage {
 
-    async fn save(&self, name: String) -> Result<()>;
+    async fn save(&self, name: String, content: &[u8]) -> Result<()>;
+
+    async fn read(&self, name: String) -> Result<Vec<u8>>;
     
     async fn remove(&self, name: String) -> Result<()>;
 
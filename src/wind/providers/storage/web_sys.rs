@@ -1,47 +1,109 @@
-use anyhow::bail;
-use web_sys::FileSystemGetFileOptions;
+use anyhow::{bail, Result};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{js_sys::Uint8Array, DomException, File, FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetFileOptions, FileSystemWritableFileStream};
 
 use super::Storage;
 
-pub(crate) struct LocalStorage {
-    pub(crate) prefix: String
+/// Origin private file system storage, files being named `{prefix}{name}`.
+pub struct LocalStorage {
+    pub prefix: String
 }
 
-impl Storage for LocalStorage {
+impl LocalStorage {
+
+    fn path(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
 
-    async fn save(&self, name: String) -> anyhow::Result<()> {
+    async fn root(&self) -> Result<FileSystemDirectoryHandle> {
 
         let navigator = web_sys::window().unwrap().navigator();
 
-        let handle = match wasm_bindgen_futures::JsFuture::from(navigator.storage().get_directory()).await {
-            Ok(handle) => {
-                web_sys::FileSystemDirectoryHandle::from(handle)
-            }
-            Err(_e) => {
-                bail!("Fail getting root directory handler")
-            }
+        match JsFuture::from(navigator.storage().get_directory()).await {
+            Ok(handle) => Ok(FileSystemDirectoryHandle::from(handle)),
+            Err(e) => bail!("Fail getting root directory handler : {:?}", e),
+        }
+    }
+
+    /// File handle, `None` if the file does not exist and `create` is not set.
+    async fn file_handle(&self, name: &str, create: bool) -> Result<Option<FileSystemFileHandle>> {
+
+        let root = self.root().await?;
+
+        match JsFuture::from(root.get_file_handle_with_options(&self.path(name), FileSystemGetFileOptions::new().create(create))).await {
+            Ok(handle) => Ok(Some(FileSystemFileHandle::from(handle))),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => bail!("Fail getting file handler {} : {:?}", name, e),
+        }
+    }
+}
+
+fn is_not_found(e: &wasm_bindgen::JsValue) -> bool {
+    e.dyn_ref::<DomException>().is_some_and(|e| e.name() == "NotFoundError")
+}
+
+impl Storage for LocalStorage {
+
+    async fn save(&self, name: String, content: &[u8]) -> Result<()> {
+
+        let Some(handle) = self.file_handle(&name, true).await? else {
+            bail!("Fail creating file {}", name)
+        };
+
+        let stream = match JsFuture::from(handle.create_writable()).await {
+            Ok(stream) => FileSystemWritableFileStream::from(stream),
+            Err(e) => bail!("Fail opening file {} for writing : {:?}", name, e),
         };
 
-        let _handle = match wasm_bindgen_futures::JsFuture::from(handle.get_file_handle_with_options(&name, FileSystemGetFileOptions::new().create(true))).await {
-            Ok(handle) => {
-                web_sys::FileSystemFileHandle::from(handle)
-            }
-            Err(_e) => {
-                bail!("Fail getting file handler")
-            }
+        let written = match stream.write_with_u8_array(content) {
+            Ok(promise) => JsFuture::from(promise).await,
+            Err(e) => Err(e),
         };
 
+        // close even on failure to release the file lock, the content being committed on close only
+        let closed = JsFuture::from(stream.close()).await;
 
+        if let Err(e) = written {
+            bail!("Fail writing file {} : {:?}", name, e);
+        }
+        if let Err(e) = closed {
+            bail!("Fail closing file {} : {:?}", name, e);
+        }
 
         Ok(())
     }
 
-    async fn remove(&self, _name: String) -> anyhow::Result<()> {
-        todo!()
+    async fn read(&self, name: String) -> Result<Vec<u8>> {
+
+        let Some(handle) = self.file_handle(&name, false).await? else {
+            bail!("File {} not found", name)
+        };
+
+        let file = match JsFuture::from(handle.get_file()).await {
+            Ok(file) => File::from(file),
+            Err(e) => bail!("Fail getting file {} : {:?}", name, e),
+        };
+
+        match JsFuture::from(file.array_buffer()).await {
+            Ok(buffer) => Ok(Uint8Array::new(&buffer).to_vec()),
+            Err(e) => bail!("Fail reading file {} : {:?}", name, e),
+        }
+    }
+
+    async fn remove(&self, name: String) -> Result<()> {
+
+        let root = self.root().await?;
+
+        match JsFuture::from(root.remove_entry(&self.path(&name))).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_not_found(&e) => Ok(()),
+            Err(e) => bail!("Fail removing file {} : {:?}", name, e),
+        }
     }
 
-    async fn exists(&self, _name: String) -> anyhow::Result<bool> {
-        
-        Ok(false)
+    async fn exists(&self, name: String) -> Result<bool> {
+
+        Ok(self.file_handle(&name, false).await?.is_some())
     }
-}
\ No newline at end of file
+}
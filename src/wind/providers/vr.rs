@@ -8,23 +8,27 @@ use anyhow::{bail, Result};
 use byteorder::ReadBytesExt;
 use chrono::{DateTime, Duration, DurationRound, Utc};
 use chrono::serde::ts_seconds;
-use clokwerk::{AsyncScheduler, Job, TimeUnits};
 use futures_util::future::ready;
 #[cfg(feature = "wasm")]
 use gloo::timers::callback::Interval;
 use log::{debug, error};
 use reqwest::Url;
 use serde::Deserialize;
-#[cfg(feature = "tokio")]
-use tokio::task::spawn_local;
 use futures_util::stream::StreamExt;
 
-use crate::wind::ProviderStatus;
+use crate::wind::{ProviderStatus, STALE_AFTER_HOURS};
+use super::cache;
+use super::retry::Retry;
 use crate::{position::Coords, utils::Speed, wind::{vector_to_degrees, InstantWind, Provider, Wind}};
 
 #[derive(Debug)]
 pub(crate) struct VrWindProvider {
     references: Arc<Mutex<References>>,
+    retry: Retry,
+    /// Delay between two reloads of the references index.
+    refresh_interval: Duration,
+    /// Where the references index is reloaded from.
+    index_url: Url,
 }
 
 unsafe impl Send for VrWindProvider {}
@@ -35,13 +39,17 @@ impl Provider for VrWindProvider {
         debug!("Start vr VrWindProvider");
 
         let references = self.references.clone();
+        let retry = self.retry.clone();
+        let index_url = self.index_url.clone();
 
         #[cfg(feature = "wasm")]
         {
-            let interval = Interval::new(10*60*1_000, move || {
+            let interval = Interval::new(self.refresh_interval.num_milliseconds() as u32, move || {
                 let references = references.clone();
+                let retry = retry.clone();
+                let index_url = index_url.clone();
                 wasm_bindgen_futures::spawn_local(async move {
-                    Self::init(references).await;
+                    Self::init(references, &retry, &index_url).await;
                 });
             });
 
@@ -56,23 +64,48 @@ impl Provider for VrWindProvider {
             // });
         }
 
-        #[cfg(not(feature = "wasm"))]
+        // without a runtime to run the reloads on, the references stay the ones loaded at creation
+        #[cfg(all(not(feature = "wasm"), feature = "tokio"))]
         {
-            let mut scheduler = AsyncScheduler::new();
+            let period = self.refresh_interval.to_std().unwrap_or(std::time::Duration::from_secs(600));
 
-            scheduler.every(10.minutes()).plus(1.minutes())
-                .run(move || {
-                    let references = references.clone();
-                    async move {
-                        Self::init(references);
-                    }
-                });
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                loop {
+                    interval.tick().await;
+                    Self::init(references.clone(), &retry, &index_url).await;
+                }
+            });
         }
 
+        #[cfg(all(not(feature = "wasm"), not(feature = "tokio")))]
+        let _ = (references, retry, index_url);
+
     }
 
+    fn refresh_now(&self) {
+        debug!("Refresh VrWindProvider");
+
+        let references = self.references.clone();
+        let retry = self.retry.clone();
+        let index_url = self.index_url.clone();
+
+        #[cfg(feature = "wasm")]
+        wasm_bindgen_futures::spawn_local(async move {
+            Self::init(references, &retry, &index_url).await;
+        });
+
+        #[cfg(all(not(feature = "wasm"), feature = "tokio"))]
+        tokio::spawn(async move {
+            Self::init(references, &retry, &index_url).await;
+        });
+
+        #[cfg(all(not(feature = "wasm"), not(feature = "tokio")))]
+        let _ = (references, retry, index_url);
+    }
 
     fn status(&self) -> ProviderStatus {
+        let stale = self.is_stale(&Utc::now(), &Duration::hours(STALE_AFTER_HOURS));
         let references: std::sync::MutexGuard<References> = self.references.lock().unwrap();
 
         ProviderStatus {
@@ -83,11 +116,25 @@ impl Provider for VrWindProvider {
                 let refs = references.iter().map(|r| r.valid - Duration::hours(r.delta_ref as i64)).collect::<Vec<_>>();
                 (references[0].valid, refs)
             }).collect(),
+            available_range: references.available_range(),
+            stale,
+        }
+    }
+
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.references.lock().unwrap().available_range()
+    }
+
+    fn data_age(&self, now: &DateTime<Utc>) -> Duration {
+        match self.references.lock().unwrap().newest_avail() {
+            Some(avail) => now.signed_duration_since(avail),
+            None => Duration::max_value(),
         }
     }
 
     fn find(&self, m: &chrono::prelude::DateTime<chrono::prelude::Utc>) -> Box<dyn InstantWind + Send + Sync> {
         let m = m.add(Duration::minutes(-1)).duration_trunc(Duration::minutes(10)).expect("datetime rounded");
+        let gust_factor = self.gust_factor();
 
         let references = self.references.lock().unwrap();
 
@@ -100,7 +147,7 @@ impl Provider for VrWindProvider {
                         let w1: Vec<Reference> = refs.iter().map_while(|s| {
                             Some(s.clone())
                         }).collect();
-                        return Box::new(VrInstantWind { w1, w2: None, h: 0.0 });
+                        return Box::new(VrInstantWind { w1, w2: None, h: 0.0, gust_factor });
                     }
                     Some(previous_refs) => {
                         let previous_ref = &previous_refs[0];
@@ -110,12 +157,12 @@ impl Provider for VrWindProvider {
                             Some(s.clone())
                         }).collect();
                         if h == 0 {
-                            return Box::new(VrInstantWind { w1, w2: None, h: 0.0 });
+                            return Box::new(VrInstantWind { w1, w2: None, h: 0.0, gust_factor });
                         }
                         let w2: Vec<Reference> = refs.iter().map_while(|s| {
                             Some(s.clone())
                         }).collect();
-                        return Box::new(VrInstantWind { w1, w2: Some(w2), h: h as f64 / delta as f64 });
+                        return Box::new(VrInstantWind { w1, w2: Some(w2), h: h as f64 / delta as f64, gust_factor });
                     }
                 }
             }
@@ -128,54 +175,119 @@ impl Provider for VrWindProvider {
             Some(s.clone())
         }).collect();
 
-        Box::new(VrInstantWind { w1, w2: None, h: 0.0 })
+        Box::new(VrInstantWind { w1, w2: None, h: 0.0, gust_factor })
     }
 
 }
 
 impl VrWindProvider {
 
-    pub(crate) async fn new() -> Result<Self> {
+    pub(crate) async fn new(retry: Retry) -> Result<Self> {
+        Self::from_index_url(retry, Self::live_index_url()).await
+    }
+
+    /// Provider over the references index at `index_url`, or over the cached one if it can't be loaded.
+    ///
+    /// The cached data of the references no longer listed is dropped.
+    pub(crate) async fn from_index_url(retry: Retry, index_url: Url) -> Result<Self> {
         debug!("Create VrWindProvider");
 
-        let references = match Self::load().await {
-            Ok(mut references) => {
-                for reference in references.references.iter_mut() {
-                    for r in reference {
-                        match r.load().await {
-                            Ok(_) => {}
-                            Err(e) => {
-                                bail!("Error loading reference data : {}", e);
-                            }
-                        }
-                    }
+        let references = match Self::load(&retry, &index_url).await {
+            Ok(references) => references,
+            Err(e) => match Self::cached().await {
+                Some(references) => {
+                    error!("Error loading winds references, using cached ones : {}", e);
+                    references
+                }
+                None => {
+                    bail!("Error loading winds references : {}", e);
                 }
-
-                Arc::new(Mutex::new(references))
             },
-            Err(e) => {
-                bail!("Error loading winds references : {}", e);
-            }
         };
 
+        let vr = Self::with_references(references, retry).await?.with_index_url(index_url);
+
+        let names = vr.references.lock().unwrap().names();
+        cache::retain_references(&names).await;
+
+        Ok(vr)
+    }
+
+    /// Provider over `references`, loading their data from the cache or else downloading it.
+    pub(crate) async fn with_references(references: References, retry: Retry) -> Result<Self> {
+        for reference in references.references.iter() {
+            for r in reference {
+                match r.load(&retry).await {
+                    Ok(_) => {}
+                    Err(e) => {
+                        bail!("Error loading reference data : {}", e);
+                    }
+                }
+            }
+        }
+
         Ok(Self {
-            references,
+            references: Arc::new(Mutex::new(references)),
+            retry,
+            refresh_interval: Duration::minutes(10),
+            index_url: Self::live_index_url(),
         })
     }
 
-    async fn load() -> Result<References> {
+    /// Reloads the references index every `refresh_interval` once started.
+    pub(crate) fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Reloads the references index from `index_url` instead of the live one.
+    pub(crate) fn with_index_url(mut self, index_url: Url) -> Self {
+        self.index_url = index_url;
+        self
+    }
+
+    fn live_index_url() -> Url {
+        Url::parse("https://static.virtualregatta.com/winds/live/references.json").expect("valid url")
+    }
+
+    /// Last references index cached by `load`.
+    pub(crate) async fn cached() -> Option<References> {
+        let index = cache::get_index().await?;
+
+        match serde_json::from_str(&index) {
+            Ok(references) => Some(references),
+            Err(e) => {
+                error!("Error parsing cached winds references : {}", e);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn from_references(references: References) -> Self {
+        Self {
+            references: Arc::new(Mutex::new(references)),
+            retry: Retry::default(),
+            refresh_interval: Duration::minutes(10),
+            index_url: Self::live_index_url(),
+        }
+    }
+
+    async fn load(retry: &Retry, url: &Url) -> Result<References> {
         debug!("Load Vr Wind References");
 
         let client = reqwest::Client::new();
-        let url = Url::parse("https://static.virtualregatta.com")?.join("winds/live/references.json")?;
 
-        let response = client.get(url.clone())
-            .send()
-            .await?;
+        let response = retry.get(&client, url.clone()).await?;
 
         match response.status() {
             reqwest::StatusCode::OK => {
-                let references = response.json::<References>().await?;
+                let index = response.text().await?;
+                let references = serde_json::from_str::<References>(&index)?;
+
+                let cached = Self::cached().await;
+                if cached.is_none_or(|cached| cached.export != references.export || cached.publish != references.publish) {
+                    cache::put_index(&index).await;
+                }
 
                 Ok(references)
             }
@@ -185,8 +297,8 @@ impl VrWindProvider {
         }
     }
 
-    async fn init(references: Arc<Mutex<References>>) {
-        match Self::load().await {
+    async fn init(references: Arc<Mutex<References>>, retry: &Retry, index_url: &Url) {
+        match Self::load(retry, index_url).await {
             Ok(mut refs) => {
                 let mut errors = false;
 
@@ -199,7 +311,7 @@ impl VrWindProvider {
                             found
                         };
                         if !found {
-                            match r.load().await {
+                            match r.load(retry).await {
                                 Ok(_) => {}
                                 Err(e) => {
                                     errors = true;
@@ -211,8 +323,12 @@ impl VrWindProvider {
                 }
 
                 if !errors {
-                    let mut references = references.lock().unwrap();
-                    *references = refs;
+                    let names = refs.names();
+                    {
+                        let mut references = references.lock().unwrap();
+                        *references = refs;
+                    }
+                    cache::retain_references(&names).await;
                 }
             },
             Err(e) => {
@@ -227,6 +343,7 @@ pub(crate) struct VrInstantWind {
     w1: Vec<Reference>,
     w2: Option<Vec<Reference>>,
     h: f64,
+    gust_factor: f64,
 }
 
 impl Display for VrInstantWind {
@@ -344,13 +461,14 @@ impl InstantWind for VrInstantWind {
 
         Wind {
             direction: vector_to_degrees(u, v),
-            speed: d
+            gust: Some(d.clone() * self.gust_factor),
+            speed: d,
         }
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct References {
+pub(crate) struct References {
     #[serde(rename="export_ts", with = "ts_seconds")]
     export: DateTime<Utc>,
     #[serde(rename="publish_ts", with = "ts_seconds")]
@@ -361,6 +479,23 @@ struct References {
 }
 
 impl References {
+    /// References are only swapped in once all their data is loaded, see `VrWindProvider::init`.
+    fn available_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let first = self.references.first()?.first()?.valid;
+        let last = self.references.last()?.first()?.valid;
+
+        Some((first, last))
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.references.iter().flatten().map(|r| r.reference.clone()).collect()
+    }
+
+    /// When the newest run among the loaded references was made available.
+    fn newest_avail(&self) -> Option<DateTime<Utc>> {
+        self.references.iter().flatten().map(|r| r.avail).max()
+    }
+
     fn move_data(&mut self, reference: &String) -> (Arc<Mutex<Option<Box<[[(f64,f64);360];181]>>>>, bool) {
         for r in self.references.iter_mut() {
             for r in r.iter() {
@@ -403,7 +538,7 @@ impl Debug for Reference {
 }
 
 impl Reference {
-     async fn load(&self) -> Result<()> {
+     async fn load(&self, retry: &Retry) -> Result<()> {
         debug!("Load reference : {:?}", self);
 
         {
@@ -412,6 +547,14 @@ impl Reference {
             }
         }
 
+        if let Some(buffer) = cache::get_reference(&self.reference).await.and_then(|data| Self::unflatten(&data)) {
+            debug!("Reference {} found in cache", self.reference);
+            let mut data = self.data.lock().unwrap();
+            *data = Some(buffer);
+
+            return Ok(())
+        }
+
         let lat_0: i32 = -90;
         let lon_0 = -180;
 
@@ -420,7 +563,7 @@ impl Reference {
 
         debug!("Download from url {}", url);
 
-        let response = match client.get(url).send().await {
+        let response = match retry.get(&client, url).await {
             Ok(response) => response,
             Err(e) => {
                 bail!("Error downloading file : {}", e);
@@ -449,9 +592,28 @@ impl Reference {
             }
         }
 
+        cache::put_reference(&self.reference, &Self::flatten(&buffer)).await;
+
         let mut data = self.data.lock().unwrap();
         *data = Some(buffer);
 
         Ok(())
     }
+
+    fn flatten(buffer: &[[(f64, f64); 360]; 181]) -> Vec<f64> {
+        buffer.iter().flat_map(|row| row.iter().flat_map(|(u, v)| [*u, *v])).collect()
+    }
+
+    fn unflatten(data: &[f64]) -> Option<Box<[[(f64, f64); 360]; 181]>> {
+        if data.len() != 181 * 360 * 2 {
+            return None
+        }
+
+        let mut buffer: Box<[[(f64, f64); 360]; 181]> = vec![[(0.0,0.0);360];181].try_into().unwrap();
+        for (i, uv) in data.chunks_exact(2).enumerate() {
+            buffer[i / 360][i % 360] = (uv[0], uv[1]);
+        }
+
+        Some(buffer)
+    }
 }
\ No newline at end of file
@@ -27,7 +27,7 @@ async fn vr() {
 
     debug!("Testing VrWindProvider ...");
 
-    let vr = match VrWindProvider::new().await {
+    let vr = match VrWindProvider::new(Default::default()).await {
         Ok(vr) => vr,
         Err(e) => panic!("Error building VrWindProvider : {}", e)
     };
@@ -36,3 +36,166 @@ async fn vr() {
 
     vr.start();
 }
+
+#[wasm_bindgen_test]
+async fn vr_cached() {
+    use crate::position::Coords;
+    use crate::wind::providers::{cache, vr::References};
+
+    let index = r#"{
+        "export_ts": 1700000000, "publish_ts": 1700000000, "start_ts": 1700000000,
+        "references": [[{"reference": "cached", "valid_ts": 1700000000, "delta_ref": 0, "delta": 0, "avail_ts": 1700000000, "rel_path": "missing/cached.wnd"}]]
+    }"#;
+
+    // same 20 km/h wind everywhere
+    let data = (0..181 * 360).flat_map(|_| [0.0, -20.0]).collect::<Vec<f64>>();
+
+    cache::put_index(index).await;
+    cache::put_reference("cached", &data).await;
+
+    // as after a reload : nothing in memory, `rel_path` can't be downloaded
+    let references: References = VrWindProvider::cached().await.expect("cached index");
+    let vr = VrWindProvider::with_references(references, Default::default()).await.expect("cached data");
+
+    let wind = vr.find(&chrono::Utc::now()).interpolate(&Coords { lat: 46.0, lon: -5.0 });
+    assert_eq!(wind.speed.km_h(), 20.0);
+}
+
+#[wasm_bindgen_test]
+async fn vr_offline_reload() {
+    use crate::position::Coords;
+    use crate::wind::providers::{cache, retry::Retry};
+
+    let index = r#"{
+        "export_ts": 1700000000, "publish_ts": 1700000000, "start_ts": 1700000000,
+        "references": [[{"reference": "kept", "valid_ts": 1700000000, "delta_ref": 0, "delta": 0, "avail_ts": 1700000000, "rel_path": "missing/kept.wnd"}]]
+    }"#;
+    let data = (0..181 * 360).flat_map(|_| [0.0, -20.0]).collect::<Vec<f64>>();
+
+    cache::put_index(index).await;
+    cache::put_reference("kept", &data).await;
+    cache::put_reference("dropped", &data).await;
+
+    // reloaded twice with the network unavailable, from the cache only
+    let offline = reqwest::Url::parse("http://127.0.0.1:9/references.json").expect("url");
+    let retry = Retry { attempts: 1, base_delay: std::time::Duration::ZERO };
+    for _ in 0..2 {
+        let vr = VrWindProvider::from_index_url(retry.clone(), offline.clone()).await.expect("cached winds");
+
+        let wind = vr.find(&chrono::Utc::now()).interpolate(&Coords { lat: 46.0, lon: -5.0 });
+        assert_eq!(wind.speed.km_h(), 20.0);
+    }
+
+    // only the references of the index are kept
+    assert!(cache::get_reference("kept").await.is_some());
+    assert!(cache::get_reference("dropped").await.is_none());
+}
+
+#[wasm_bindgen_test]
+async fn vr_gust() {
+    use crate::position::Coords;
+    use crate::wind::providers::{cache, vr::References};
+
+    let index = r#"{
+        "export_ts": 1700000000, "publish_ts": 1700000000, "start_ts": 1700000000,
+        "references": [[{"reference": "gust", "valid_ts": 1700000000, "delta_ref": 0, "delta": 0, "avail_ts": 1700000000, "rel_path": "missing/gust.wnd"}]]
+    }"#;
+    let data = (0..181 * 360).flat_map(|_| [12.0, 16.0]).collect::<Vec<f64>>();
+
+    cache::put_index(index).await;
+    cache::put_reference("gust", &data).await;
+
+    let references: References = VrWindProvider::cached().await.expect("cached index");
+    let vr = VrWindProvider::with_references(references, Default::default()).await.expect("cached data");
+
+    let wind = vr.find(&chrono::Utc::now()).interpolate(&Coords { lat: 46.0, lon: -5.0 });
+    let gust = wind.gust.expect("gust");
+    assert!((gust.kts() - 1.3 * wind.speed.kts()).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+async fn vr_refresh_now() {
+    use web_sys::js_sys::Array;
+    use crate::wind::providers::{cache, vr::References};
+
+    let index = r#"{
+        "export_ts": 1700000000, "publish_ts": 1700000000, "start_ts": 1700000000,
+        "references": [[{"reference": "stale", "valid_ts": 1700000000, "delta_ref": 0, "delta": 0, "avail_ts": 1700000000, "rel_path": "missing/stale.wnd"}]]
+    }"#;
+    let fresh = r#"{
+        "export_ts": 1700003600, "publish_ts": 1700003600, "start_ts": 1700003600,
+        "references": [[{"reference": "fresh", "valid_ts": 1700003600, "delta_ref": 0, "delta": 0, "avail_ts": 1700003600, "rel_path": "missing/fresh.wnd"}]]
+    }"#;
+    let data = (0..181 * 360).flat_map(|_| [0.0, -20.0]).collect::<Vec<f64>>();
+
+    cache::put_index(index).await;
+    cache::put_reference("stale", &data).await;
+    cache::put_reference("fresh", &data).await;
+
+    // the refreshed index is served from a blob rather than the live one, its data from the cache
+    let blob = web_sys::Blob::new_with_str_sequence(&Array::of1(&fresh.into())).expect("blob");
+    let index_url = reqwest::Url::parse(&web_sys::Url::create_object_url_with_blob(&blob).expect("object url")).expect("blob url");
+
+    let references: References = VrWindProvider::cached().await.expect("cached index");
+    let vr = VrWindProvider::with_references(references, Default::default()).await.expect("cached data")
+        .with_index_url(index_url);
+    let stale = vr.status().current_ref_time;
+
+    vr.refresh_now();
+    for _ in 0..50 {
+        if vr.status().current_ref_time > stale {
+            break;
+        }
+        gloo::timers::future::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    assert!(vr.status().current_ref_time > stale);
+}
+
+#[wasm_bindgen_test]
+async fn vr_stale() {
+    use chrono::{Duration, TimeZone, Utc};
+    use crate::wind::providers::{cache, vr::References};
+    use crate::wind::STALE_AFTER_HOURS;
+
+    let index = r#"{
+        "export_ts": 1700000000, "publish_ts": 1700000000, "start_ts": 1700000000,
+        "references": [
+            [{"reference": "old6", "valid_ts": 1700000000, "delta_ref": 6, "delta": 0, "avail_ts": 1699978400, "rel_path": "missing/old6.wnd"}],
+            [{"reference": "old9", "valid_ts": 1700010800, "delta_ref": 9, "delta": 0, "avail_ts": 1699978400, "rel_path": "missing/old9.wnd"}]
+        ]
+    }"#;
+    let data = (0..181 * 360).flat_map(|_| [0.0, -20.0]).collect::<Vec<f64>>();
+
+    cache::put_index(index).await;
+    cache::put_reference("old6", &data).await;
+    cache::put_reference("old9", &data).await;
+
+    let references: References = VrWindProvider::cached().await.expect("cached index");
+    let vr = VrWindProvider::with_references(references, Default::default()).await.expect("cached data");
+
+    // run made available 6 hours before its first forecast, forecasts reaching 9 hours after it
+    let avail = Utc.timestamp_opt(1699978400, 0).unwrap();
+    assert_eq!(vr.data_age(&(avail + Duration::hours(3))), Duration::hours(3));
+    assert_eq!(vr.data_age(&(avail + Duration::hours(12))), Duration::hours(12));
+    assert!(!vr.is_stale(&(avail + Duration::hours(3)), &Duration::hours(STALE_AFTER_HOURS)));
+    assert!(vr.is_stale(&(avail + Duration::hours(13)), &Duration::hours(STALE_AFTER_HOURS)));
+
+    assert!(vr.status().stale);
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen_test]
+async fn local_storage() {
+    use crate::wind::providers::storage::{web_sys::LocalStorage, Storage};
+
+    let storage = LocalStorage { prefix: "test_".into() };
+    let name = "stamp.grib".to_string();
+
+    storage.save(name.clone(), &[1, 2, 3, 4]).await.expect("save");
+    assert!(storage.exists(name.clone()).await.expect("exists"));
+    assert_eq!(storage.read(name.clone()).await.expect("read"), vec![1, 2, 3, 4]);
+
+    storage.remove(name.clone()).await.expect("remove");
+    assert!(!storage.exists(name).await.expect("exists"));
+}
@@ -0,0 +1,50 @@
+//! Allocation counts, kept out of the unit tests as the counting allocator is global to the test binary.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use phtheirichthys::bench;
+
+/// System allocator counting the allocations made by each thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Runs `f`, returning its result and the number of allocations it made on the current thread.
+fn count<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(|count| count.get());
+    let result = f();
+    let after = ALLOCATIONS.with(|count| count.get());
+
+    (result, after - before)
+}
+
+#[test]
+fn merging_alternatives_does_not_allocate() {
+    let merge = bench::merging_alternatives(500);
+    let (best, allocations) = count(merge);
+
+    assert_eq!(allocations, 0);
+    assert_eq!(best, 49.0);
+}